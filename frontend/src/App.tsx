@@ -1,6 +1,6 @@
 import { useState, useEffect, useCallback } from 'react';
 import type { Event, EventTypeSummary, Classification, Filters, Rule, AuthStatus } from './types';
-import { fetchEvents, fetchEventTypes, fetchStats, fetchRules, fetchEventCount, fetchAuthStatus } from './api';
+import { fetchEvent, fetchEvents, fetchEventTypes, fetchStats, fetchRules, fetchEventCount, fetchAuthStatus } from './api';
 import { Sidebar } from './components/Sidebar';
 import { FilterBar } from './components/FilterBar';
 import { EventList } from './components/EventList';
@@ -11,6 +11,17 @@ import './App.css';
 
 const EVENTS_PER_PAGE = 200;
 
+/** Event ID from a notification link (`/events/<id>`), if the page is one */
+function linkedEventId(pathname: string): string | null {
+  const match = pathname.match(/^\/events\/([^/]+)\/?$/);
+  if (!match) return null;
+  try {
+    return decodeURIComponent(match[1]);
+  } catch {
+    return null;
+  }
+}
+
 function App() {
   // Theme
   const { theme, toggleTheme } = useTheme();
@@ -23,6 +34,8 @@ function App() {
   const [eventTypes, setEventTypes] = useState<EventTypeSummary[]>([]);
   const [rules, setRules] = useState<Rule[]>([]);
   const [stats, setStats] = useState({ total: 0, unclassified: 0, filtered: 0 });
+  const [focusedEventId, setFocusedEventId] = useState(() => linkedEventId(window.location.pathname));
+  const [focusedEvent, setFocusedEvent] = useState<Event | null>(null);
 
   // Filters
   const [filters, setFilters] = useState<Filters>({
@@ -129,6 +142,22 @@ function App() {
     init();
   }, [authStatus?.authenticated]); // eslint-disable-line react-hooks/exhaustive-deps
 
+  // Open the event a notification linked to
+  useEffect(() => {
+    if (!authStatus?.authenticated || !focusedEventId) return;
+
+    fetchEvent(focusedEventId)
+      .then(setFocusedEvent)
+      .catch((err) => console.error('Failed to load linked event:', err));
+  }, [authStatus?.authenticated, focusedEventId]);
+
+  // Back to the plain event list
+  const handleClearFocus = useCallback(() => {
+    setFocusedEventId(null);
+    setFocusedEvent(null);
+    window.history.replaceState(null, '', '/');
+  }, []);
+
   // Reload when filters change (except initial load)
   const reloadWithFilters = useCallback(async () => {
     setIsLoading(true);
@@ -281,6 +310,8 @@ function App() {
 
           <EventList
             events={events}
+            focusedEvent={focusedEvent}
+            onClearFocus={handleClearFocus}
             isLoading={isLoading}
             isLoadingMore={isLoadingMore}
             hasMore={hasMore}
@@ -1,4 +1,4 @@
-import type { Event, EventTypeSummary, Rule, Stats, Classification, Filters, AuthStatus, PasskeyInfo, InviteToken, NotificationLogEntry, NotificationStatus, TestNotificationResult } from './types';
+import type { BulkEventAction, Event, EventTypeSummary, Rule, Stats, Classification, Filters, AuthStatus, PasskeyInfo, InviteToken, NotificationLogEntry, NotificationStatus, TestNotificationResult, BackendTestResult, NotificationBackend } from './types';
 
 const API_BASE = '/api';
 
@@ -17,6 +17,10 @@ function buildEventsQuery(filters: Filters, limit: number, offset: number): stri
     params.set('search', filters.search.trim());
   }
 
+  if (filters.summarySearch?.trim()) {
+    params.set('summary_search', filters.summarySearch.trim());
+  }
+
   params.set('limit', String(limit));
   params.set('offset', String(offset));
 
@@ -34,6 +38,18 @@ export async function fetchEvents(
   return res.json();
 }
 
+export async function fetchActionableEvents(limit: number = 200, offset: number = 0): Promise<Event[]> {
+  const res = await fetch(`${API_BASE}/events/actionable?limit=${limit}&offset=${offset}`);
+  if (!res.ok) throw new Error(`Failed to fetch actionable events: ${res.status}`);
+  return res.json();
+}
+
+export async function fetchEvent(eventId: string): Promise<Event> {
+  const res = await fetch(`${API_BASE}/events/${encodeURIComponent(eventId)}`);
+  if (!res.ok) throw new Error(`Failed to fetch event: ${res.status}`);
+  return res.json();
+}
+
 export async function fetchEventPayload(eventId: string): Promise<unknown> {
   const res = await fetch(`${API_BASE}/events/${encodeURIComponent(eventId)}/payload`);
   if (!res.ok) throw new Error(`Failed to fetch payload: ${res.status}`);
@@ -41,6 +57,39 @@ export async function fetchEventPayload(eventId: string): Promise<unknown> {
   return data.payload;
 }
 
+export async function setEventPinned(eventId: string, pinned: boolean): Promise<void> {
+  const res = await fetch(`${API_BASE}/events/${encodeURIComponent(eventId)}/pin`, {
+    method: pinned ? 'POST' : 'DELETE',
+  });
+  if (!res.ok) throw new Error(`Failed to ${pinned ? 'pin' : 'unpin'} event: ${res.status}`);
+}
+
+/** Apply an action to every event matching the filters; returns the number affected */
+export async function bulkUpdateEvents(filters: Filters, action: BulkEventAction): Promise<number> {
+  const filter: Record<string, string> = {};
+  if (filters.classifications.size > 0) {
+    filter.classification = Array.from(filters.classifications).join(',');
+  }
+  if (filters.eventTypes.size > 0) {
+    filter.event_type = Array.from(filters.eventTypes).join(',');
+  }
+  if (filters.search.trim()) {
+    filter.search = filters.search.trim();
+  }
+  if (filters.summarySearch?.trim()) {
+    filter.summary_search = filters.summarySearch.trim();
+  }
+
+  const res = await fetch(`${API_BASE}/events/bulk`, {
+    method: 'POST',
+    headers: { 'Content-Type': 'application/json' },
+    body: JSON.stringify({ filter, ...action }),
+  });
+  if (!res.ok) throw new Error(`Failed to update events: ${res.status}`);
+  const data = await res.json();
+  return data.affected;
+}
+
 export async function fetchEventTypes(): Promise<EventTypeSummary[]> {
   const res = await fetch(`${API_BASE}/events/types`);
   if (!res.ok) throw new Error(`Failed to fetch event types: ${res.status}`);
@@ -64,16 +113,18 @@ export async function setRule(eventType: string, classification: Classification)
 }
 
 export async function deleteRule(eventType: string): Promise<void> {
-  const res = await fetch(`${API_BASE}/rules/${encodeURIComponent(eventType)}`, {
+  const res = await fetch(`${API_BASE}/rules`, {
     method: 'DELETE',
+    headers: { 'Content-Type': 'application/json' },
+    body: JSON.stringify({ event_type: eventType }),
   });
   if (!res.ok && res.status !== 404) {
     throw new Error(`Failed to delete rule: ${res.status}`);
   }
 }
 
-export async function fetchStats(): Promise<Stats> {
-  const res = await fetch(`${API_BASE}/stats`);
+export async function fetchStats(sinceStartup = false): Promise<Stats> {
+  const res = await fetch(`${API_BASE}/stats${sinceStartup ? '?since_startup=true' : ''}`);
   if (!res.ok) throw new Error(`Failed to fetch stats: ${res.status}`);
   return res.json();
 }
@@ -93,6 +144,10 @@ export async function fetchEventCount(filters: Filters): Promise<number> {
     params.set('search', filters.search.trim());
   }
 
+  if (filters.summarySearch?.trim()) {
+    params.set('summary_search', filters.summarySearch.trim());
+  }
+
   const res = await fetch(`${API_BASE}/events/count?${params.toString()}`);
   if (!res.ok) throw new Error(`Failed to fetch event count: ${res.status}`);
   const data = await res.json();
@@ -313,6 +368,13 @@ export async function fetchNotificationHistory(limit: number = 50): Promise<Noti
   return res.json();
 }
 
+export async function setNotificationsMuted(muted: boolean): Promise<void> {
+  const res = await fetch(`${API_BASE}/notifications/${muted ? 'mute' : 'unmute'}`, {
+    method: 'POST',
+  });
+  if (!res.ok) throw new Error(`Failed to ${muted ? 'mute' : 'unmute'} notifications: ${res.status}`);
+}
+
 export async function sendTestNotification(): Promise<TestNotificationResult> {
   const res = await fetch(`${API_BASE}/notifications/test`, {
     method: 'POST',
@@ -320,3 +382,17 @@ export async function sendTestNotification(): Promise<TestNotificationResult> {
   if (!res.ok) throw new Error(`Failed to send test notification: ${res.status}`);
   return res.json();
 }
+
+export async function fetchNotificationBackends(): Promise<NotificationBackend[]> {
+  const res = await fetch(`${API_BASE}/notifications/backends`);
+  if (!res.ok) throw new Error(`Failed to fetch notification backends: ${res.status}`);
+  return res.json();
+}
+
+export async function sendBackendTestNotification(backendId: string): Promise<BackendTestResult> {
+  const res = await fetch(`${API_BASE}/notifications/test/${encodeURIComponent(backendId)}`, {
+    method: 'POST',
+  });
+  if (!res.ok) throw new Error(`Failed to send test notification: ${res.status}`);
+  return res.json();
+}
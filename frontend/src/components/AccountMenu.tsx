@@ -1,6 +1,6 @@
 import { useState, useCallback } from 'react';
-import type { PasskeyInfo, InviteToken, NotificationLogEntry, NotificationStatus } from '../types';
-import { fetchPasskeys, deletePasskey, createInviteToken, logout, fetchNotificationStatus, fetchNotificationHistory, sendTestNotification } from '../api';
+import type { PasskeyInfo, InviteToken, NotificationBackend, NotificationLogEntry, NotificationStatus } from '../types';
+import { fetchPasskeys, deletePasskey, createInviteToken, logout, fetchNotificationStatus, fetchNotificationHistory, fetchNotificationBackends, sendBackendTestNotification } from '../api';
 
 interface AccountMenuProps {
   onLogout: () => void;
@@ -18,24 +18,27 @@ export function AccountMenu({ onLogout, theme, onToggleTheme }: AccountMenuProps
   // Notification state
   const [notificationStatus, setNotificationStatus] = useState<NotificationStatus | null>(null);
   const [notificationHistory, setNotificationHistory] = useState<NotificationLogEntry[]>([]);
-  const [isSendingTest, setIsSendingTest] = useState(false);
-  const [testResult, setTestResult] = useState<{ success: boolean; error?: string } | null>(null);
+  const [backends, setBackends] = useState<NotificationBackend[]>([]);
+  const [testingBackend, setTestingBackend] = useState<string | null>(null);
+  const [testResults, setTestResults] = useState<Record<string, { success: boolean; error?: string }>>({});
 
   // Load data when menu opens
   const handleToggle = useCallback(async () => {
     if (!isOpen) {
       setIsLoading(true);
       setError(null);
-      setTestResult(null);
+      setTestResults({});
       try {
-        const [passkeysData, notifStatus, notifHistory] = await Promise.all([
+        const [passkeysData, notifStatus, notifHistory, notifBackends] = await Promise.all([
           fetchPasskeys(),
           fetchNotificationStatus(),
           fetchNotificationHistory(10),
+          fetchNotificationBackends(),
         ]);
         setPasskeys(passkeysData);
         setNotificationStatus(notifStatus);
         setNotificationHistory(notifHistory);
+        setBackends(notifBackends);
       } catch (err) {
         setError('Failed to load account data');
       } finally {
@@ -83,21 +86,20 @@ export function AccountMenu({ onLogout, theme, onToggleTheme }: AccountMenuProps
     }
   }, [onLogout]);
 
-  // Send test notification
-  const handleTestNotification = useCallback(async () => {
-    setIsSendingTest(true);
-    setTestResult(null);
+  // Send test notification through one backend
+  const handleTestNotification = useCallback(async (backendId: string) => {
+    setTestingBackend(backendId);
     setError(null);
     try {
-      const result = await sendTestNotification();
-      setTestResult({ success: result.success, error: result.error || undefined });
+      const result = await sendBackendTestNotification(backendId);
+      setTestResults((prev) => ({ ...prev, [backendId]: { success: result.success, error: result.error || undefined } }));
       // Refresh history after test
       const history = await fetchNotificationHistory(10);
       setNotificationHistory(history);
     } catch (err) {
-      setTestResult({ success: false, error: 'Failed to send test notification' });
+      setTestResults((prev) => ({ ...prev, [backendId]: { success: false, error: 'Failed to send test notification' } }));
     } finally {
-      setIsSendingTest(false);
+      setTestingBackend(null);
     }
   }, []);
 
@@ -119,27 +121,32 @@ export function AccountMenu({ onLogout, theme, onToggleTheme }: AccountMenuProps
 
       {isOpen && (
         <div className="account-dropdown">
-          {/* Telegram Notifications Section */}
+          {/* Notifications Section */}
           <div className="account-section">
-            <h3>Telegram Notifications</h3>
+            <h3>Notifications</h3>
             {isLoading ? (
               <p className="account-loading">Loading...</p>
             ) : notificationStatus?.configured ? (
               <>
-                <div className="notification-test">
-                  <button
-                    className="test-notification-btn"
-                    onClick={handleTestNotification}
-                    disabled={isSendingTest}
-                  >
-                    {isSendingTest ? 'Sending...' : 'Send Test Notification'}
-                  </button>
-                  {testResult && (
-                    <span className={`test-result ${testResult.success ? 'success' : 'error'}`}>
-                      {testResult.success ? '✓ Sent!' : `✗ ${testResult.error}`}
-                    </span>
-                  )}
-                </div>
+                {backends.map((backend) => {
+                  const result = testResults[backend.id];
+                  return (
+                    <div className="notification-test" key={backend.id}>
+                      <button
+                        className="test-notification-btn"
+                        onClick={() => handleTestNotification(backend.id)}
+                        disabled={testingBackend !== null}
+                      >
+                        {testingBackend === backend.id ? 'Sending...' : `Test ${backend.id}`}
+                      </button>
+                      {result && (
+                        <span className={`test-result ${result.success ? 'success' : 'error'}`}>
+                          {result.success ? '✓ Sent!' : `✗ ${result.error}`}
+                        </span>
+                      )}
+                    </div>
+                  );
+                })}
                 {notificationHistory.length > 0 && (
                   <div className="notification-history">
                     <h4>Recent Notifications</h4>
@@ -168,7 +175,7 @@ export function AccountMenu({ onLogout, theme, onToggleTheme }: AccountMenuProps
               </>
             ) : (
               <p className="not-configured">
-                Not configured. Set TELEGRAM_BOT_TOKEN and TELEGRAM_CHAT_ID environment variables.
+                Not configured. Set TELEGRAM_BOT_TOKEN and TELEGRAM_CHAT_ID, or WEBHOOK_URL.
               </p>
             )}
           </div>
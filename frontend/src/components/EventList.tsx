@@ -4,6 +4,9 @@ import { EventRow } from './EventRow';
 
 interface EventListProps {
   events: Event[];
+  /** Event opened through an `/events/<id>` link, shown above the list */
+  focusedEvent?: Event | null;
+  onClearFocus?: () => void;
   isLoading: boolean;
   isLoadingMore: boolean;
   hasMore: boolean;
@@ -13,6 +16,8 @@ interface EventListProps {
 
 export function EventList({
   events,
+  focusedEvent,
+  onClearFocus,
   isLoading,
   isLoadingMore,
   hasMore,
@@ -64,9 +69,22 @@ export function EventList({
         <button className="refresh-btn" onClick={onRefresh} disabled={isLoading}>
           {isLoading ? 'Refreshing...' : 'Refresh for new events'}
         </button>
-        {events.map((event) => (
-          <EventRow key={event.id} event={event} />
-        ))}
+        {focusedEvent && (
+          <div className="focused-event">
+            <div className="focused-event-header">
+              <span>Linked event</span>
+              <button className="focused-event-clear" onClick={onClearFocus}>
+                Show all events
+              </button>
+            </div>
+            <EventRow event={focusedEvent} focused />
+          </div>
+        )}
+        {events
+          .filter((event) => event.id !== focusedEvent?.id)
+          .map((event) => (
+            <EventRow key={event.id} event={event} />
+          ))}
 
         {/* Loading more indicator at bottom */}
         {isLoadingMore && (
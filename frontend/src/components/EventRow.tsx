@@ -1,9 +1,11 @@
-import { useState } from 'react';
+import { useState, useEffect } from 'react';
 import type { Event } from '../types';
 import { fetchEventPayload } from '../api';
 
 interface EventRowProps {
   event: Event;
+  /** Highlight the row and show its payload (e.g. opened from a notification link) */
+  focused?: boolean;
 }
 
 function formatTimestamp(timestamp: number): string {
@@ -35,29 +37,41 @@ function getClassificationIcon(classification: string): string {
   }
 }
 
-export function EventRow({ event }: EventRowProps) {
+export function EventRow({ event, focused = false }: EventRowProps) {
   const [expanded, setExpanded] = useState(false);
   const [payload, setPayload] = useState<unknown>(null);
   const [loadingPayload, setLoadingPayload] = useState(false);
 
+  const loadPayload = async () => {
+    setLoadingPayload(true);
+    try {
+      const data = await fetchEventPayload(event.id);
+      setPayload(data);
+    } catch (err) {
+      console.error('Failed to fetch payload:', err);
+      setPayload({ error: 'Failed to load payload' });
+    } finally {
+      setLoadingPayload(false);
+    }
+  };
+
   const handleExpand = async () => {
     if (!expanded && payload === null) {
-      setLoadingPayload(true);
-      try {
-        const data = await fetchEventPayload(event.id);
-        setPayload(data);
-      } catch (err) {
-        console.error('Failed to fetch payload:', err);
-        setPayload({ error: 'Failed to load payload' });
-      } finally {
-        setLoadingPayload(false);
-      }
+      await loadPayload();
     }
     setExpanded(!expanded);
   };
 
+  // A focused row opens with its payload shown
+  useEffect(() => {
+    if (focused) {
+      setExpanded(true);
+      loadPayload();
+    }
+  }, [focused, event.id]); // eslint-disable-line react-hooks/exhaustive-deps
+
   return (
-    <div className={`event-row ${expanded ? 'expanded' : ''}`}>
+    <div className={`event-row ${expanded ? 'expanded' : ''} ${focused ? 'focused' : ''}`}>
       <div className="event-row-header" onClick={handleExpand}>
         <span className="event-icon">{getClassificationIcon(event.classification)}</span>
         <span className="event-type">{event.event_type}</span>
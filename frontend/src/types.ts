@@ -9,6 +9,8 @@ export interface Event {
   timestamp: number;
   classification: Classification;
   notified: boolean;
+  pinned: boolean;
+  acknowledged_at: number | null;
   created_at: number;
 }
 
@@ -22,6 +24,7 @@ export interface EventTypeSummary {
 export interface Rule {
   event_type: string;
   classification: Classification;
+  priority: number;
 }
 
 export interface Stats {
@@ -35,8 +38,15 @@ export interface Filters {
   classifications: Set<Classification>;
   eventTypes: Set<string>;
   search: string;
+  /** Matches the summary only */
+  summarySearch?: string;
 }
 
+export type BulkEventAction =
+  | { action: 'acknowledge' }
+  | { action: 'set_classification'; classification: Classification }
+  | { action: 'delete'; confirm: true };
+
 // Auth types
 export interface AuthStatus {
   authenticated: boolean;
@@ -66,11 +76,33 @@ export interface NotificationLogEntry {
   created_at: number;
 }
 
+export interface ScheduledRetry {
+  event_id: string;
+  event_type: string;
+  summary: string;
+  attempts: number;
+  next_attempt_at: number;
+  backoff_secs: number;
+}
+
 export interface NotificationStatus {
   configured: boolean;
+  muted: boolean;
+  retries: ScheduledRetry[];
+}
+
+export interface BackendTestResult {
+  backend: string;
+  success: boolean;
+  error: string | null;
 }
 
 export interface TestNotificationResult {
   success: boolean;
   error: string | null;
+  backends: BackendTestResult[];
+}
+
+export interface NotificationBackend {
+  id: string;
 }
@@ -128,9 +128,9 @@ async fn main() -> Result<()> {
                             } else {
                                 println!("✓ (object response)");
                                 // Show keys if searching
-                                if search_filter.is_some() {
+                                if let Some(ref filter) = search_filter {
                                     let json_str = serde_json::to_string(&obj).unwrap_or_default().to_lowercase();
-                                    if json_str.contains(&search_filter.as_ref().unwrap().to_lowercase()) {
+                                    if json_str.contains(&filter.to_lowercase()) {
                                         println!("    Found match in response!");
                                         println!("    {}", serde_json::to_string_pretty(&obj).unwrap_or_default());
                                     }
@@ -65,11 +65,10 @@ async fn main() -> Result<()> {
             // Save bootstrap response for test fixtures (anonymized)
             let fixture_path = "tests/fixtures/bootstrap_response.json";
             if let Ok(anonymized) = anonymize_bootstrap(&bootstrap) {
-                if std::fs::create_dir_all("tests/fixtures").is_ok() {
-                    if std::fs::write(fixture_path, anonymized).is_ok() {
+                if std::fs::create_dir_all("tests/fixtures").is_ok()
+                    && std::fs::write(fixture_path, anonymized).is_ok() {
                         println!("\n  Saved anonymized fixture to {}", fixture_path);
                     }
-                }
             }
         }
         Err(e) => {
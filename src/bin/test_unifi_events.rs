@@ -8,15 +8,17 @@
 //! - UNIFI_PASSWORD: Password
 
 use anyhow::Result;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::fs;
 use std::sync::Arc;
 use tokio::sync::{mpsc, Mutex};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+use unifi_monitor::privacy::anonymize_payload;
 use unifi_monitor::unifi::{
     network::connect_network_websocket, protect::connect_protect_websocket,
-    system::connect_system_websocket, SeenEvents, StateTracker, UnifiConfig, UnifiEvent, UnifiSession,
+    system::connect_system_websocket, CameraTracker, ConnectionTracker, SeenEvents, SeenSet, StateTracker, UnifiConfig, UnifiEvent,
+    UnifiSession,
 };
 
 #[tokio::main]
@@ -62,7 +64,7 @@ async fn main() -> Result<()> {
     let (event_tx, mut event_rx) = mpsc::channel::<UnifiEvent>(100);
 
     // Create seen events set for deduplication
-    let seen_events: SeenEvents = Arc::new(Mutex::new(HashSet::new()));
+    let seen_events: SeenEvents = Arc::new(Mutex::new(SeenSet::default()));
 
     // Create state tracker to filter unchanged updates
     let state_tracker: StateTracker = Arc::new(Mutex::new(HashMap::new()));
@@ -98,7 +100,7 @@ async fn main() -> Result<()> {
     let state_for_network = state_tracker.clone();
     let network_handle = tokio::spawn(async move {
         println!("Connecting to Network WebSocket...");
-        match connect_network_websocket(&session_for_network, network_tx, seen_for_network, state_for_network).await {
+        match connect_network_websocket(&session_for_network, network_tx, seen_for_network, state_for_network, None, &ConnectionTracker::default()).await {
             Ok(_) => println!("Network WebSocket closed normally"),
             Err(e) => println!("Network WebSocket error: {}", e),
         }
@@ -111,7 +113,7 @@ async fn main() -> Result<()> {
     let state_for_system = state_tracker.clone();
     let system_handle = tokio::spawn(async move {
         println!("Connecting to System WebSocket...");
-        match connect_system_websocket(&session_for_system, system_tx, seen_for_system, state_for_system).await {
+        match connect_system_websocket(&session_for_system, system_tx, seen_for_system, state_for_system, None, &ConnectionTracker::default()).await {
             Ok(_) => println!("System WebSocket closed normally"),
             Err(e) => println!("System WebSocket error: {}", e),
         }
@@ -125,7 +127,7 @@ async fn main() -> Result<()> {
     let last_update_id = bootstrap.last_update_id.clone();
     let protect_handle = tokio::spawn(async move {
         println!("Connecting to Protect WebSocket...");
-        match connect_protect_websocket(&session_for_protect, &last_update_id, protect_tx, seen_for_protect, state_for_protect, None).await {
+        match connect_protect_websocket(&session_for_protect, &last_update_id, protect_tx, seen_for_protect, state_for_protect, CameraTracker::default(), None, &ConnectionTracker::default()).await {
             Ok(_) => println!("Protect WebSocket closed normally"),
             Err(e) => println!("Protect WebSocket error: {}", e),
         }
@@ -168,7 +170,7 @@ fn save_events(events: &HashMap<String, Vec<serde_json::Value>>) {
         let path = format!("{}/{}.json", fixture_dir, safe_name);
 
         // Anonymize events before saving
-        let anonymized: Vec<_> = event_list.iter().map(|e| anonymize_event(e)).collect();
+        let anonymized: Vec<_> = event_list.iter().map(anonymize_payload).collect();
 
         match serde_json::to_string_pretty(&anonymized) {
             Ok(json) => {
@@ -185,28 +187,3 @@ fn save_events(events: &HashMap<String, Vec<serde_json::Value>>) {
 
     println!("\nTotal: {} events saved", total);
 }
-
-fn anonymize_event(event: &serde_json::Value) -> serde_json::Value {
-    let mut cloned = event.clone();
-
-    if let Some(obj) = cloned.as_object_mut() {
-        // Anonymize common sensitive fields
-        for key in &["mac", "ip", "hostname", "name", "id", "userId", "user"] {
-            if obj.contains_key(*key) {
-                obj.insert(
-                    key.to_string(),
-                    serde_json::Value::String(format!("REDACTED_{}", key.to_uppercase())),
-                );
-            }
-        }
-
-        // Recursively anonymize nested objects
-        for (_, value) in obj.iter_mut() {
-            if value.is_object() {
-                *value = anonymize_event(value);
-            }
-        }
-    }
-
-    cloned
-}
@@ -1,9 +1,15 @@
 //! SQLite database module for event storage and classification
 
-use rusqlite::{params, Connection, OptionalExtension};
-use std::path::Path;
-use std::sync::{Arc, Mutex};
-use tracing::{debug, info};
+pub mod settings;
+
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension, Transaction, TransactionBehavior};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, warn};
 
 use crate::unifi::types::{EventSource, Severity, UnifiEvent};
 
@@ -26,6 +32,7 @@ impl Classification {
         }
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s {
             "ignored" => Some(Classification::Ignored),
@@ -37,6 +44,373 @@ impl Classification {
     }
 }
 
+/// How event classifications are kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClassificationMode {
+    /// Each event row stores its classification; rule changes rewrite the
+    /// matching rows. Fast to filter, but a rule change on a busy type is a
+    /// bulk UPDATE.
+    #[default]
+    Stored,
+    /// Classification is derived at query time by joining the rules table.
+    /// Rule changes are O(1); listing and counting pay for the join.
+    Dynamic,
+}
+
+impl ClassificationMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ClassificationMode::Stored => "stored",
+            ClassificationMode::Dynamic => "dynamic",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "stored" => Some(ClassificationMode::Stored),
+            "dynamic" => Some(ClassificationMode::Dynamic),
+            _ => None,
+        }
+    }
+}
+
+/// Why an event type received its classification
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClassificationReason {
+    /// A rule for exactly this event type
+    ExactRule(String),
+    /// A `*` wildcard rule matching this event type
+    WildcardRule(String),
+    /// No rule matched, so the event is unclassified
+    Default,
+}
+
+impl ClassificationReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ClassificationReason::ExactRule(_) => "exact",
+            ClassificationReason::WildcardRule(_) => "wildcard",
+            ClassificationReason::Default => "default",
+        }
+    }
+
+    /// The rule pattern that matched, if any
+    pub fn rule(&self) -> Option<&str> {
+        match self {
+            ClassificationReason::ExactRule(pattern) | ClassificationReason::WildcardRule(pattern) => Some(pattern),
+            ClassificationReason::Default => None,
+        }
+    }
+}
+
+/// A classification rule
+///
+/// `event_type` is an exact event type or a pattern where `*` matches any
+/// run of characters (e.g. `camera.*`). When several rules match, the one
+/// with the highest `priority` wins, then an exact rule, then the pattern
+/// with the most literal characters, then the rule created first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub event_type: String,
+    pub classification: Classification,
+    pub priority: i64,
+}
+
+/// GLOB pattern for the rule pattern expression `pattern`, with `?` and `[`
+/// escaped so that `*` is the only wildcard
+fn rule_glob_sql(pattern: &str) -> String {
+    format!("replace(replace({}, '[', '[[]'), '?', '[?]')", pattern)
+}
+
+/// Condition on rule `r` matching the event type expression `event_type`
+fn rule_matches_sql(event_type: &str) -> String {
+    format!(
+        "(r.event_type = {0} OR (instr(r.event_type, '*') > 0 AND {0} GLOB {1}))",
+        event_type,
+        rule_glob_sql("r.event_type")
+    )
+}
+
+/// Ranking of matching rules, best first (see [`Rule`])
+const RULE_MATCH_ORDER: &str =
+    "r.priority DESC, instr(r.event_type, '*') = 0 DESC, length(replace(r.event_type, '*', '')) DESC, r.rowid ASC";
+
+/// Drops the stored-mode rule triggers, including those from before wildcard rules
+const DROP_SYNC_TRIGGERS: &str = r#"
+    DROP TRIGGER IF EXISTS trg_rules_insert;
+    DROP TRIGGER IF EXISTS trg_rules_update;
+    DROP TRIGGER IF EXISTS trg_rules_delete;
+    DROP TRIGGER IF EXISTS trg_rule_match_insert;
+    DROP TRIGGER IF EXISTS trg_rule_match_update;
+    DROP TRIGGER IF EXISTS trg_rule_match_delete;
+    DROP TRIGGER IF EXISTS trg_events_clear_retry;
+"#;
+
+/// Events still waiting for a notification; shared by the retry queries so
+/// the sender never waits on a retry it would not send
+const PENDING_NOTIFY_SQL: &str = "classification = 'notify' AND notified = 0";
+
+/// Expression for the classification of the event type expression `event_type`
+fn matched_classification_sql(event_type: &str) -> String {
+    format!(
+        "COALESCE((SELECT r.classification FROM event_type_rules r WHERE {} ORDER BY {} LIMIT 1), 'unclassified')",
+        rule_matches_sql(event_type),
+        RULE_MATCH_ORDER
+    )
+}
+
+/// Comparison operator in a payload filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadFilterOp {
+    Eq,
+    Ne,
+    Gt,
+    Lt,
+    /// Array at the path contains the value
+    Contains,
+}
+
+impl PayloadFilterOp {
+    fn sql(&self) -> &'static str {
+        match self {
+            PayloadFilterOp::Eq => "=",
+            PayloadFilterOp::Ne => "!=",
+            PayloadFilterOp::Gt => ">",
+            PayloadFilterOp::Lt => "<",
+            PayloadFilterOp::Contains => "contains",
+        }
+    }
+}
+
+/// Filter on a value inside the event payload, e.g. `data.score > 80` or
+/// `data.smartDetectTypes contains person`
+///
+/// The path and value are always bound as SQL parameters; only the operator
+/// (from a fixed set) is written into the query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PayloadFilter {
+    /// SQLite JSON path (e.g. `$.data.score`)
+    pub path: String,
+    pub op: PayloadFilterOp,
+    pub value: serde_json::Value,
+}
+
+impl PayloadFilter {
+    /// Parse a `path op value` expression
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let expr = expr.trim();
+
+        // Path runs until the first character that can't be part of one
+        let path_len = expr
+            .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '[' | ']' | '$')))
+            .unwrap_or(expr.len());
+        let (path, rest) = expr.split_at(path_len);
+        let path = Self::parse_path(path)?;
+
+        let rest = rest.trim_start();
+        let (op, value) = if let Some(v) = rest.strip_prefix("contains ") {
+            (PayloadFilterOp::Contains, v)
+        } else if let Some(v) = rest.strip_prefix("!=") {
+            (PayloadFilterOp::Ne, v)
+        } else if let Some(v) = rest.strip_prefix('>') {
+            (PayloadFilterOp::Gt, v)
+        } else if let Some(v) = rest.strip_prefix('<') {
+            (PayloadFilterOp::Lt, v)
+        } else if let Some(v) = rest.strip_prefix('=') {
+            (PayloadFilterOp::Eq, v)
+        } else {
+            return Err("Expected operator: =, !=, >, < or contains".to_string());
+        };
+
+        let value = Self::parse_value(value.trim())?;
+        Ok(Self { path, op, value })
+    }
+
+    /// Convert `data.items[0].name` (optionally prefixed with `$.`) to `$.data.items[0].name`
+    fn parse_path(path: &str) -> Result<String, String> {
+        let path = path.strip_prefix("$.").unwrap_or(path);
+        if path.is_empty() {
+            return Err("Missing payload path".to_string());
+        }
+
+        for segment in path.split('.') {
+            let (name, index) = match segment.find('[') {
+                Some(i) => segment.split_at(i),
+                None => (segment, ""),
+            };
+            let valid_name = name
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            let valid_index = index.is_empty()
+                || index
+                    .strip_prefix('[')
+                    .and_then(|i| i.strip_suffix(']'))
+                    .is_some_and(|i| !i.is_empty() && i.chars().all(|c| c.is_ascii_digit()));
+            if !valid_name || !valid_index {
+                return Err(format!("Invalid payload path segment: {}", segment));
+            }
+        }
+
+        Ok(format!("$.{}", path))
+    }
+
+    fn parse_value(value: &str) -> Result<serde_json::Value, String> {
+        if value.is_empty() {
+            return Err("Missing value".to_string());
+        }
+        for quote in ['"', '\''] {
+            if let Some(inner) = value.strip_prefix(quote).and_then(|v| v.strip_suffix(quote)) {
+                return Ok(serde_json::Value::String(inner.to_string()));
+            }
+        }
+        if let Ok(i) = value.parse::<i64>() {
+            return Ok(i.into());
+        }
+        if let Ok(f) = value.parse::<f64>() {
+            return Ok(f.into());
+        }
+        match value {
+            "true" => Ok(true.into()),
+            "false" => Ok(false.into()),
+            _ => Ok(serde_json::Value::String(value.to_string())),
+        }
+    }
+
+    /// Append this filter's condition and parameters to a query
+    fn push_sql(&self, sql: &mut String, params_vec: &mut Vec<Box<dyn rusqlite::ToSql>>) {
+        if self.op == PayloadFilterOp::Contains {
+            sql.push_str(" AND EXISTS (SELECT 1 FROM json_each(payload, ?) WHERE value = ?)");
+        } else {
+            sql.push_str(&format!(" AND json_extract(payload, ?) {} ?", self.op.sql()));
+        }
+        params_vec.push(Box::new(self.path.clone()));
+        params_vec.push(match &self.value {
+            serde_json::Value::Bool(b) => Box::new(*b as i64),
+            serde_json::Value::Number(n) => match n.as_i64() {
+                Some(i) => Box::new(i),
+                None => Box::new(n.as_f64().unwrap_or_default()),
+            },
+            serde_json::Value::String(s) => Box::new(s.clone()),
+            other => Box::new(other.to_string()),
+        });
+    }
+}
+
+/// Conditions for `query_events` / `count_events`; empty fields match everything
+#[derive(Debug, Clone, Default)]
+pub struct EventFilter {
+    /// Any of these classifications (OR within)
+    pub classifications: Vec<Classification>,
+    /// Any of these event types (OR within)
+    pub event_types: Vec<String>,
+    /// Substring of event_type, summary, source or payload (case-insensitive)
+    pub search: Option<String>,
+    /// Substring of the summary only (case-insensitive), combinable with `search`
+    pub summary_search: Option<String>,
+    pub payload: Option<PayloadFilter>,
+    pub pinned: Option<bool>,
+    /// Only acknowledged (true) or unacknowledged (false) events
+    pub acknowledged: Option<bool>,
+    /// Only notified (true) or not yet notified (false) events
+    pub notified: Option<bool>,
+}
+
+impl EventFilter {
+    fn push_sql(&self, sql: &mut String, params_vec: &mut Vec<Box<dyn rusqlite::ToSql>>) {
+        if !self.classifications.is_empty() {
+            let placeholders: Vec<&str> = self.classifications.iter().map(|_| "?").collect();
+            sql.push_str(&format!(" AND classification IN ({})", placeholders.join(",")));
+            for c in &self.classifications {
+                params_vec.push(Box::new(c.as_str().to_string()));
+            }
+        }
+
+        if !self.event_types.is_empty() {
+            let placeholders: Vec<&str> = self.event_types.iter().map(|_| "?").collect();
+            sql.push_str(&format!(" AND event_type IN ({})", placeholders.join(",")));
+            for et in &self.event_types {
+                params_vec.push(Box::new(et.clone()));
+            }
+        }
+
+        if let Some(q) = &self.search {
+            sql.push_str(" AND (event_type LIKE ? OR summary LIKE ? OR source LIKE ? OR payload LIKE ?)");
+            let pattern = format!("%{}%", q);
+            params_vec.push(Box::new(pattern.clone()));
+            params_vec.push(Box::new(pattern.clone()));
+            params_vec.push(Box::new(pattern.clone()));
+            params_vec.push(Box::new(pattern));
+        }
+
+        if let Some(q) = &self.summary_search {
+            sql.push_str(" AND summary LIKE ?");
+            params_vec.push(Box::new(format!("%{}%", q)));
+        }
+
+        if let Some(filter) = &self.payload {
+            filter.push_sql(sql, params_vec);
+        }
+
+        if let Some(pinned) = self.pinned {
+            sql.push_str(" AND pinned = ?");
+            params_vec.push(Box::new(pinned as i64));
+        }
+
+        if let Some(acknowledged) = self.acknowledged {
+            sql.push_str(if acknowledged {
+                " AND acknowledged_at IS NOT NULL"
+            } else {
+                " AND acknowledged_at IS NULL"
+            });
+        }
+
+        if let Some(notified) = self.notified {
+            sql.push_str(" AND notified = ?");
+            params_vec.push(Box::new(notified as i64));
+        }
+    }
+
+    /// True if the filter matches every event
+    pub fn is_empty(&self) -> bool {
+        self.classifications.is_empty()
+            && self.event_types.is_empty()
+            && self.search.is_none()
+            && self.summary_search.is_none()
+            && self.payload.is_none()
+            && self.pinned.is_none()
+            && self.acknowledged.is_none()
+            && self.notified.is_none()
+    }
+}
+
+/// Change applied to every event matching a filter by
+/// [`Database::bulk_update_events`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BulkEventAction {
+    /// Mark events as acknowledged (already acknowledged events are left alone)
+    Acknowledge,
+    /// Overwrite the stored classification. Only meaningful in stored mode,
+    /// and a later change to a matching rule reclassifies the events again.
+    /// Events set to notify are marked notified, so past events aren't sent
+    /// as pending notifications.
+    SetClassification(Classification),
+    /// Delete events; pinned events are kept
+    Delete,
+}
+
+/// Result of [`Database::store_event_detailed`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StoreOutcome {
+    pub classification: Classification,
+    /// The first stored event of its type, with no rule matching the type
+    pub new_event_type: bool,
+    /// A new row was written (false for suppressed events and duplicate IDs)
+    pub inserted: bool,
+}
+
 /// Stored event with classification info
 #[derive(Debug, Clone)]
 pub struct StoredEvent {
@@ -51,35 +425,383 @@ pub struct StoredEvent {
     pub notified: bool,
     pub notify_attempts: i32,
     pub created_at: i64,
+    /// Pinned events are never deleted by cleanup
+    pub pinned: bool,
+    /// When the event was acknowledged (unix seconds), if it has been
+    pub acknowledged_at: Option<i64>,
+}
+
+#[cfg(test)]
+impl StoredEvent {
+    /// A pending `notify` Protect event of the given type, for tests
+    pub(crate) fn test_notify(event_type: &str) -> Self {
+        Self {
+            id: "test-123".to_string(),
+            source: EventSource::Protect,
+            event_type: event_type.to_string(),
+            severity: None,
+            payload: serde_json::json!({}),
+            summary: "Motion detected".to_string(),
+            timestamp: 1_700_000_000,
+            classification: Classification::Notify,
+            notified: false,
+            notify_attempts: 0,
+            created_at: 1_700_000_001,
+            pinned: false,
+            acknowledged_at: None,
+        }
+    }
+}
+
+/// A failed notification waiting for its next attempt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduledRetry {
+    pub event_id: String,
+    pub event_type: String,
+    pub summary: String,
+    /// Event payload, for redacting the summary
+    pub payload: serde_json::Value,
+    /// Failed attempts so far
+    pub attempts: i32,
+    /// When the next attempt is due (unix seconds)
+    pub next_attempt_at: i64,
+    /// Delay before `next_attempt_at`, doubling after each failure
+    pub backoff_secs: i64,
+}
+
+/// How far a session or invite token's creation time may be ahead of the
+/// current wall clock before we assume the clock stepped backward and reject it
+const CLOCK_SKEW_TOLERANCE_SECS: i64 = 300;
+
+/// Limits on the VACUUM that `cleanup_by_size` runs after deleting events
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VacuumPolicy {
+    /// Skip the VACUUM if the previous one started less than this long ago
+    pub min_interval: Duration,
+    /// Interrupt a VACUUM still running after this long (it rolls back)
+    pub max_duration: Duration,
+}
+
+impl Default for VacuumPolicy {
+    fn default() -> Self {
+        Self {
+            min_interval: Duration::from_secs(6 * 3600),
+            max_duration: Duration::from_secs(30),
+        }
+    }
+}
+
+/// What happened to the VACUUM step of a cleanup
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VacuumOutcome {
+    Completed(Duration),
+    /// A VACUUM already ran within `VacuumPolicy::min_interval`
+    SkippedRecent,
+    /// Writes were pending, or another connection held a write lock, so the
+    /// VACUUM didn't start or gave way to them
+    SkippedBusy,
+    /// Interrupted at `VacuumPolicy::max_duration`; the file is unchanged
+    TimedOut(Duration),
+}
+
+/// How often a running VACUUM checks for writes to give way to
+const VACUUM_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Write access to the shared connection, counted as a pending write from
+/// before the lock is taken until dropped
+struct WriteConn<'a> {
+    conn: MutexGuard<'a, Connection>,
+    _pending: PendingWrite<'a>,
+}
+
+struct PendingWrite<'a>(&'a AtomicUsize);
+
+impl Drop for PendingWrite<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl Deref for WriteConn<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        &self.conn
+    }
+}
+
+impl DerefMut for WriteConn<'_> {
+    fn deref_mut(&mut self) -> &mut Connection {
+        &mut self.conn
+    }
 }
 
+/// Migration backups kept beside the database by default
+pub const DEFAULT_MIGRATION_BACKUPS: usize = 3;
+
+/// Version of the schema this build creates, stored in `PRAGMA user_version`.
+/// Bump it whenever the tables, the stored-mode triggers or the
+/// `events_classified` view change, so existing databases are migrated (and
+/// backed up first, if enabled) and the triggers rebuilt.
+const SCHEMA_VERSION: i64 = 2;
+
+/// Columns added after their table was first released, as
+/// `(table, column, declaration)`; added on open where missing
+const COLUMN_MIGRATIONS: &[(&str, &str, &str)] = &[
+    ("events", "pinned", "INTEGER NOT NULL DEFAULT 0"),
+    ("events", "acknowledged_at", "INTEGER"),
+    ("event_type_rules", "priority", "INTEGER NOT NULL DEFAULT 0"),
+    // Notification retry schedule: when the next attempt is due, the delay
+    // that led to it, and backends (comma-separated IDs, or `backend:recipient`
+    // for part of a backend's recipients) that already delivered
+    ("events", "next_attempt_at", "INTEGER"),
+    ("events", "notify_backoff_secs", "INTEGER"),
+    ("events", "notify_delivered", "TEXT"),
+];
+
 /// Database handle (thread-safe)
 #[derive(Clone)]
 pub struct Database {
     conn: Arc<Mutex<Connection>>,
+    /// Opened with `open_read_only` - all write methods fail early
+    read_only: bool,
+    classification_mode: ClassificationMode,
+    /// File path, so VACUUM can run on its own connection (None in memory)
+    path: Option<PathBuf>,
+    vacuum_policy: VacuumPolicy,
+    last_vacuum: Arc<Mutex<Option<Instant>>>,
+    /// Writers holding or waiting for the shared connection; a VACUUM gives
+    /// way to them instead of making them wait
+    pending_writes: Arc<AtomicUsize>,
 }
 
 impl Database {
     /// Open or create the database at the given path
+    ///
+    /// File databases use WAL journal mode so that other processes can read
+    /// them (see `open_read_only`) while this one is writing.
     pub fn open<P: AsRef<Path>>(path: P) -> rusqlite::Result<Self> {
+        Self::open_with_mode(path, ClassificationMode::Stored)
+    }
+
+    /// Open or create the database, keeping classifications as `mode` says
+    ///
+    /// Switching a database back to `Stored` after running it `Dynamic`
+    /// rewrites every event whose stored classification went stale, once.
+    pub fn open_with_mode<P: AsRef<Path>>(path: P, mode: ClassificationMode) -> rusqlite::Result<Self> {
+        Self::open_file(path.as_ref(), mode, None)
+    }
+
+    /// Like `open_with_mode`, but if a schema migration is pending, first copy
+    /// the file to a timestamped `<name>.<time>.bak` beside it
+    ///
+    /// Only the newest `keep` backups are kept. If the migration then fails,
+    /// the backup's path is logged for recovery.
+    pub fn open_with_migration_backup<P: AsRef<Path>>(
+        path: P,
+        mode: ClassificationMode,
+        keep: usize,
+    ) -> rusqlite::Result<Self> {
+        Self::open_file(path.as_ref(), mode, Some(keep))
+    }
+
+    fn open_file(path: &Path, mode: ClassificationMode, backup_keep: Option<usize>) -> rusqlite::Result<Self> {
         let conn = Connection::open(path)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        let backup = match backup_keep {
+            Some(keep) if Self::has_pending_migrations(&conn)? => Some(Self::backup_before_migrate(&conn, path, keep)?),
+            _ => None,
+        };
         let db = Self {
             conn: Arc::new(Mutex::new(conn)),
+            read_only: false,
+            classification_mode: mode,
+            path: Some(path.to_path_buf()),
+            vacuum_policy: VacuumPolicy::default(),
+            last_vacuum: Arc::new(Mutex::new(None)),
+            pending_writes: Arc::new(AtomicUsize::new(0)),
         };
-        db.initialize()?;
+        if let Err(e) = db.migrate() {
+            if let Some(backup) = &backup {
+                error!(error = %e, backup = %backup.display(), "Database migration failed, restore from the backup");
+            }
+            return Err(e);
+        }
         Ok(db)
     }
 
+    /// Whether an existing database is at an older schema version
+    fn has_pending_migrations(conn: &Connection) -> rusqlite::Result<bool> {
+        let has_tables = conn
+            .prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'events'")?
+            .exists([])?;
+        let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        Ok(has_tables && version < SCHEMA_VERSION)
+    }
+
+    /// Bring the schema up to date, then record its version
+    fn migrate(&self) -> rusqlite::Result<()> {
+        let outdated = {
+            let conn = self.conn.lock().unwrap();
+            let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+            version < SCHEMA_VERSION
+        };
+        self.initialize()?;
+        self.apply_classification_mode(outdated)?;
+        if outdated {
+            self.conn.lock().unwrap().pragma_update(None, "user_version", SCHEMA_VERSION)?;
+            info!(version = SCHEMA_VERSION, "Database schema migrated");
+        }
+        Ok(())
+    }
+
+    /// Snapshot the database with SQLite's backup API, then prune older
+    /// snapshots down to `keep`; returns the new backup's path
+    fn backup_before_migrate(conn: &Connection, path: &Path, keep: usize) -> rusqlite::Result<PathBuf> {
+        let file_name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        let dir = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let backup = dir.join(format!("{}.{}.bak", file_name, chrono::Utc::now().format("%Y%m%d-%H%M%S")));
+        conn.backup(rusqlite::DatabaseName::Main, &backup, None)?;
+        info!(backup = %backup.display(), "Backed up database before migrating");
+
+        // Only our own `<name>.YYYYMMDD-HHMMSS.bak` files; their timestamps
+        // sort by name, oldest first
+        let is_migration_backup = |name: &str| {
+            let Some(stamp) = name
+                .strip_prefix(file_name.as_str())
+                .and_then(|rest| rest.strip_prefix('.'))
+                .and_then(|rest| rest.strip_suffix(".bak"))
+            else {
+                return false;
+            };
+            chrono::NaiveDateTime::parse_from_str(stamp, "%Y%m%d-%H%M%S").is_ok() && stamp.len() == 15
+        };
+        let mut backups: Vec<PathBuf> = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|p| p.file_name().and_then(|n| n.to_str()).is_some_and(is_migration_backup))
+                .collect(),
+            Err(e) => {
+                warn!(error = %e, "Failed to list old database backups");
+                Vec::new()
+            }
+        };
+        backups.sort();
+        let excess = backups.len().saturating_sub(keep.max(1));
+        for old in &backups[..excess] {
+            if let Err(e) = std::fs::remove_file(old) {
+                warn!(error = %e, backup = %old.display(), "Failed to remove old database backup");
+            }
+        }
+        Ok(backup)
+    }
+
+    /// Replace the default VACUUM limits
+    pub fn with_vacuum_policy(mut self, policy: VacuumPolicy) -> Self {
+        self.vacuum_policy = policy;
+        self
+    }
+
+    /// Open an existing database read-only (e.g. for a separate reporting process)
+    ///
+    /// Reading concurrently with a writer in another process relies on the
+    /// writer having put the database in WAL mode, which `open` does. The
+    /// schema is not created or migrated, and every write method returns a
+    /// `SQLITE_READONLY` error without touching SQLite.
+    ///
+    /// The classification mode follows the writer: if its sync triggers are
+    /// absent, classifications are derived at query time.
+    pub fn open_read_only<P: AsRef<Path>>(path: P) -> rusqlite::Result<Self> {
+        let conn = Connection::open_with_flags(
+            path.as_ref(),
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        let classification_mode = if Self::has_sync_triggers(&conn)? {
+            ClassificationMode::Stored
+        } else {
+            ClassificationMode::Dynamic
+        };
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+            read_only: true,
+            classification_mode,
+            path: Some(path.as_ref().to_path_buf()),
+            vacuum_policy: VacuumPolicy::default(),
+            last_vacuum: Arc::new(Mutex::new(None)),
+            pending_writes: Arc::new(AtomicUsize::new(0)),
+        })
+    }
+
     /// Open an in-memory database (for testing)
     pub fn open_in_memory() -> rusqlite::Result<Self> {
         let conn = Connection::open_in_memory()?;
         let db = Self {
             conn: Arc::new(Mutex::new(conn)),
+            read_only: false,
+            classification_mode: ClassificationMode::Stored,
+            path: None,
+            vacuum_policy: VacuumPolicy::default(),
+            last_vacuum: Arc::new(Mutex::new(None)),
+            pending_writes: Arc::new(AtomicUsize::new(0)),
         };
-        db.initialize()?;
+        db.migrate()?;
         Ok(db)
     }
 
+    /// Whether this handle was opened with `open_read_only`
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// How this handle keeps event classifications
+    pub fn classification_mode(&self) -> ClassificationMode {
+        self.classification_mode
+    }
+
+    /// Table (or view) that event reads select from
+    fn events_source(&self) -> &'static str {
+        match self.classification_mode {
+            ClassificationMode::Stored => "events",
+            ClassificationMode::Dynamic => "events_classified",
+        }
+    }
+
+    /// Lock the connection for a write, failing early on read-only handles
+    fn write_conn(&self) -> rusqlite::Result<WriteConn<'_>> {
+        if self.read_only {
+            return Err(rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_READONLY),
+                Some("Database was opened read-only".to_string()),
+            ));
+        }
+        self.pending_writes.fetch_add(1, Ordering::SeqCst);
+        let pending = PendingWrite(&self.pending_writes);
+        Ok(WriteConn {
+            conn: self.conn.lock().unwrap(),
+            _pending: pending,
+        })
+    }
+
+    /// Confirm the database still accepts writes by writing a scratch row in
+    /// a transaction that is rolled back
+    ///
+    /// Fails on a read-only mount or handle, or a lock held past the busy
+    /// timeout. Goes through SQLite rather than the `read_only` flag, so it
+    /// reports what a write would actually do.
+    pub fn check_writable(&self) -> rusqlite::Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let tx = Transaction::new_unchecked(&conn, TransactionBehavior::Immediate)?;
+        tx.execute(
+            "INSERT OR REPLACE INTO settings (key, value, updated_at) VALUES ('health_check', '', 0)",
+            [],
+        )?;
+        tx.rollback()
+    }
+
     /// Initialize database schema
     fn initialize(&self) -> rusqlite::Result<()> {
         let conn = self.conn.lock().unwrap();
@@ -106,7 +828,8 @@ impl Database {
                 classification TEXT NOT NULL DEFAULT 'unclassified',
                 notified INTEGER DEFAULT 0,
                 notify_attempts INTEGER DEFAULT 0,
-                created_at INTEGER NOT NULL
+                created_at INTEGER NOT NULL,
+                pinned INTEGER NOT NULL DEFAULT 0
             );
 
             -- Indexes for common queries
@@ -115,6 +838,20 @@ impl Database {
             CREATE INDEX IF NOT EXISTS idx_events_classification ON events(classification);
             CREATE INDEX IF NOT EXISTS idx_events_notified ON events(notified) WHERE notified = 0;
 
+            -- Latest state per entity for compacted (heartbeat-like) event types,
+            -- updated in place instead of appending to events
+            CREATE TABLE IF NOT EXISTS entity_state (
+                event_type TEXT NOT NULL,
+                entity_id TEXT NOT NULL,
+                source TEXT NOT NULL,
+                summary TEXT NOT NULL,
+                payload TEXT NOT NULL,
+                first_seen INTEGER NOT NULL,
+                last_seen INTEGER NOT NULL,
+                change_count INTEGER NOT NULL DEFAULT 1,
+                PRIMARY KEY (event_type, entity_id)
+            );
+
             -- Sync state for WebSocket reconnection
             CREATE TABLE IF NOT EXISTS sync_state (
                 source TEXT PRIMARY KEY,
@@ -162,13 +899,170 @@ impl Database {
                 created_at INTEGER NOT NULL
             );
             CREATE INDEX IF NOT EXISTS idx_notification_log_created ON notification_log(created_at DESC);
+
+            -- Small persisted settings (e.g. the notification mute switch)
+            CREATE TABLE IF NOT EXISTS settings (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
             "#,
         )?;
 
+        for (table, column, decl) in COLUMN_MIGRATIONS {
+            Self::add_column_if_missing(&conn, table, column, decl)?;
+        }
+        conn.execute_batch(
+            "CREATE INDEX IF NOT EXISTS idx_events_next_attempt ON events(next_attempt_at) \
+             WHERE next_attempt_at IS NOT NULL;",
+        )?;
+
+        // Recreated on every start so it picks up new event columns
+        conn.execute_batch(&format!(
+            r#"
+            -- Events with their classification derived from the rules table,
+            -- used instead of `events` in dynamic classification mode
+            DROP VIEW IF EXISTS events_classified;
+            CREATE VIEW events_classified AS
+            SELECT e.id, e.source, e.event_type, e.severity, e.payload, e.summary, e.timestamp,
+                   {} AS classification,
+                   e.notified, e.notify_attempts, e.created_at, e.pinned, e.next_attempt_at,
+                   e.acknowledged_at
+            FROM events e;
+            "#,
+            matched_classification_sql("e.event_type")
+        ))?;
+
         info!("Database initialized");
         Ok(())
     }
 
+    fn add_column_if_missing(conn: &Connection, table: &str, column: &str, decl: &str) -> rusqlite::Result<()> {
+        let exists = conn
+            .prepare(&format!("SELECT 1 FROM pragma_table_info('{}') WHERE name = ?1", table))?
+            .exists([column])?;
+        if !exists {
+            conn.execute_batch(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, decl))?;
+            info!(table, column, "Added database column");
+        }
+        Ok(())
+    }
+
+    /// Drop scheduled retries of events whose rule no longer says notify.
+    /// Stored mode does this in `trg_events_clear_retry`; dynamic mode has
+    /// no column to trigger on, so rule writers call this instead.
+    fn clear_stale_retries(&self, conn: &Connection) -> rusqlite::Result<()> {
+        if self.classification_mode != ClassificationMode::Dynamic {
+            return Ok(());
+        }
+        let cleared = conn.execute(
+            r#"
+            UPDATE events SET next_attempt_at = NULL, notify_backoff_secs = NULL
+            WHERE next_attempt_at IS NOT NULL
+              AND id NOT IN (SELECT id FROM events_classified WHERE classification = 'notify')
+            "#,
+            [],
+        )?;
+        if cleared > 0 {
+            debug!(cleared, "Dropped retries of events no longer classified notify");
+        }
+        Ok(())
+    }
+
+    fn has_sync_triggers(conn: &Connection) -> rusqlite::Result<bool> {
+        conn.query_row(
+            "SELECT 1 FROM sqlite_master WHERE type = 'trigger' AND name = 'trg_rule_match_insert'",
+            [],
+            |_| Ok(()),
+        )
+        .optional()
+        .map(|row| row.is_some())
+    }
+
+    /// Install or drop the triggers that keep stored classifications in sync;
+    /// `rebuild` replaces triggers from an older schema version
+    fn apply_classification_mode(&self, rebuild: bool) -> rusqlite::Result<()> {
+        let conn = self.conn.lock().unwrap();
+
+        match self.classification_mode {
+            ClassificationMode::Dynamic => {
+                conn.execute_batch(DROP_SYNC_TRIGGERS)?;
+                self.clear_stale_retries(&conn)?;
+            }
+            ClassificationMode::Stored => {
+                if !rebuild && Self::has_sync_triggers(&conn)? {
+                    return Ok(());
+                }
+
+                // Keep each event's denormalized classification in sync with the
+                // rules table, however the rules are written. Events matching the
+                // changed rule's pattern are re-resolved against all rules; the
+                // exact-match updates use idx_events_event_type.
+                let reclassify = |pattern: &str| {
+                    format!(
+                        r#"
+                        UPDATE events SET classification = {0}
+                        WHERE event_type = {1};
+                        UPDATE events SET classification = {0}
+                        WHERE instr({1}, '*') > 0 AND event_type GLOB {2};
+                        "#,
+                        matched_classification_sql("events.event_type"),
+                        pattern,
+                        rule_glob_sql(pattern)
+                    )
+                };
+                let tx = conn.unchecked_transaction()?;
+                tx.execute_batch(DROP_SYNC_TRIGGERS)?;
+                tx.execute_batch(&format!(
+                    r#"
+                    CREATE TRIGGER trg_rule_match_insert AFTER INSERT ON event_type_rules
+                    BEGIN
+                        {new}
+                    END;
+
+                    CREATE TRIGGER trg_rule_match_update AFTER UPDATE ON event_type_rules
+                    BEGIN
+                        {old}
+                        {new}
+                    END;
+
+                    CREATE TRIGGER trg_rule_match_delete AFTER DELETE ON event_type_rules
+                    BEGIN
+                        {old}
+                    END;
+
+                    -- A scheduled retry is dropped once its event stops being notify
+                    CREATE TRIGGER trg_events_clear_retry AFTER UPDATE OF classification ON events
+                    WHEN NEW.next_attempt_at IS NOT NULL AND NEW.classification IS NOT 'notify'
+                    BEGIN
+                        UPDATE events SET next_attempt_at = NULL, notify_backoff_secs = NULL
+                        WHERE id = NEW.id;
+                    END;
+                    "#,
+                    new = reclassify("NEW.event_type"),
+                    old = reclassify("OLD.event_type"),
+                ))?;
+
+                // Rules may have changed while the triggers were absent (dynamic
+                // mode, or a database from before they existed)
+                let resynced = tx.execute(
+                    &format!(
+                        "UPDATE events SET classification = {0} WHERE classification IS NOT {0}",
+                        matched_classification_sql("events.event_type")
+                    ),
+                    [],
+                )?;
+                tx.commit()?;
+
+                if resynced > 0 {
+                    info!(resynced, "Stored event classifications resynced with rules");
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     /// Get classification rule for an event type
     pub fn get_rule(&self, event_type: &str) -> rusqlite::Result<Option<Classification>> {
         let conn = self.conn.lock().unwrap();
@@ -184,147 +1078,494 @@ impl Database {
         .map(|opt| opt.flatten())
     }
 
-    /// Set classification rule for an event type
-    /// Also updates all existing events of this type to the new classification
+    /// Set classification rule for an event type or `*` pattern, keeping
+    /// the priority of an existing rule (new rules get priority 0)
+    /// In stored mode, existing events are reclassified by the
+    /// `trg_rule_match_*` triggers
     pub fn set_rule(&self, event_type: &str, classification: Classification) -> rusqlite::Result<()> {
-        let conn = self.conn.lock().unwrap();
+        self.set_rule_with_priority(event_type, classification, None)?;
+        Ok(())
+    }
+
+    /// Set classification rule along with its priority (`None` keeps the
+    /// existing priority). Returns the rule's resulting priority.
+    pub fn set_rule_with_priority(
+        &self,
+        event_type: &str,
+        classification: Classification,
+        priority: Option<i64>,
+    ) -> rusqlite::Result<i64> {
+        let conn = self.write_conn()?;
         let now = chrono::Utc::now().timestamp();
 
-        conn.execute(
+        let priority: i64 = conn.query_row(
             r#"
-            INSERT INTO event_type_rules (event_type, classification, created_at, updated_at)
-            VALUES (?1, ?2, ?3, ?3)
+            INSERT INTO event_type_rules (event_type, classification, priority, created_at, updated_at)
+            VALUES (?1, ?2, COALESCE(?3, 0), ?4, ?4)
             ON CONFLICT(event_type) DO UPDATE SET
                 classification = excluded.classification,
+                priority = COALESCE(?3, priority),
                 updated_at = excluded.updated_at
+            RETURNING priority
             "#,
-            params![event_type, classification.as_str(), now],
-        )?;
-
-        // Update all existing events of this type to the new classification
-        let updated = conn.execute(
-            "UPDATE events SET classification = ?1 WHERE event_type = ?2",
-            params![classification.as_str(), event_type],
+            params![event_type, classification.as_str(), priority, now],
+            |row| row.get(0),
         )?;
+        self.clear_stale_retries(&conn)?;
 
-        debug!(event_type, classification = classification.as_str(), updated, "Rule set and events updated");
-        Ok(())
+        debug!(event_type, classification = classification.as_str(), priority, "Rule set and events updated");
+        Ok(priority)
     }
 
     /// Delete a classification rule
-    /// Also reverts all existing events of this type to unclassified
+    /// Existing events of this type revert to unclassified
     pub fn delete_rule(&self, event_type: &str) -> rusqlite::Result<bool> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.write_conn()?;
         let rows = conn.execute(
             "DELETE FROM event_type_rules WHERE event_type = ?1",
             params![event_type],
         )?;
+        self.clear_stale_retries(&conn)?;
 
         if rows > 0 {
-            // Revert all events of this type to unclassified
-            let updated = conn.execute(
-                "UPDATE events SET classification = 'unclassified' WHERE event_type = ?1",
-                params![event_type],
-            )?;
-            debug!(event_type, updated, "Rule deleted and events reverted to unclassified");
+            debug!(event_type, "Rule deleted and events reverted to unclassified");
         }
 
         Ok(rows > 0)
     }
 
-    /// Get all classification rules
-    pub fn get_all_rules(&self) -> rusqlite::Result<Vec<(String, Classification)>> {
+    /// Rename an event type, merging its events and rule into `to`
+    ///
+    /// If both types have a rule, `to`'s rule wins and `from`'s is dropped.
+    /// Merged events are reclassified by the rules that now match them (or
+    /// unclassified).
+    /// Returns the number of events renamed.
+    pub fn rename_event_type(&self, from: &str, to: &str) -> rusqlite::Result<usize> {
+        let mut conn = self.write_conn()?;
+        if from == to {
+            return Ok(0);
+        }
+
+        let tx = conn.transaction()?;
+
+        // Move the rule over unless `to` already has one, then drop the leftover
+        tx.execute(
+            "UPDATE OR IGNORE event_type_rules SET event_type = ?2 WHERE event_type = ?1",
+            params![from, to],
+        )?;
+        tx.execute(
+            "DELETE FROM event_type_rules WHERE event_type = ?1",
+            params![from],
+        )?;
+
+        let renamed = tx.execute(
+            "UPDATE events SET event_type = ?2 WHERE event_type = ?1",
+            params![from, to],
+        )?;
+
+        tx.execute(
+            &format!(
+                "UPDATE events SET classification = {} WHERE event_type = ?1",
+                matched_classification_sql("?1")
+            ),
+            params![to],
+        )?;
+        self.clear_stale_retries(&tx)?;
+
+        tx.commit()?;
+
+        debug!(from, to, renamed, "Event type renamed");
+        Ok(renamed)
+    }
+
+    /// Replace every classification rule and set the given settings, all in
+    /// one transaction (used to import a configuration bundle)
+    pub fn replace_config(
+        &self,
+        rules: &[Rule],
+        settings: &[(&str, String)],
+    ) -> rusqlite::Result<()> {
+        let mut conn = self.write_conn()?;
+        let now = chrono::Utc::now().timestamp();
+        let tx = conn.transaction()?;
+
+        tx.execute("DELETE FROM event_type_rules", [])?;
+        for rule in rules {
+            tx.execute(
+                r#"
+                INSERT INTO event_type_rules (event_type, classification, priority, created_at, updated_at)
+                VALUES (?1, ?2, ?3, ?4, ?4)
+                ON CONFLICT(event_type) DO UPDATE SET
+                    classification = excluded.classification,
+                    priority = excluded.priority,
+                    updated_at = excluded.updated_at
+                "#,
+                params![rule.event_type, rule.classification.as_str(), rule.priority, now],
+            )?;
+        }
+
+        for (key, value) in settings {
+            tx.execute(
+                r#"
+                INSERT INTO settings (key, value, updated_at)
+                VALUES (?1, ?2, ?3)
+                ON CONFLICT(key) DO UPDATE SET
+                    value = excluded.value,
+                    updated_at = excluded.updated_at
+                "#,
+                params![key, value, now],
+            )?;
+        }
+        self.clear_stale_retries(&tx)?;
+
+        tx.commit()?;
+        info!(rules = rules.len(), settings = settings.len(), "Configuration replaced");
+        Ok(())
+    }
+
+    /// Get all classification rules in creation order, which is the final
+    /// tie-break between equally ranked matching rules
+    pub fn get_all_rules(&self) -> rusqlite::Result<Vec<Rule>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT event_type, classification, priority FROM event_type_rules ORDER BY rowid"
+        )?;
+
+        let rows = stmt.query_map([], |row| {
+            let classification_str: String = row.get(1)?;
+            Ok(Rule {
+                event_type: row.get(0)?,
+                classification: Classification::from_str(&classification_str)
+                    .unwrap_or(Classification::Unclassified),
+                priority: row.get(2)?,
+            })
+        })?;
+
+        rows.collect()
+    }
+
+    /// Find the rule that classifies an event type: the best-ranked exact or
+    /// wildcard rule matching it (see [`Rule`])
+    pub fn matching_rule(&self, event_type: &str) -> rusqlite::Result<Option<Rule>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            &format!(
+                "SELECT r.event_type, r.classification, r.priority FROM event_type_rules r WHERE {} ORDER BY {} LIMIT 1",
+                rule_matches_sql("?1"),
+                RULE_MATCH_ORDER
+            ),
+            params![event_type],
+            |row| {
+                let classification_str: String = row.get(1)?;
+                Ok(Rule {
+                    event_type: row.get(0)?,
+                    classification: Classification::from_str(&classification_str)
+                        .unwrap_or(Classification::Unclassified),
+                    priority: row.get(2)?,
+                })
+            },
+        )
+        .optional()
+    }
+
+    /// Get classification for an event type without storing
+    pub fn get_classification(&self, event_type: &str) -> rusqlite::Result<Classification> {
+        Ok(self.explain_classification(event_type)?.0)
+    }
+
+    /// Get classification for an event type along with the reason it applies
+    pub fn explain_classification(
+        &self,
+        event_type: &str,
+    ) -> rusqlite::Result<(Classification, ClassificationReason)> {
+        match self.matching_rule(event_type)? {
+            Some(rule) if rule.event_type == event_type => {
+                Ok((rule.classification, ClassificationReason::ExactRule(rule.event_type)))
+            }
+            Some(rule) => Ok((rule.classification, ClassificationReason::WildcardRule(rule.event_type))),
+            None => Ok((Classification::Unclassified, ClassificationReason::Default)),
+        }
+    }
+
+    /// Store an event, applying classification rules
+    /// Returns the classification applied
+    /// Note: Suppressed events are NOT stored
+    pub fn store_event(&self, event: &UnifiEvent) -> rusqlite::Result<Classification> {
+        Ok(self.store_event_detailed(event)?.classification)
+    }
+
+    /// Like `store_event`, also reporting whether the event's type was never
+    /// seen before: no events of it stored and no rule classifying it
+    pub fn store_event_detailed(&self, event: &UnifiEvent) -> rusqlite::Result<StoreOutcome> {
+        // First, look up the classification rule
+        let (classification, reason) = self.explain_classification(&event.event_type)?;
+
+        // Don't store suppressed events
+        if classification == Classification::Suppressed {
+            return Ok(StoreOutcome {
+                classification,
+                new_event_type: false,
+                inserted: false,
+            });
+        }
+
+        let conn = self.write_conn()?;
+        // Checked under the write lock, so only one of a burst counts as new
+        let new_event_type = reason == ClassificationReason::Default
+            && !conn
+                .prepare("SELECT 1 FROM events WHERE event_type = ?1 LIMIT 1")?
+                .exists([&event.event_type])?;
+        let now = chrono::Utc::now().timestamp();
+        let payload = serde_json::to_string(&event.raw).unwrap_or_default();
+        let severity = event.severity.map(|s| format!("{:?}", s).to_lowercase());
+
+        let inserted = conn.execute(
+            r#"
+            INSERT OR IGNORE INTO events
+            (id, source, event_type, severity, payload, summary, timestamp, classification, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            "#,
+            params![
+                event.id,
+                event.source.to_string(),
+                event.event_type,
+                severity,
+                payload,
+                event.summary,
+                event.timestamp.timestamp(),
+                classification.as_str(),
+                now,
+            ],
+        )? > 0;
+
+        debug!(
+            id = event.id,
+            event_type = event.event_type,
+            classification = classification.as_str(),
+            inserted,
+            "Event stored"
+        );
+
+        Ok(StoreOutcome {
+            classification,
+            new_event_type,
+            inserted,
+        })
+    }
+
+    /// Get one stored event by ID
+    pub fn get_event(&self, event_id: &str) -> rusqlite::Result<Option<StoredEvent>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            &format!(
+                r#"
+                SELECT id, source, event_type, severity, payload, summary, timestamp,
+                       classification, notified, notify_attempts, created_at, pinned, acknowledged_at
+                FROM {}
+                WHERE id = ?1
+                "#,
+                self.events_source()
+            ),
+            params![event_id],
+            Self::row_to_stored_event,
+        )
+        .optional()
+    }
+
+    /// Check whether an event with this ID is already stored
+    pub fn event_exists(&self, event_id: &str) -> rusqlite::Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT 1 FROM events WHERE id = ?1",
+            params![event_id],
+            |_| Ok(()),
+        )
+        .optional()
+        .map(|row| row.is_some())
+    }
+
+    /// Get events that need notification (notify classification, not yet
+    /// notified, and not waiting for a scheduled retry)
+    pub fn get_pending_notifications(&self) -> rusqlite::Result<Vec<StoredEvent>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            r#"
+            SELECT id, source, event_type, severity, payload, summary, timestamp,
+                   classification, notified, notify_attempts, created_at, pinned, acknowledged_at
+            FROM {}
+            WHERE classification = 'notify' AND notified = 0 AND next_attempt_at IS NULL
+            ORDER BY timestamp ASC
+            "#,
+            self.events_source()
+        ))?;
+
+        let rows = stmt.query_map([], Self::row_to_stored_event)?;
+        rows.collect()
+    }
+
+    /// Get pending notifications whose scheduled retry is due at `now`
+    pub fn get_due_notifications(&self, now: i64) -> rusqlite::Result<Vec<StoredEvent>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            r#"
+            SELECT id, source, event_type, severity, payload, summary, timestamp,
+                   classification, notified, notify_attempts, created_at, pinned, acknowledged_at
+            FROM {}
+            WHERE {} AND next_attempt_at <= ?1
+            ORDER BY next_attempt_at ASC
+            "#,
+            self.events_source(),
+            PENDING_NOTIFY_SQL
+        ))?;
+
+        let rows = stmt.query_map(params![now], Self::row_to_stored_event)?;
+        rows.collect()
+    }
+
+    /// When the earliest scheduled notification retry is due
+    pub fn next_notification_retry_at(&self) -> rusqlite::Result<Option<i64>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            &format!(
+                "SELECT MIN(next_attempt_at) FROM {} WHERE {}",
+                self.events_source(),
+                PENDING_NOTIFY_SQL
+            ),
+            [],
+            |row| row.get(0),
+        )
+    }
+
+    /// Notifications waiting for a retry, soonest first
+    pub fn get_scheduled_retries(&self) -> rusqlite::Result<Vec<ScheduledRetry>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT event_type, classification FROM event_type_rules ORDER BY event_type"
+            r#"
+            SELECT id, event_type, summary, notify_attempts, next_attempt_at, COALESCE(notify_backoff_secs, 0),
+                   payload
+            FROM events
+            WHERE notified = 0 AND next_attempt_at IS NOT NULL
+            ORDER BY next_attempt_at ASC
+            "#,
         )?;
 
         let rows = stmt.query_map([], |row| {
-            let event_type: String = row.get(0)?;
-            let classification_str: String = row.get(1)?;
-            let classification = Classification::from_str(&classification_str)
-                .unwrap_or(Classification::Unclassified);
-            Ok((event_type, classification))
+            let payload: String = row.get(6)?;
+            Ok(ScheduledRetry {
+                event_id: row.get(0)?,
+                event_type: row.get(1)?,
+                summary: row.get(2)?,
+                payload: serde_json::from_str(&payload).unwrap_or(serde_json::Value::Null),
+                attempts: row.get(3)?,
+                next_attempt_at: row.get(4)?,
+                backoff_secs: row.get(5)?,
+            })
         })?;
-
         rows.collect()
     }
 
-    /// Get classification for an event type without storing
-    pub fn get_classification(&self, event_type: &str) -> rusqlite::Result<Classification> {
-        Ok(self.get_rule(event_type)?.unwrap_or(Classification::Unclassified))
+    /// Schedule the next attempt of a failed notification
+    ///
+    /// `delivered` lists the backends (or `backend:recipient` for single
+    /// recipients) that already delivered it, so the retry skips them.
+    pub fn schedule_notification_retry(
+        &self,
+        event_id: &str,
+        next_attempt_at: i64,
+        backoff_secs: i64,
+        delivered: &[&str],
+    ) -> rusqlite::Result<()> {
+        let conn = self.write_conn()?;
+        conn.execute(
+            "UPDATE events SET next_attempt_at = ?1, notify_backoff_secs = ?2, notify_delivered = ?3 WHERE id = ?4",
+            params![next_attempt_at, backoff_secs, delivered.join(","), event_id],
+        )?;
+        Ok(())
     }
 
-    /// Store an event, applying classification rules
-    /// Returns the classification applied
-    /// Note: Suppressed events are NOT stored
-    pub fn store_event(&self, event: &UnifiEvent) -> rusqlite::Result<Classification> {
-        // First, look up the classification rule
-        let classification = self
-            .get_rule(&event.event_type)?
-            .unwrap_or(Classification::Unclassified);
-
-        // Don't store suppressed events
-        if classification == Classification::Suppressed {
-            return Ok(classification);
-        }
+    /// Remove any scheduled retry, leaving the notification pending
+    pub fn clear_notification_retry(&self, event_id: &str) -> rusqlite::Result<()> {
+        let conn = self.write_conn()?;
+        conn.execute(
+            "UPDATE events SET next_attempt_at = NULL, notify_backoff_secs = NULL WHERE id = ?1",
+            params![event_id],
+        )?;
+        Ok(())
+    }
 
+    /// Backends (or `backend:recipient`) that already delivered an event's notification
+    pub fn get_delivered_backends(&self, event_id: &str) -> rusqlite::Result<Vec<String>> {
         let conn = self.conn.lock().unwrap();
-        let now = chrono::Utc::now().timestamp();
-        let payload = serde_json::to_string(&event.raw).unwrap_or_default();
-        let severity = event.severity.map(|s| format!("{:?}", s).to_lowercase());
+        let delivered: Option<String> = conn
+            .query_row(
+                "SELECT notify_delivered FROM events WHERE id = ?1",
+                params![event_id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .flatten();
+        Ok(delivered
+            .map(|d| d.split(',').filter(|id| !id.is_empty()).map(str::to_string).collect())
+            .unwrap_or_default())
+    }
 
-        conn.execute(
-            r#"
-            INSERT OR IGNORE INTO events
-            (id, source, event_type, severity, payload, summary, timestamp, classification, created_at)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
-            "#,
-            params![
-                event.id,
-                event.source.to_string(),
-                event.event_type,
-                severity,
-                payload,
-                event.summary,
-                event.timestamp.timestamp(),
-                classification.as_str(),
-                now,
-            ],
+    /// Pin or unpin an event, protecting it from cleanup
+    /// Returns false if the event doesn't exist
+    pub fn set_pinned(&self, event_id: &str, pinned: bool) -> rusqlite::Result<bool> {
+        let conn = self.write_conn()?;
+        let rows = conn.execute(
+            "UPDATE events SET pinned = ?1 WHERE id = ?2",
+            params![pinned as i32, event_id],
         )?;
+        Ok(rows > 0)
+    }
 
-        debug!(
-            id = event.id,
-            event_type = event.event_type,
-            classification = classification.as_str(),
-            "Event stored"
-        );
+    /// Apply `action` to every event matching `filter` in one transaction
+    /// Returns the number of events changed or deleted
+    pub fn bulk_update_events(&self, filter: &EventFilter, action: BulkEventAction) -> rusqlite::Result<usize> {
+        let mut conn = self.write_conn()?;
 
-        Ok(classification)
-    }
+        let mut matching = format!("SELECT id FROM {} WHERE 1=1", self.events_source());
+        let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+        filter.push_sql(&mut matching, &mut params_vec);
+
+        let sql = match action {
+            BulkEventAction::Acknowledge => {
+                params_vec.insert(0, Box::new(chrono::Utc::now().timestamp()));
+                format!(
+                    "UPDATE events SET acknowledged_at = ? WHERE acknowledged_at IS NULL AND id IN ({})",
+                    matching
+                )
+            }
+            BulkEventAction::SetClassification(classification) => {
+                params_vec.insert(0, Box::new(classification.as_str()));
+                format!(
+                    "UPDATE events SET classification = ?1, \
+                     notified = CASE WHEN ?1 = 'notify' THEN 1 ELSE notified END \
+                     WHERE classification <> ?1 AND id IN ({})",
+                    matching
+                )
+            }
+            BulkEventAction::Delete => {
+                format!("DELETE FROM events WHERE pinned = 0 AND id IN ({})", matching)
+            }
+        };
 
-    /// Get events that need notification (notify classification, not yet notified)
-    pub fn get_pending_notifications(&self) -> rusqlite::Result<Vec<StoredEvent>> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            r#"
-            SELECT id, source, event_type, severity, payload, summary, timestamp,
-                   classification, notified, notify_attempts, created_at
-            FROM events
-            WHERE classification = 'notify' AND notified = 0
-            ORDER BY timestamp ASC
-            "#,
-        )?;
+        let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
 
-        let rows = stmt.query_map([], |row| Self::row_to_stored_event(row))?;
-        rows.collect()
+        let tx = conn.transaction()?;
+        let affected = tx.execute(&sql, params_refs.as_slice())?;
+        tx.commit()?;
+
+        info!(?action, affected, "Bulk event update applied");
+        Ok(affected)
     }
 
     /// Mark an event as notified
     pub fn mark_notified(&self, event_id: &str) -> rusqlite::Result<()> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.write_conn()?;
         conn.execute(
-            "UPDATE events SET notified = 1 WHERE id = ?1",
+            "UPDATE events SET notified = 1, next_attempt_at = NULL WHERE id = ?1",
             params![event_id],
         )?;
         debug!(event_id, "Event marked as notified");
@@ -333,7 +1574,7 @@ impl Database {
 
     /// Increment notify attempts for an event
     pub fn increment_notify_attempts(&self, event_id: &str) -> rusqlite::Result<i32> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.write_conn()?;
         conn.execute(
             "UPDATE events SET notify_attempts = notify_attempts + 1 WHERE id = ?1",
             params![event_id],
@@ -375,7 +1616,7 @@ impl Database {
 
     /// Set last update ID for a source
     pub fn set_last_update_id(&self, source: &str, update_id: &str) -> rusqlite::Result<()> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.write_conn()?;
         let now = chrono::Utc::now().timestamp();
 
         conn.execute(
@@ -395,7 +1636,7 @@ impl Database {
 
     /// Clear last update ID for a source (used when saved ID becomes invalid)
     pub fn clear_last_update_id(&self, source: &str) -> rusqlite::Result<()> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.write_conn()?;
         conn.execute(
             "DELETE FROM sync_state WHERE source = ?1",
             params![source],
@@ -404,128 +1645,251 @@ impl Database {
         Ok(())
     }
 
-    /// Query events with filters (supports multiple classifications and event types)
+    /// Get a setting's raw value
+    pub fn get_setting(&self, key: &str) -> rusqlite::Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            params![key],
+            |row| row.get(0),
+        )
+        .optional()
+    }
+
+    /// Set a setting's raw value
+    pub fn set_setting(&self, key: &str, value: &str) -> rusqlite::Result<()> {
+        let conn = self.write_conn()?;
+        let now = chrono::Utc::now().timestamp();
+
+        conn.execute(
+            r#"
+            INSERT INTO settings (key, value, updated_at)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT(key) DO UPDATE SET
+                value = excluded.value,
+                updated_at = excluded.updated_at
+            "#,
+            params![key, value, now],
+        )?;
+
+        debug!(key, value, "Setting updated");
+        Ok(())
+    }
+
+    /// Get a boolean setting (`None` if unset or not a boolean)
+    pub fn get_bool_setting(&self, key: &str) -> rusqlite::Result<Option<bool>> {
+        Ok(self.get_setting(key)?.and_then(|v| v.parse().ok()))
+    }
+
+    /// Get an integer setting (`None` if unset or not an integer)
+    pub fn get_int_setting(&self, key: &str) -> rusqlite::Result<Option<i64>> {
+        Ok(self.get_setting(key)?.and_then(|v| v.parse().ok()))
+    }
+
+    /// Whether the notification kill switch is on
+    pub fn notifications_muted(&self) -> rusqlite::Result<bool> {
+        Ok(self.get_bool_setting(settings::NOTIFICATIONS_MUTED)?.unwrap_or(false))
+    }
+
+    /// Turn the notification kill switch on or off
+    pub fn set_notifications_muted(&self, muted: bool) -> rusqlite::Result<()> {
+        self.set_setting(settings::NOTIFICATIONS_MUTED, &muted.to_string())
+    }
+
+    /// Query events matching a filter, newest first
     pub fn query_events(
         &self,
-        classifications: &[Classification],
-        event_types: &[&str],
-        search: Option<&str>,
+        filter: &EventFilter,
+        limit: usize,
+        offset: usize,
+    ) -> rusqlite::Result<Vec<StoredEvent>> {
+        self.query_events_ordered(filter, "timestamp DESC, id DESC", limit, offset)
+    }
+
+    /// Notify events that are neither acknowledged nor notified yet, oldest
+    /// first: the queue of events still needing an operator's attention
+    pub fn actionable_events(&self, limit: usize, offset: usize) -> rusqlite::Result<Vec<StoredEvent>> {
+        let filter = EventFilter {
+            classifications: vec![Classification::Notify],
+            acknowledged: Some(false),
+            notified: Some(false),
+            ..Default::default()
+        };
+        self.query_events_ordered(&filter, "timestamp ASC, id ASC", limit, offset)
+    }
+
+    fn query_events_ordered(
+        &self,
+        filter: &EventFilter,
+        order_by: &str,
         limit: usize,
         offset: usize,
     ) -> rusqlite::Result<Vec<StoredEvent>> {
         let conn = self.conn.lock().unwrap();
 
-        let mut sql = String::from(
+        let mut sql = format!(
             r#"
             SELECT id, source, event_type, severity, payload, summary, timestamp,
-                   classification, notified, notify_attempts, created_at
-            FROM events
+                   classification, notified, notify_attempts, created_at, pinned, acknowledged_at
+            FROM {}
             WHERE 1=1
             "#,
+            self.events_source()
         );
 
         let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+        filter.push_sql(&mut sql, &mut params_vec);
 
-        // Multiple classifications (OR within)
-        if !classifications.is_empty() {
-            let placeholders: Vec<&str> = classifications.iter().map(|_| "?").collect();
-            sql.push_str(&format!(" AND classification IN ({})", placeholders.join(",")));
-            for c in classifications {
-                params_vec.push(Box::new(c.as_str().to_string()));
-            }
-        }
-
-        // Multiple event types (OR within)
-        if !event_types.is_empty() {
-            let placeholders: Vec<&str> = event_types.iter().map(|_| "?").collect();
-            sql.push_str(&format!(" AND event_type IN ({})", placeholders.join(",")));
-            for et in event_types {
-                params_vec.push(Box::new(et.to_string()));
-            }
-        }
-
-        if let Some(q) = search {
-            // Search across event_type, summary, source, and payload (case-insensitive)
-            sql.push_str(" AND (event_type LIKE ? OR summary LIKE ? OR source LIKE ? OR payload LIKE ?)");
-            let pattern = format!("%{}%", q);
-            params_vec.push(Box::new(pattern.clone()));
-            params_vec.push(Box::new(pattern.clone()));
-            params_vec.push(Box::new(pattern.clone()));
-            params_vec.push(Box::new(pattern));
-        }
-
-        sql.push_str(" ORDER BY timestamp DESC, id DESC LIMIT ? OFFSET ?");
+        sql.push_str(&format!(" ORDER BY {} LIMIT ? OFFSET ?", order_by));
         params_vec.push(Box::new(limit as i64));
         params_vec.push(Box::new(offset as i64));
 
         let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
 
         let mut stmt = conn.prepare(&sql)?;
-        let rows = stmt.query_map(params_refs.as_slice(), |row| Self::row_to_stored_event(row))?;
+        let rows = stmt.query_map(params_refs.as_slice(), Self::row_to_stored_event)?;
 
         rows.collect()
     }
 
-    /// Count events matching filters
-    pub fn count_events(
-        &self,
-        classifications: &[Classification],
-        event_types: &[&str],
-        search: Option<&str>,
-    ) -> rusqlite::Result<i64> {
+    /// Count events matching a filter
+    pub fn count_events(&self, filter: &EventFilter) -> rusqlite::Result<i64> {
         let conn = self.conn.lock().unwrap();
 
-        let mut sql = String::from("SELECT COUNT(*) FROM events WHERE 1=1");
+        let mut sql = format!("SELECT COUNT(*) FROM {} WHERE 1=1", self.events_source());
         let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+        filter.push_sql(&mut sql, &mut params_vec);
 
-        if !classifications.is_empty() {
-            let placeholders: Vec<&str> = classifications.iter().map(|_| "?").collect();
-            sql.push_str(&format!(" AND classification IN ({})", placeholders.join(",")));
-            for c in classifications {
-                params_vec.push(Box::new(c.as_str().to_string()));
-            }
+        let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
+
+        conn.query_row(&sql, params_refs.as_slice(), |row| row.get(0))
+    }
+
+    /// Iterate over all stored events (oldest first) without loading them all at once
+    ///
+    /// Events are fetched `batch_size` rows at a time using keyset pagination
+    /// on (timestamp, id), so memory stays bounded regardless of table size.
+    /// This is a blocking iterator; use `stream_events` from async code.
+    pub fn iter_events(&self, batch_size: usize) -> EventIter {
+        EventIter {
+            db: self.clone(),
+            batch_size: batch_size.max(1),
+            cursor: None,
+            buffer: VecDeque::new(),
+            done: false,
         }
+    }
 
-        if !event_types.is_empty() {
-            let placeholders: Vec<&str> = event_types.iter().map(|_| "?").collect();
-            sql.push_str(&format!(" AND event_type IN ({})", placeholders.join(",")));
-            for et in event_types {
-                params_vec.push(Box::new(et.to_string()));
+    /// Stream all stored events (oldest first), paging on a blocking thread
+    pub fn stream_events(
+        &self,
+        batch_size: usize,
+    ) -> tokio_stream::wrappers::ReceiverStream<rusqlite::Result<StoredEvent>> {
+        let (tx, rx) = tokio::sync::mpsc::channel(batch_size.max(1));
+        let iter = self.iter_events(batch_size);
+        tokio::task::spawn_blocking(move || {
+            for event in iter {
+                if tx.blocking_send(event).is_err() {
+                    break;
+                }
             }
-        }
+        });
+        tokio_stream::wrappers::ReceiverStream::new(rx)
+    }
 
-        if let Some(q) = search {
-            sql.push_str(" AND (event_type LIKE ? OR summary LIKE ? OR source LIKE ? OR payload LIKE ?)");
-            let pattern = format!("%{}%", q);
-            params_vec.push(Box::new(pattern.clone()));
-            params_vec.push(Box::new(pattern.clone()));
-            params_vec.push(Box::new(pattern.clone()));
-            params_vec.push(Box::new(pattern));
-        }
+    /// Fetch the next page of events after the (timestamp, id) cursor
+    fn events_after(
+        &self,
+        cursor: Option<&(i64, String)>,
+        limit: usize,
+    ) -> rusqlite::Result<Vec<StoredEvent>> {
+        let conn = self.conn.lock().unwrap();
+        let (after_ts, after_id) = match cursor {
+            Some((ts, id)) => (Some(*ts), Some(id.as_str())),
+            None => (None, None),
+        };
 
-        let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
+        let mut stmt = conn.prepare(&format!(
+            r#"
+            SELECT id, source, event_type, severity, payload, summary, timestamp,
+                   classification, notified, notify_attempts, created_at, pinned, acknowledged_at
+            FROM {}
+            WHERE ?1 IS NULL OR timestamp > ?1 OR (timestamp = ?1 AND id > ?2)
+            ORDER BY timestamp ASC, id ASC
+            LIMIT ?3
+            "#,
+            self.events_source()
+        ))?;
 
-        conn.query_row(&sql, params_refs.as_slice(), |row| row.get(0))
+        let rows = stmt.query_map(params![after_ts, after_id, limit as i64], Self::row_to_stored_event)?;
+        rows.collect()
+    }
+
+    /// Non-suppressed events stored at or after `since` (unix seconds), oldest first
+    ///
+    /// Used to replay events an SSE subscriber missed after falling behind.
+    pub fn events_created_since(&self, since: i64, limit: usize) -> rusqlite::Result<Vec<StoredEvent>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            r#"
+            SELECT id, source, event_type, severity, payload, summary, timestamp,
+                   classification, notified, notify_attempts, created_at, pinned, acknowledged_at
+            FROM {}
+            WHERE created_at >= ?1 AND classification != 'suppressed'
+            ORDER BY created_at ASC, id ASC
+            LIMIT ?2
+            "#,
+            self.events_source()
+        ))?;
+
+        let rows = stmt.query_map(params![since, limit as i64], Self::row_to_stored_event)?;
+        rows.collect()
     }
 
     /// Get distinct event types with counts and their classification
     pub fn get_event_type_summary(&self) -> rusqlite::Result<Vec<EventTypeSummary>> {
+        self.get_event_type_summary_between(None, None)
+    }
+
+    /// Event type summary limited to events with `since <= timestamp < until`
+    pub fn get_event_type_summary_between(
+        &self,
+        since: Option<i64>,
+        until: Option<i64>,
+    ) -> rusqlite::Result<Vec<EventTypeSummary>> {
+        self.event_type_summary("timestamp", since, until)
+    }
+
+    /// Per-type summary of events stored at or after `created_since`, so that
+    /// backfilled history (old timestamps, recent storage) counts as new
+    pub fn get_event_type_summary_created_since(&self, created_since: i64) -> rusqlite::Result<Vec<EventTypeSummary>> {
+        self.event_type_summary("created_at", Some(created_since), None)
+    }
+
+    /// Summary bounded on `column` (`timestamp` or `created_at`)
+    fn event_type_summary(
+        &self,
+        column: &'static str,
+        since: Option<i64>,
+        until: Option<i64>,
+    ) -> rusqlite::Result<Vec<EventTypeSummary>> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
+        let mut stmt = conn.prepare(&format!(
             r#"
             SELECT
                 e.event_type,
                 COUNT(*) as count,
                 MAX(e.timestamp) as latest,
-                COALESCE(r.classification, 'unclassified') as classification
+                {classification} as classification
             FROM events e
-            LEFT JOIN event_type_rules r ON e.event_type = r.event_type
+            WHERE (?1 IS NULL OR e.{column} >= ?1) AND (?2 IS NULL OR e.{column} < ?2)
             GROUP BY e.event_type
             ORDER BY latest DESC
             "#,
-        )?;
+            classification = matched_classification_sql("e.event_type"),
+        ))?;
 
-        let rows = stmt.query_map([], |row| {
+        let rows = stmt.query_map(params![since, until], |row| {
             Ok(EventTypeSummary {
                 event_type: row.get(0)?,
                 count: row.get(1)?,
@@ -538,15 +1902,20 @@ impl Database {
         rows.collect()
     }
 
-    /// Get the current database file size in bytes
+    /// Get the space used by data in bytes
+    ///
+    /// Free pages left by deletes aren't counted: they are reused by later
+    /// writes, and a VACUUM returns them to the filesystem. So a cleanup whose
+    /// VACUUM was skipped or timed out still sees the space it freed.
     pub fn get_size_bytes(&self) -> rusqlite::Result<u64> {
         let conn = self.conn.lock().unwrap();
         let page_count: u64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+        let freelist_count: u64 = conn.query_row("PRAGMA freelist_count", [], |row| row.get(0))?;
         let page_size: u64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
-        Ok(page_count * page_size)
+        Ok(page_count.saturating_sub(freelist_count) * page_size)
     }
 
-    /// Get the current database file size in MB
+    /// Get the space used by data in MB (see `get_size_bytes`)
     pub fn get_size_mb(&self) -> rusqlite::Result<f64> {
         let bytes = self.get_size_bytes()?;
         Ok(bytes as f64 / (1024.0 * 1024.0))
@@ -559,7 +1928,7 @@ impl Database {
     }
 
     /// Delete oldest events to bring database under size limit
-    /// Returns cleanup result with stats
+    /// Pinned events are kept. Returns cleanup result with stats
     pub fn cleanup_by_size(&self, max_size_mb: f64) -> rusqlite::Result<CleanupResult> {
         let size_before_mb = self.get_size_mb()?;
 
@@ -568,6 +1937,7 @@ impl Database {
                 deleted_events: 0,
                 size_before_mb,
                 size_after_mb: size_before_mb,
+                vacuum: None,
             });
         }
 
@@ -577,6 +1947,7 @@ impl Database {
                 deleted_events: 0,
                 size_before_mb,
                 size_after_mb: size_before_mb,
+                vacuum: None,
             });
         }
 
@@ -596,11 +1967,11 @@ impl Database {
 
         // Delete oldest events
         let deleted = {
-            let conn = self.conn.lock().unwrap();
+            let conn = self.write_conn()?;
             conn.execute(
                 r#"
                 DELETE FROM events WHERE id IN (
-                    SELECT id FROM events ORDER BY timestamp ASC LIMIT ?
+                    SELECT id FROM events WHERE pinned = 0 ORDER BY timestamp ASC LIMIT ?
                 )
                 "#,
                 params![events_to_delete],
@@ -610,10 +1981,7 @@ impl Database {
         debug!(deleted, "Deleted old events");
 
         // Run VACUUM to reclaim space (this actually shrinks the file)
-        {
-            let conn = self.conn.lock().unwrap();
-            conn.execute("VACUUM", [])?;
-        }
+        let vacuum = self.vacuum()?;
 
         let size_after_mb = self.get_size_mb()?;
 
@@ -621,6 +1989,7 @@ impl Database {
             deleted,
             size_before_mb,
             size_after_mb,
+            ?vacuum,
             "Database cleanup complete"
         );
 
@@ -628,26 +1997,116 @@ impl Database {
             deleted_events: deleted,
             size_before_mb,
             size_after_mb,
+            vacuum: Some(vacuum),
         })
     }
 
+    /// VACUUM the database within the limits of the `VacuumPolicy`
+    ///
+    /// File databases are vacuumed on a dedicated connection, so the shared
+    /// one stays free for reads (WAL lets them proceed) while the file is
+    /// rewritten. The VACUUM is interrupted once it exceeds `max_duration`,
+    /// or as soon as a write is pending, so writers only wait for it to roll
+    /// back rather than for the whole VACUUM.
+    pub fn vacuum(&self) -> rusqlite::Result<VacuumOutcome> {
+        if self.read_only {
+            // Fails with the same SQLITE_READONLY error as other writes
+            drop(self.write_conn()?);
+        }
+
+        if self.pending_writes.load(Ordering::SeqCst) > 0 {
+            debug!("Skipping VACUUM, writes are pending");
+            return Ok(VacuumOutcome::SkippedBusy);
+        }
+
+        {
+            let mut last = self.last_vacuum.lock().unwrap();
+            if last.is_some_and(|at| at.elapsed() < self.vacuum_policy.min_interval) {
+                debug!("Skipping VACUUM, one ran recently");
+                return Ok(VacuumOutcome::SkippedRecent);
+            }
+            *last = Some(Instant::now());
+        }
+
+        let dedicated;
+        let shared;
+        let conn: &Connection = match &self.path {
+            Some(path) => {
+                dedicated = Connection::open(path)?;
+                // Don't queue behind another writer; try again next cleanup
+                dedicated.busy_timeout(Duration::from_millis(500))?;
+                &dedicated
+            }
+            None => {
+                shared = self.conn.lock().unwrap();
+                &shared
+            }
+        };
+
+        // Interrupt the VACUUM if it outlives max_duration or a write is
+        // waiting; the watchdog returns whether it gave way to a write
+        let interrupt = conn.get_interrupt_handle();
+        let (done_tx, done_rx) = std::sync::mpsc::channel::<()>();
+        let max_duration = self.vacuum_policy.max_duration;
+        let pending_writes = Arc::clone(&self.pending_writes);
+        let started = Instant::now();
+        let watchdog = std::thread::spawn(move || loop {
+            if !matches!(
+                done_rx.recv_timeout(VACUUM_POLL_INTERVAL),
+                Err(std::sync::mpsc::RecvTimeoutError::Timeout)
+            ) {
+                return false;
+            }
+            if pending_writes.load(Ordering::SeqCst) > 0 {
+                interrupt.interrupt();
+                return true;
+            }
+            if started.elapsed() >= max_duration {
+                interrupt.interrupt();
+                return false;
+            }
+        });
+
+        let result = conn.execute("VACUUM", []);
+        let elapsed = started.elapsed();
+        let _ = done_tx.send(());
+        let yielded = watchdog.join().unwrap_or(false);
+
+        match result {
+            Ok(_) => {
+                info!(duration_ms = elapsed.as_millis() as u64, "VACUUM complete");
+                Ok(VacuumOutcome::Completed(elapsed))
+            }
+            Err(rusqlite::Error::SqliteFailure(e, _))
+                if e.code == rusqlite::ErrorCode::OperationInterrupted && yielded =>
+            {
+                // Didn't finish, so don't hold off the next attempt
+                *self.last_vacuum.lock().unwrap() = None;
+                debug!(duration_ms = elapsed.as_millis() as u64, "VACUUM interrupted to let writes through");
+                Ok(VacuumOutcome::SkippedBusy)
+            }
+            Err(rusqlite::Error::SqliteFailure(e, _)) if e.code == rusqlite::ErrorCode::OperationInterrupted => {
+                tracing::warn!(duration_ms = elapsed.as_millis() as u64, "VACUUM exceeded its time limit and was interrupted");
+                Ok(VacuumOutcome::TimedOut(elapsed))
+            }
+            Err(rusqlite::Error::SqliteFailure(e, _))
+                if matches!(e.code, rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked) =>
+            {
+                // Didn't run, so don't hold off the next attempt
+                *self.last_vacuum.lock().unwrap() = None;
+                debug!("Skipping VACUUM, database is busy");
+                Ok(VacuumOutcome::SkippedBusy)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
     fn row_to_stored_event(row: &rusqlite::Row) -> rusqlite::Result<StoredEvent> {
         let source_str: String = row.get(1)?;
-        let source = match source_str.as_str() {
-            "protect" => EventSource::Protect,
-            "network" => EventSource::Network,
-            "system" => EventSource::System,
-            _ => EventSource::System,
-        };
+        let source = EventSource::from_str(&source_str).unwrap_or(EventSource::System);
 
         let severity_str: Option<String> = row.get(3)?;
-        let severity = severity_str.and_then(|s| match s.as_str() {
-            "info" => Some(Severity::Info),
-            "warning" => Some(Severity::Warning),
-            "error" => Some(Severity::Error),
-            "critical" => Some(Severity::Critical),
-            _ => None,
-        });
+        let severity = severity_str.and_then(|s| Severity::from_str(&s));
 
         let payload_str: String = row.get(4)?;
         let payload = serde_json::from_str(&payload_str).unwrap_or(serde_json::Value::Null);
@@ -668,9 +2127,74 @@ impl Database {
             notified: row.get::<_, i32>(8)? != 0,
             notify_attempts: row.get(9)?,
             created_at: row.get(10)?,
+            pinned: row.get::<_, i32>(11)? != 0,
+            acknowledged_at: row.get(12)?,
         })
     }
 
+    // ==================== Entity State Methods ====================
+
+    /// Record a compacted event as the current state of its entity
+    /// Returns how many times the entity has been seen for this event type
+    pub fn upsert_entity_state(&self, event: &UnifiEvent, entity_id: &str) -> rusqlite::Result<i64> {
+        let conn = self.write_conn()?;
+        let payload = serde_json::to_string(&event.raw).unwrap_or_default();
+        conn.query_row(
+            r#"
+            INSERT INTO entity_state (event_type, entity_id, source, summary, payload, first_seen, last_seen)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)
+            ON CONFLICT(event_type, entity_id) DO UPDATE SET
+                source = CASE WHEN excluded.last_seen >= last_seen THEN excluded.source ELSE source END,
+                summary = CASE WHEN excluded.last_seen >= last_seen THEN excluded.summary ELSE summary END,
+                payload = CASE WHEN excluded.last_seen >= last_seen THEN excluded.payload ELSE payload END,
+                first_seen = MIN(first_seen, excluded.first_seen),
+                last_seen = MAX(last_seen, excluded.last_seen),
+                change_count = change_count + 1
+            RETURNING change_count
+            "#,
+            params![
+                event.event_type,
+                entity_id,
+                event.source.to_string(),
+                event.summary,
+                payload,
+                event.timestamp.timestamp(),
+            ],
+            |row| row.get(0),
+        )
+    }
+
+    /// Get compacted entity states, most recently seen first
+    pub fn get_entity_states(&self, event_type: Option<&str>, limit: usize) -> rusqlite::Result<Vec<EntityState>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT event_type, entity_id, source, summary, payload, first_seen, last_seen, change_count
+            FROM entity_state
+            WHERE ?1 IS NULL OR event_type = ?1
+            ORDER BY last_seen DESC
+            LIMIT ?2
+            "#,
+        )?;
+
+        let rows = stmt.query_map(params![event_type, limit as i64], |row| {
+            let source_str: String = row.get(2)?;
+            let payload_str: String = row.get(4)?;
+            Ok(EntityState {
+                event_type: row.get(0)?,
+                entity_id: row.get(1)?,
+                source: EventSource::from_str(&source_str).unwrap_or(EventSource::System),
+                summary: row.get(3)?,
+                payload: serde_json::from_str(&payload_str).unwrap_or(serde_json::Value::Null),
+                first_seen: row.get(5)?,
+                last_seen: row.get(6)?,
+                change_count: row.get(7)?,
+            })
+        })?;
+
+        rows.collect()
+    }
+
     // ==================== Authentication Methods ====================
 
     /// Check if any passkeys are registered
@@ -681,15 +2205,19 @@ impl Database {
     }
 
     /// Store a passkey credential
-    pub fn store_passkey(&self, id: &str, credential: &[u8], name: Option<&str>) -> rusqlite::Result<()> {
-        let conn = self.conn.lock().unwrap();
+    /// Returns false (and stores nothing) if a passkey with this credential ID already exists
+    pub fn store_passkey(&self, id: &str, credential: &[u8], name: Option<&str>) -> rusqlite::Result<bool> {
+        let conn = self.write_conn()?;
         let now = chrono::Utc::now().timestamp();
-        conn.execute(
-            "INSERT INTO passkeys (id, credential, name, created_at) VALUES (?1, ?2, ?3, ?4)",
+        let rows = conn.execute(
+            "INSERT INTO passkeys (id, credential, name, created_at) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(id) DO NOTHING",
             params![id, credential, name, now],
         )?;
-        debug!(id, "Passkey stored");
-        Ok(())
+        if rows > 0 {
+            debug!(id, "Passkey stored");
+        }
+        Ok(rows > 0)
     }
 
     /// Get a passkey credential by ID
@@ -729,7 +2257,7 @@ impl Database {
 
     /// Delete a passkey by ID
     pub fn delete_passkey(&self, id: &str) -> rusqlite::Result<bool> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.write_conn()?;
         let rows = conn.execute("DELETE FROM passkeys WHERE id = ?1", params![id])?;
         Ok(rows > 0)
     }
@@ -743,7 +2271,7 @@ impl Database {
             .map(char::from)
             .collect();
 
-        let conn = self.conn.lock().unwrap();
+        let conn = self.write_conn()?;
         let now = chrono::Utc::now().timestamp();
         let expires_at = now + (expiry_days * 24 * 60 * 60);
 
@@ -757,12 +2285,16 @@ impl Database {
     }
 
     /// Validate a session ID (returns true if valid and not expired)
+    ///
+    /// Sessions are persisted, so they use the wall clock. A session created
+    /// "in the future" means the clock has since stepped backward; it is
+    /// rejected so its lifetime can't be stretched by the size of the step.
     pub fn validate_session(&self, session_id: &str) -> rusqlite::Result<bool> {
         let conn = self.conn.lock().unwrap();
         let now = chrono::Utc::now().timestamp();
         let count: i64 = conn.query_row(
-            "SELECT COUNT(*) FROM sessions WHERE id = ?1 AND expires_at > ?2",
-            params![session_id, now],
+            "SELECT COUNT(*) FROM sessions WHERE id = ?1 AND expires_at > ?2 AND created_at <= ?2 + ?3",
+            params![session_id, now, CLOCK_SKEW_TOLERANCE_SECS],
             |row| row.get(0),
         )?;
         Ok(count > 0)
@@ -770,23 +2302,26 @@ impl Database {
 
     /// Delete a session
     pub fn delete_session(&self, session_id: &str) -> rusqlite::Result<()> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.write_conn()?;
         conn.execute("DELETE FROM sessions WHERE id = ?1", params![session_id])?;
         Ok(())
     }
 
     /// Delete all sessions (used when all passkeys are deleted)
     pub fn delete_all_sessions(&self) -> rusqlite::Result<()> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.write_conn()?;
         conn.execute("DELETE FROM sessions", [])?;
         Ok(())
     }
 
     /// Clean up expired sessions
     pub fn cleanup_expired_sessions(&self) -> rusqlite::Result<usize> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.write_conn()?;
         let now = chrono::Utc::now().timestamp();
-        let rows = conn.execute("DELETE FROM sessions WHERE expires_at <= ?1", params![now])?;
+        let rows = conn.execute(
+            "DELETE FROM sessions WHERE expires_at <= ?1",
+            params![now],
+        )?;
         if rows > 0 {
             debug!(count = rows, "Cleaned up expired sessions");
         }
@@ -802,7 +2337,7 @@ impl Database {
 
     /// Set setup token (replaces any existing)
     pub fn set_setup_token(&self, token: &str) -> rusqlite::Result<()> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.write_conn()?;
         let now = chrono::Utc::now().timestamp();
         conn.execute("DELETE FROM setup_token", [])?;
         conn.execute(
@@ -814,7 +2349,7 @@ impl Database {
 
     /// Delete setup token
     pub fn delete_setup_token(&self) -> rusqlite::Result<()> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.write_conn()?;
         conn.execute("DELETE FROM setup_token", [])?;
         Ok(())
     }
@@ -844,7 +2379,7 @@ impl Database {
             .collect::<Vec<_>>()
             .join("-");
 
-        let conn = self.conn.lock().unwrap();
+        let conn = self.write_conn()?;
         let now = chrono::Utc::now().timestamp();
         let expires_at = now + expiry_secs;
 
@@ -858,14 +2393,16 @@ impl Database {
     }
 
     /// Validate and consume an invite token (returns true if valid)
+    ///
+    /// Like sessions, tokens created after "now" are rejected (clock stepped backward).
     pub fn validate_invite_token(&self, token: &str) -> rusqlite::Result<bool> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.write_conn()?;
         let now = chrono::Utc::now().timestamp();
 
         // Check if valid
         let count: i64 = conn.query_row(
-            "SELECT COUNT(*) FROM invite_tokens WHERE token = ?1 AND expires_at > ?2",
-            params![token, now],
+            "SELECT COUNT(*) FROM invite_tokens WHERE token = ?1 AND expires_at > ?2 AND created_at <= ?2 + ?3",
+            params![token, now, CLOCK_SKEW_TOLERANCE_SECS],
             |row| row.get(0),
         )?;
 
@@ -880,9 +2417,12 @@ impl Database {
 
     /// Clean up expired invite tokens
     pub fn cleanup_expired_invite_tokens(&self) -> rusqlite::Result<usize> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.write_conn()?;
         let now = chrono::Utc::now().timestamp();
-        let rows = conn.execute("DELETE FROM invite_tokens WHERE expires_at <= ?1", params![now])?;
+        let rows = conn.execute(
+            "DELETE FROM invite_tokens WHERE expires_at <= ?1",
+            params![now],
+        )?;
         Ok(rows)
     }
 
@@ -897,7 +2437,7 @@ impl Database {
         status: &str,
         error_message: Option<&str>,
     ) -> rusqlite::Result<()> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.write_conn()?;
         let now = chrono::Utc::now().timestamp();
         conn.execute(
             r#"
@@ -914,9 +2454,11 @@ impl Database {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
             r#"
-            SELECT id, event_id, event_type, event_summary, status, error_message, created_at
-            FROM notification_log
-            ORDER BY created_at DESC
+            SELECT l.id, l.event_id, l.event_type, l.event_summary, l.status, l.error_message, l.created_at,
+                   e.payload
+            FROM notification_log l
+            LEFT JOIN events e ON e.id = l.event_id
+            ORDER BY l.created_at DESC
             LIMIT ?1
             "#,
         )?;
@@ -930,6 +2472,9 @@ impl Database {
                 status: row.get(4)?,
                 error_message: row.get(5)?,
                 created_at: row.get(6)?,
+                event_payload: row
+                    .get::<_, Option<String>>(7)?
+                    .and_then(|payload| serde_json::from_str(&payload).ok()),
             })
         })?;
 
@@ -938,7 +2483,7 @@ impl Database {
 
     /// Clean up old notification logs (keep last N entries)
     pub fn cleanup_notification_logs(&self, keep_count: usize) -> rusqlite::Result<usize> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.write_conn()?;
         let rows = conn.execute(
             r#"
             DELETE FROM notification_log WHERE id NOT IN (
@@ -954,6 +2499,55 @@ impl Database {
     }
 }
 
+/// Batched iterator over stored events (see `Database::iter_events`)
+pub struct EventIter {
+    db: Database,
+    batch_size: usize,
+    /// (timestamp, id) of the last event fetched
+    cursor: Option<(i64, String)>,
+    buffer: VecDeque<StoredEvent>,
+    done: bool,
+}
+
+impl Iterator for EventIter {
+    type Item = rusqlite::Result<StoredEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buffer.is_empty() && !self.done {
+            match self.db.events_after(self.cursor.as_ref(), self.batch_size) {
+                Ok(batch) => {
+                    if batch.len() < self.batch_size {
+                        self.done = true;
+                    }
+                    if let Some(last) = batch.last() {
+                        self.cursor = Some((last.timestamp, last.id.clone()));
+                    }
+                    self.buffer.extend(batch);
+                }
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+
+        self.buffer.pop_front().map(Ok)
+    }
+}
+
+/// Latest state of an entity for a compacted event type
+#[derive(Debug, Clone)]
+pub struct EntityState {
+    pub event_type: String,
+    pub entity_id: String,
+    pub source: EventSource,
+    pub summary: String,
+    pub payload: serde_json::Value,
+    pub first_seen: i64,
+    pub last_seen: i64,
+    pub change_count: i64,
+}
+
 /// Passkey info for UI display
 #[derive(Debug, Clone)]
 pub struct PasskeyInfo {
@@ -972,6 +2566,8 @@ pub struct NotificationLogEntry {
     pub status: String,
     pub error_message: Option<String>,
     pub created_at: i64,
+    /// Payload of the event, while it's still stored
+    pub event_payload: Option<serde_json::Value>,
 }
 
 /// Summary of an event type for UI display
@@ -989,6 +2585,8 @@ pub struct CleanupResult {
     pub deleted_events: u64,
     pub size_before_mb: f64,
     pub size_after_mb: f64,
+    /// None when no events had to be deleted
+    pub vacuum: Option<VacuumOutcome>,
 }
 
 #[cfg(test)]
@@ -1015,6 +2613,139 @@ mod tests {
         assert_eq!(db.get_rule("test.event").unwrap(), None);
     }
 
+    #[test]
+    fn test_explain_classification() {
+        let db = Database::open_in_memory().unwrap();
+
+        // No rule -> default
+        let (classification, reason) = db.explain_classification("test.event").unwrap();
+        assert_eq!(classification, Classification::Unclassified);
+        assert_eq!(reason, ClassificationReason::Default);
+        assert_eq!(reason.rule(), None);
+
+        // Exact rule
+        db.set_rule("test.event", Classification::Notify).unwrap();
+        let (classification, reason) = db.explain_classification("test.event").unwrap();
+        assert_eq!(classification, Classification::Notify);
+        assert_eq!(reason, ClassificationReason::ExactRule("test.event".to_string()));
+        assert_eq!(reason.as_str(), "exact");
+        assert_eq!(reason.rule(), Some("test.event"));
+
+        // Rule for a different type doesn't match
+        let (_, reason) = db.explain_classification("other.event").unwrap();
+        assert_eq!(reason, ClassificationReason::Default);
+    }
+
+    #[test]
+    fn test_rename_event_type() {
+        let db = Database::open_in_memory().unwrap();
+        store_type(&db, "old-1", "smartDetectZone");
+        store_type(&db, "old-2", "smartDetectZone");
+        store_type(&db, "new-1", "smartDetectObject");
+
+        // Rule moves over when the new name has none
+        db.set_rule("smartDetectZone", Classification::Notify).unwrap();
+        assert_eq!(db.rename_event_type("smartDetectZone", "smartDetectObject").unwrap(), 2);
+        assert_eq!(db.get_rule("smartDetectZone").unwrap(), None);
+        assert_eq!(db.get_rule("smartDetectObject").unwrap(), Some(Classification::Notify));
+        let events = db.query_events(&EventFilter::default(), 10, 0).unwrap();
+        assert_eq!(events.len(), 3);
+        assert!(events.iter().all(|e| e.event_type == "smartDetectObject"
+            && e.classification == Classification::Notify));
+
+        // The target's existing rule takes precedence
+        store_type(&db, "old-3", "motion");
+        db.set_rule("motion", Classification::Notify).unwrap();
+        db.set_rule("smartDetectObject", Classification::Ignored).unwrap();
+        assert_eq!(db.rename_event_type("motion", "smartDetectObject").unwrap(), 1);
+        assert_eq!(db.get_rule("motion").unwrap(), None);
+        assert_eq!(db.get_rule("smartDetectObject").unwrap(), Some(Classification::Ignored));
+        let events = db.query_events(&EventFilter::default(), 10, 0).unwrap();
+        assert_eq!(events.len(), 4);
+        assert!(events.iter().all(|e| e.classification == Classification::Ignored));
+    }
+
+    fn store_type(db: &Database, id: &str, event_type: &str) {
+        db.store_event(&UnifiEvent {
+            id: id.to_string(),
+            timestamp: chrono::Utc::now(),
+            source: EventSource::Protect,
+            event_type: event_type.to_string(),
+            summary: "Test".to_string(),
+            severity: None,
+            raw: serde_json::json!({}),
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_new_event_type_detection() {
+        let db = Database::open_in_memory().unwrap();
+        let event = |id: &str, event_type: &str| UnifiEvent {
+            id: id.to_string(),
+            timestamp: chrono::Utc::now(),
+            source: EventSource::Network,
+            event_type: event_type.to_string(),
+            summary: "Test".to_string(),
+            severity: None,
+            raw: serde_json::json!({}),
+        };
+
+        assert!(db.store_event_detailed(&event("a", "foo.bar")).unwrap().new_event_type);
+        assert!(!db.store_event_detailed(&event("b", "foo.bar")).unwrap().new_event_type);
+
+        // A type the user has already classified isn't new, even unseen
+        db.set_rule("known.*", Classification::Ignored).unwrap();
+        assert!(!db.store_event_detailed(&event("c", "known.type")).unwrap().new_event_type);
+        db.set_rule("muted", Classification::Suppressed).unwrap();
+        assert!(!db.store_event_detailed(&event("d", "muted")).unwrap().new_event_type);
+    }
+
+    #[test]
+    fn test_rule_triggers_reclassify_events() {
+        let db = Database::open_in_memory().unwrap();
+        store_type(&db, "a", "motion");
+        store_type(&db, "b", "ring");
+
+        // Rules written directly (not via set_rule) still reclassify events
+        {
+            let conn = db.conn.lock().unwrap();
+            conn.execute(
+                "INSERT INTO event_type_rules (event_type, classification, created_at, updated_at)
+                 VALUES ('motion', 'notify', 0, 0)",
+                [],
+            )
+            .unwrap();
+        }
+        let notify = db.query_events(&classified(Classification::Notify), 10, 0).unwrap();
+        assert_eq!(notify.len(), 1);
+        assert_eq!(notify[0].id, "a");
+
+        db.set_rule("motion", Classification::Ignored).unwrap();
+        assert_eq!(db.count_events(&classified(Classification::Ignored)).unwrap(), 1);
+
+        db.delete_rule("motion").unwrap();
+        assert_eq!(db.count_events(&classified(Classification::Unclassified)).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_reclassification_uses_event_type_index() {
+        let db = Database::open_in_memory().unwrap();
+        let conn = db.conn.lock().unwrap();
+        let plan: Vec<String> = conn
+            .prepare("EXPLAIN QUERY PLAN UPDATE events SET classification = ?1 WHERE event_type = ?2")
+            .unwrap()
+            .query_map(params!["notify", "motion"], |row| row.get::<_, String>(3))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert!(
+            plan.iter().any(|step| step.contains("INDEX idx_events_event_type")),
+            "unexpected plan: {:?}",
+            plan
+        );
+    }
+
     #[test]
     fn test_store_and_query_events() {
         let db = Database::open_in_memory().unwrap();
@@ -1034,12 +2765,678 @@ mod tests {
         assert_eq!(classification, Classification::Unclassified);
 
         // Query back
-        let events = db.query_events(&[], &[], None, 10, 0).unwrap();
+        let events = db.query_events(&EventFilter::default(), 10, 0).unwrap();
         assert_eq!(events.len(), 1);
         assert_eq!(events[0].id, "test-123");
         assert_eq!(events[0].classification, Classification::Unclassified);
     }
 
+    #[test]
+    fn test_dynamic_classification_mode() {
+        let path = std::env::temp_dir().join(format!("unifi-monitor-test-{}.db", uuid::Uuid::new_v4()));
+
+        let db = Database::open_with_mode(&path, ClassificationMode::Dynamic).unwrap();
+        store_type(&db, "a", "motion");
+        store_type(&db, "b", "ring");
+        db.set_rule("motion", Classification::Notify).unwrap();
+
+        // Queries see the rule, but the stored column is left alone
+        let notify = db.query_events(&classified(Classification::Notify), 10, 0).unwrap();
+        assert_eq!(notify.len(), 1);
+        assert_eq!(notify[0].id, "a");
+        assert_eq!(db.get_pending_notifications().unwrap().len(), 1);
+        let stored: String = db
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("SELECT classification FROM events WHERE id = 'a'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(stored, "unclassified");
+
+        // Read-only handles follow the writer's mode
+        let ro = Database::open_read_only(&path).unwrap();
+        assert_eq!(ro.classification_mode(), ClassificationMode::Dynamic);
+        assert_eq!(ro.count_events(&classified(Classification::Notify)).unwrap(), 1);
+        drop(ro);
+        drop(db);
+
+        // Switching back to stored resyncs the rows
+        let db = Database::open(&path).unwrap();
+        let stored: String = db
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("SELECT classification FROM events WHERE id = 'a'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(stored, "notify");
+        assert_eq!(db.count_events(&classified(Classification::Notify)).unwrap(), 1);
+
+        drop(db);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    #[test]
+    fn test_backup_before_migration() {
+        let dir = std::env::temp_dir().join(format!("unifi-monitor-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        let path = dir.join("monitor.db");
+        let backups = || {
+            let mut names: Vec<String> = std::fs::read_dir(&dir)
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+                .filter(|name| name.ends_with(".bak"))
+                .collect();
+            names.sort();
+            names
+        };
+
+        // Nothing to migrate: no backup
+        let db = Database::open_with_migration_backup(&path, ClassificationMode::Stored, 2).unwrap();
+        store_type(&db, "a", "motion");
+        drop(db);
+        let db = Database::open_with_migration_backup(&path, ClassificationMode::Stored, 2).unwrap();
+        assert!(backups().is_empty());
+
+        // A database from before `notify_delivered` and schema versions existed
+        db.conn
+            .lock()
+            .unwrap()
+            .execute_batch("ALTER TABLE events DROP COLUMN notify_delivered; PRAGMA user_version = 0;")
+            .unwrap();
+        drop(db);
+        for stale in ["monitor.db.20200101-000000.bak", "monitor.db.20200102-000000.bak"] {
+            std::fs::write(dir.join(stale), "").unwrap();
+        }
+        // Files that only look similar are never pruned
+        for unrelated in ["monitor.db.manual.bak", "monitor.db.20200101-000000.old.bak"] {
+            std::fs::write(dir.join(unrelated), "").unwrap();
+        }
+
+        let db = Database::open_with_migration_backup(&path, ClassificationMode::Stored, 2).unwrap();
+        assert_eq!(db.query_events(&EventFilter::default(), 10, 0).unwrap().len(), 1);
+        let names = backups();
+        assert_eq!(names.len(), 4);
+        assert_eq!(names[0], "monitor.db.20200101-000000.old.bak");
+        assert_eq!(names[1], "monitor.db.20200102-000000.bak");
+        assert_eq!(names[3], "monitor.db.manual.bak");
+
+        // The new backup holds the data as it was before the migration
+        let backup = Connection::open(dir.join(&names[2])).unwrap();
+        let count: i64 = backup.query_row("SELECT COUNT(*) FROM events", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 1);
+        assert!(!Database::has_pending_migrations(&db.conn.lock().unwrap()).unwrap());
+        assert!(Database::has_pending_migrations(&backup).unwrap());
+
+        // Triggers from an older schema version are rebuilt
+        db.conn
+            .lock()
+            .unwrap()
+            .execute_batch("DROP TRIGGER trg_rule_match_update; PRAGMA user_version = 0;")
+            .unwrap();
+        drop(db);
+        let db = Database::open_with_mode(&path, ClassificationMode::Stored).unwrap();
+        let rebuilt = db
+            .conn
+            .lock()
+            .unwrap()
+            .prepare("SELECT 1 FROM sqlite_master WHERE type = 'trigger' AND name = 'trg_rule_match_update'")
+            .unwrap()
+            .exists([])
+            .unwrap();
+        assert!(rebuilt);
+
+        drop(db);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    /// Overlapping wildcard rules resolve by priority, then specificity
+    fn check_wildcard_priority(db: &Database) {
+        let classification_of = |id: &str| {
+            db.query_events(&EventFilter::default(), 10, 0)
+                .unwrap()
+                .into_iter()
+                .find(|e| e.id == id)
+                .unwrap()
+                .classification
+        };
+        store_type(db, "a", "camera.update");
+        store_type(db, "b", "camera.motion");
+
+        // Equal priority and specificity: the older rule wins
+        db.set_rule("*.update", Classification::Ignored).unwrap();
+        db.set_rule("camera.*", Classification::Notify).unwrap();
+        assert_eq!(classification_of("a"), Classification::Ignored);
+        assert_eq!(classification_of("b"), Classification::Notify);
+
+        // Priority overrides that, for stored and newly arriving events
+        db.set_rule_with_priority("camera.*", Classification::Notify, Some(10)).unwrap();
+        store_type(db, "c", "camera.update");
+        assert_eq!(classification_of("a"), Classification::Notify);
+        assert_eq!(classification_of("c"), Classification::Notify);
+        let (_, reason) = db.explain_classification("camera.update").unwrap();
+        assert_eq!(reason, ClassificationReason::WildcardRule("camera.*".to_string()));
+        assert_eq!(reason.as_str(), "wildcard");
+
+        // Setting a rule without a priority keeps the existing one
+        db.set_rule("camera.*", Classification::Unclassified).unwrap();
+        assert_eq!(classification_of("a"), Classification::Unclassified);
+        let rule = db.get_all_rules().unwrap().into_iter().find(|r| r.event_type == "camera.*").unwrap();
+        assert_eq!(rule.priority, 10);
+
+        // At equal priority the more specific pattern wins, and an exact rule
+        // beats any pattern
+        db.set_rule_with_priority("camera.up*", Classification::Ignored, Some(10)).unwrap();
+        assert_eq!(classification_of("a"), Classification::Ignored);
+        assert_eq!(classification_of("b"), Classification::Unclassified);
+        db.set_rule_with_priority("camera.update", Classification::Notify, Some(10)).unwrap();
+        assert_eq!(classification_of("a"), Classification::Notify);
+        assert_eq!(
+            db.explain_classification("camera.update").unwrap().1,
+            ClassificationReason::ExactRule("camera.update".to_string())
+        );
+
+        // Removing the winners falls back to the next matching rule
+        for pattern in ["camera.update", "camera.up*", "camera.*"] {
+            db.delete_rule(pattern).unwrap();
+        }
+        assert_eq!(classification_of("a"), Classification::Ignored);
+        assert_eq!(classification_of("b"), Classification::Unclassified);
+        db.delete_rule("*.update").unwrap();
+        assert_eq!(classification_of("a"), Classification::Unclassified);
+    }
+
+    #[test]
+    fn test_wildcard_rule_priority() {
+        check_wildcard_priority(&Database::open_in_memory().unwrap());
+
+        let path = std::env::temp_dir().join(format!("unifi-monitor-test-{}.db", uuid::Uuid::new_v4()));
+        let db = Database::open_with_mode(&path, ClassificationMode::Dynamic).unwrap();
+        check_wildcard_priority(&db);
+        drop(db);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    #[test]
+    fn test_wildcard_rule_only_star_is_special() {
+        for mode in [ClassificationMode::Stored, ClassificationMode::Dynamic] {
+            let path = std::env::temp_dir().join(format!("unifi-monitor-test-{}.db", uuid::Uuid::new_v4()));
+            let db = Database::open_with_mode(&path, mode).unwrap();
+            store_type(&db, "literal", "what?.[x]");
+            store_type(&db, "other", "whatX.x");
+            db.set_rule("what?.[x]*", Classification::Notify).unwrap();
+
+            let classified: Vec<_> = db
+                .query_events(&EventFilter::default(), 10, 0)
+                .unwrap()
+                .into_iter()
+                .map(|e| (e.id, e.classification))
+                .collect();
+            assert!(classified.contains(&("literal".to_string(), Classification::Notify)));
+            assert!(classified.contains(&("other".to_string(), Classification::Unclassified)));
+            assert_eq!(db.matching_rule("whatX.x").unwrap(), None);
+
+            drop(db);
+            for suffix in ["", "-wal", "-shm"] {
+                let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+            }
+        }
+    }
+
+    #[test]
+    fn test_reclassified_event_drops_its_retry() {
+        for mode in [ClassificationMode::Stored, ClassificationMode::Dynamic] {
+            let path = std::env::temp_dir().join(format!("unifi-monitor-test-{}.db", uuid::Uuid::new_v4()));
+            let db = Database::open_with_mode(&path, mode).unwrap();
+            db.set_rule("motion", Classification::Notify).unwrap();
+            let retry_at = chrono::Utc::now().timestamp() + 60;
+            for id in ["a", "b"] {
+                store_type(&db, id, "motion");
+                db.schedule_notification_retry(id, retry_at, 60, &[]).unwrap();
+            }
+            assert_eq!(db.next_notification_retry_at().unwrap(), Some(retry_at));
+
+            db.set_rule("motion", Classification::Ignored).unwrap();
+            assert_eq!(db.next_notification_retry_at().unwrap(), None);
+            assert!(db.get_scheduled_retries().unwrap().is_empty());
+
+            // Back to notify, the events are still pending but no longer scheduled
+            db.set_rule("motion", Classification::Notify).unwrap();
+            assert_eq!(db.next_notification_retry_at().unwrap(), None);
+
+            drop(db);
+            for suffix in ["", "-wal", "-shm"] {
+                let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+            }
+        }
+
+        // Bulk reclassification in stored mode
+        let db = Database::open_in_memory().unwrap();
+        db.set_rule("motion", Classification::Notify).unwrap();
+        store_type(&db, "a", "motion");
+        db.schedule_notification_retry("a", chrono::Utc::now().timestamp() + 60, 60, &[]).unwrap();
+        db.bulk_update_events(&EventFilter::default(), BulkEventAction::SetClassification(Classification::Ignored))
+            .unwrap();
+        assert_eq!(db.next_notification_retry_at().unwrap(), None);
+    }
+
+    #[test]
+    fn test_pinned_event_survives_cleanup() {
+        let db = Database::open_in_memory().unwrap();
+        for i in 0..20 {
+            store_type(&db, &format!("evt-{}", i), "motion");
+        }
+        assert!(db.set_pinned("evt-0", true).unwrap());
+        assert!(!db.set_pinned("missing", true).unwrap());
+
+        // A tiny limit deletes every event that cleanup is allowed to touch
+        let result = db.cleanup_by_size(0.001).unwrap();
+        assert_eq!(result.deleted_events, 19);
+
+        let remaining = db.query_events(&EventFilter::default(), 50, 0).unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, "evt-0");
+        assert!(remaining[0].pinned);
+
+        let pinned = EventFilter {
+            pinned: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(db.count_events(&pinned).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_vacuum_skips_recent_run() {
+        let path = std::env::temp_dir().join(format!("unifi-monitor-test-{}.db", uuid::Uuid::new_v4()));
+
+        let db = Database::open(&path).unwrap();
+        store_type(&db, "a", "motion");
+        assert!(matches!(db.vacuum().unwrap(), VacuumOutcome::Completed(_)));
+        assert_eq!(db.vacuum().unwrap(), VacuumOutcome::SkippedRecent);
+
+        let db = db
+            .with_vacuum_policy(VacuumPolicy {
+                min_interval: Duration::ZERO,
+                ..VacuumPolicy::default()
+            });
+        assert!(matches!(db.vacuum().unwrap(), VacuumOutcome::Completed(_)));
+
+        drop(db);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    #[test]
+    fn test_vacuum_gives_way_to_writes() {
+        let path = std::env::temp_dir().join(format!("unifi-monitor-test-{}.db", uuid::Uuid::new_v4()));
+        let db = Database::open(&path).unwrap().with_vacuum_policy(VacuumPolicy {
+            min_interval: Duration::ZERO,
+            max_duration: Duration::from_secs(600),
+        });
+
+        // A pending write keeps the VACUUM from starting
+        {
+            let _writing = db.write_conn().unwrap();
+            assert_eq!(db.vacuum().unwrap(), VacuumOutcome::SkippedBusy);
+        }
+
+        // Enough data that the VACUUM is still running when the write arrives
+        {
+            let conn = db.conn.lock().unwrap();
+            let padding = "x".repeat(4000);
+            let tx = conn.unchecked_transaction().unwrap();
+            for i in 0..20_000 {
+                tx.execute(
+                    "INSERT INTO events (id, source, event_type, payload, summary, timestamp, classification, created_at)
+                     VALUES (?1, 'network', 'motion', ?2, 'Test', 0, 'unclassified', 0)",
+                    params![format!("bulk-{}", i), padding],
+                )
+                .unwrap();
+            }
+            tx.commit().unwrap();
+        }
+
+        let vacuuming = {
+            let db = db.clone();
+            std::thread::spawn(move || db.vacuum().unwrap())
+        };
+        std::thread::sleep(Duration::from_millis(50));
+        let started = Instant::now();
+        store_type(&db, "during", "motion");
+        assert!(started.elapsed() < Duration::from_secs(5), "write waited {:?}", started.elapsed());
+
+        let outcome = vacuuming.join().unwrap();
+        assert!(matches!(outcome, VacuumOutcome::SkippedBusy | VacuumOutcome::Completed(_)));
+        if outcome == VacuumOutcome::SkippedBusy {
+            // Gave way, so the next cleanup may try again
+            assert!(db.last_vacuum.lock().unwrap().is_none());
+        }
+        assert_eq!(db.get_event("during").unwrap().map(|e| e.id), Some("during".to_string()));
+
+        drop(db);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    #[test]
+    fn test_cleanup_counts_freed_pages_without_vacuum() {
+        let path = std::env::temp_dir().join(format!("unifi-monitor-test-{}.db", uuid::Uuid::new_v4()));
+
+        let db = Database::open(&path).unwrap();
+        let padding = "x".repeat(1000);
+        for i in 0..500 {
+            db.store_event(&UnifiEvent {
+                id: format!("evt-{:03}", i),
+                timestamp: chrono::DateTime::from_timestamp(1_700_000_000 + i, 0).unwrap(),
+                source: EventSource::Network,
+                event_type: "motion".to_string(),
+                summary: "Test".to_string(),
+                severity: None,
+                raw: serde_json::json!({ "padding": padding }),
+            })
+            .unwrap();
+        }
+        // Every cleanup VACUUM below is skipped, so the file never shrinks
+        assert!(matches!(db.vacuum().unwrap(), VacuumOutcome::Completed(_)));
+
+        let max_mb = db.get_size_mb().unwrap() * 0.9;
+        let first = db.cleanup_by_size(max_mb).unwrap();
+        assert!(first.deleted_events > 0);
+        assert_eq!(first.vacuum, Some(VacuumOutcome::SkippedRecent));
+        assert!(first.size_after_mb <= max_mb);
+
+        let second = db.cleanup_by_size(max_mb).unwrap();
+        assert_eq!(second.deleted_events, 0);
+        assert_eq!(db.get_event_count().unwrap(), 500 - first.deleted_events);
+
+        drop(db);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    #[test]
+    fn test_read_only_rejects_writes() {
+        let path = std::env::temp_dir().join(format!("unifi-monitor-test-{}.db", uuid::Uuid::new_v4()));
+
+        let db = Database::open(&path).unwrap();
+        db.set_rule("test.event", Classification::Notify).unwrap();
+
+        let ro = Database::open_read_only(&path).unwrap();
+        assert!(ro.is_read_only());
+
+        // Reads work
+        assert_eq!(ro.get_rule("test.event").unwrap(), Some(Classification::Notify));
+
+        // Writes fail with a clear error
+        let err = ro.set_rule("test.event", Classification::Ignored).unwrap_err();
+        assert!(err.to_string().contains("read-only"), "unexpected error: {}", err);
+        assert!(ro.log_notification(None, None, None, "sent", None).is_err());
+        assert_eq!(db.get_rule("test.event").unwrap(), Some(Classification::Notify));
+
+        drop(ro);
+        drop(db);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    fn store_payload(db: &Database, id: &str, payload: serde_json::Value) {
+        db.store_event(&UnifiEvent {
+            id: id.to_string(),
+            timestamp: chrono::Utc::now(),
+            source: EventSource::Protect,
+            event_type: "smartDetectZone".to_string(),
+            summary: "Smart detection".to_string(),
+            severity: None,
+            raw: payload,
+        })
+        .unwrap();
+    }
+
+    fn payload_filter(expr: &str) -> EventFilter {
+        EventFilter {
+            payload: Some(PayloadFilter::parse(expr).unwrap()),
+            ..Default::default()
+        }
+    }
+
+    fn classified(classification: Classification) -> EventFilter {
+        EventFilter {
+            classifications: vec![classification],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_summary_search_ignores_payload() {
+        let db = Database::open_in_memory().unwrap();
+        let store = |id: &str, summary: &str, payload: serde_json::Value| {
+            db.store_event(&UnifiEvent {
+                id: id.to_string(),
+                timestamp: chrono::Utc::now(),
+                source: EventSource::Network,
+                event_type: "EVT_WU_Connected".to_string(),
+                summary: summary.to_string(),
+                severity: None,
+                raw: payload,
+            })
+            .unwrap();
+        };
+        store("in-summary", "Garage Camera connected", serde_json::json!({"ap": "office"}));
+        store("in-payload", "Client connected", serde_json::json!({"hostname": "garage-camera"}));
+
+        let broad = EventFilter {
+            search: Some("garage".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(db.count_events(&broad).unwrap(), 2);
+
+        let summary_only = EventFilter {
+            summary_search: Some("garage".to_string()),
+            ..Default::default()
+        };
+        let events = db.query_events(&summary_only, 10, 0).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].id, "in-summary");
+
+        // Both apply together
+        let combined = EventFilter {
+            search: Some("office".to_string()),
+            summary_search: Some("connected".to_string()),
+            ..Default::default()
+        };
+        let events = db.query_events(&combined, 10, 0).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].id, "in-summary");
+    }
+
+    #[test]
+    fn test_payload_filter_numeric() {
+        let db = Database::open_in_memory().unwrap();
+        store_payload(&db, "low", serde_json::json!({"data": {"score": 50}}));
+        store_payload(&db, "high", serde_json::json!({"data": {"score": 90}}));
+
+        let filter = payload_filter("data.score > 80");
+        let events = db.query_events(&filter, 10, 0).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].id, "high");
+        assert_eq!(db.count_events(&filter).unwrap(), 1);
+
+        let events = db.query_events(&payload_filter("data.score=50"), 10, 0).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].id, "low");
+    }
+
+    #[test]
+    fn test_payload_filter_array_contains() {
+        let db = Database::open_in_memory().unwrap();
+        store_payload(&db, "person", serde_json::json!({"data": {"smartDetectTypes": ["person", "vehicle"]}}));
+        store_payload(&db, "animal", serde_json::json!({"data": {"smartDetectTypes": ["animal"]}}));
+
+        let events = db
+            .query_events(&payload_filter("data.smartDetectTypes contains 'person'"), 10, 0)
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].id, "person");
+    }
+
+    #[test]
+    fn test_payload_filter_rejects_invalid_expressions() {
+        assert!(PayloadFilter::parse("data.score").is_err());
+        assert!(PayloadFilter::parse("data.score ~ 5").is_err());
+        assert!(PayloadFilter::parse("= 5").is_err());
+        assert!(PayloadFilter::parse("data.'x' = 1").is_err());
+        assert!(PayloadFilter::parse("data.x[a] = 1").is_err());
+
+        // Anything after the operator is a bound value, never SQL
+        let filter = PayloadFilter::parse("data.x = 1; DROP TABLE events").unwrap();
+        assert_eq!(filter.value, serde_json::json!("1; DROP TABLE events"));
+    }
+
+    #[test]
+    fn test_iter_events_pages_in_batches() {
+        let db = Database::open_in_memory().unwrap();
+        let base = chrono::Utc::now();
+        for i in 0..25 {
+            db.store_event(&UnifiEvent {
+                id: format!("event-{:02}", i),
+                // Several events share a timestamp to exercise the id tiebreak
+                timestamp: base + chrono::Duration::seconds(i / 3),
+                source: EventSource::Network,
+                event_type: "test".to_string(),
+                summary: "Test".to_string(),
+                severity: None,
+                raw: serde_json::json!({}),
+            })
+            .unwrap();
+        }
+
+        let ids: Vec<String> = db
+            .iter_events(4)
+            .map(|e| e.unwrap().id)
+            .collect();
+        let expected: Vec<String> = (0..25).map(|i| format!("event-{:02}", i)).collect();
+        assert_eq!(ids, expected);
+    }
+
+    #[tokio::test]
+    async fn test_stream_events() {
+        use tokio_stream::StreamExt;
+
+        let db = Database::open_in_memory().unwrap();
+        for i in 0..7 {
+            db.store_event(&UnifiEvent {
+                id: format!("event-{}", i),
+                timestamp: chrono::Utc::now(),
+                source: EventSource::Network,
+                event_type: "test".to_string(),
+                summary: "Test".to_string(),
+                severity: None,
+                raw: serde_json::json!({}),
+            })
+            .unwrap();
+        }
+
+        let events: Vec<_> = db.stream_events(3).collect().await;
+        assert_eq!(events.len(), 7);
+        assert!(events.iter().all(|e| e.is_ok()));
+    }
+
+    #[test]
+    fn test_store_duplicate_passkey() {
+        let db = Database::open_in_memory().unwrap();
+        assert!(db.store_passkey("cred-1", b"first", Some("Laptop")).unwrap());
+        assert!(!db.store_passkey("cred-1", b"second", Some("Laptop again")).unwrap());
+
+        // The original credential is kept
+        assert_eq!(db.get_passkey("cred-1").unwrap(), Some(b"first".to_vec()));
+        assert_eq!(db.get_all_passkeys().unwrap().len(), 1);
+    }
+
+    /// Simulate the wall clock stepping back by `secs` since the row was created
+    fn step_clock_back(db: &Database, table: &str, secs: i64) {
+        let conn = db.conn.lock().unwrap();
+        conn.execute(
+            &format!("UPDATE {} SET created_at = created_at + ?1, expires_at = expires_at + ?1", table),
+            params![secs],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_session_rejected_after_clock_steps_back() {
+        let db = Database::open_in_memory().unwrap();
+
+        // Small corrections are tolerated
+        let session = db.create_session(30).unwrap();
+        step_clock_back(&db, "sessions", 60);
+        assert!(db.validate_session(&session).unwrap());
+
+        // A large backward step would stretch the session's lifetime, so it's rejected
+        step_clock_back(&db, "sessions", 3600);
+        assert!(!db.validate_session(&session).unwrap());
+        // Cleanup waits for it to expire, in case the clock is the one that's wrong
+        assert_eq!(db.cleanup_expired_sessions().unwrap(), 0);
+        assert!(!db.validate_session(&session).unwrap());
+    }
+
+    #[test]
+    fn test_invite_rejected_after_clock_steps_back() {
+        let db = Database::open_in_memory().unwrap();
+
+        let token = db.create_invite_token(300).unwrap();
+        step_clock_back(&db, "invite_tokens", 3600);
+        assert!(!db.validate_invite_token(&token).unwrap());
+
+        let token = db.create_invite_token(300).unwrap();
+        assert!(db.validate_invite_token(&token).unwrap());
+        // Consumed on first use
+        assert!(!db.validate_invite_token(&token).unwrap());
+    }
+
+    #[test]
+    fn test_entity_state_updated_in_place() {
+        let db = Database::open_in_memory().unwrap();
+        let base = chrono::Utc::now();
+        let heartbeat = |secs: i64, hostname: &str| UnifiEvent {
+            id: format!("network-{}", secs),
+            timestamp: base + chrono::Duration::seconds(secs),
+            source: EventSource::Network,
+            event_type: "sta:sync".to_string(),
+            summary: format!("Client sync: {}", hostname),
+            severity: None,
+            raw: serde_json::json!({"data": [{"mac": "aa:bb", "hostname": hostname}]}),
+        };
+
+        assert_eq!(db.upsert_entity_state(&heartbeat(0, "laptop"), "aa:bb").unwrap(), 1);
+        assert_eq!(db.upsert_entity_state(&heartbeat(30, "laptop-2"), "aa:bb").unwrap(), 2);
+        assert_eq!(db.upsert_entity_state(&heartbeat(60, "phone"), "cc:dd").unwrap(), 1);
+
+        let states = db.get_entity_states(Some("sta:sync"), 10).unwrap();
+        assert_eq!(states.len(), 2);
+        let laptop = states.iter().find(|s| s.entity_id == "aa:bb").unwrap();
+        assert_eq!(laptop.change_count, 2);
+        assert_eq!(laptop.summary, "Client sync: laptop-2");
+        assert_eq!(laptop.last_seen - laptop.first_seen, 30);
+
+        // An event arriving out of order is counted but doesn't roll the state back
+        assert_eq!(db.upsert_entity_state(&heartbeat(-10, "stale"), "aa:bb").unwrap(), 3);
+        let states = db.get_entity_states(Some("sta:sync"), 10).unwrap();
+        let laptop = states.iter().find(|s| s.entity_id == "aa:bb").unwrap();
+        assert_eq!(laptop.summary, "Client sync: laptop-2");
+        assert_eq!(laptop.last_seen - laptop.first_seen, 40);
+
+        // Nothing was appended to the event history
+        assert_eq!(db.count_events(&EventFilter::default()).unwrap(), 0);
+        assert!(db.get_entity_states(Some("device:sync"), 10).unwrap().is_empty());
+    }
+
     #[test]
     fn test_sync_state() {
         let db = Database::open_in_memory().unwrap();
@@ -1052,4 +3449,44 @@ mod tests {
         db.set_last_update_id("protect", "def456").unwrap();
         assert_eq!(db.get_last_update_id("protect").unwrap(), Some("def456".to_string()));
     }
+
+    #[test]
+    fn test_event_type_summary_created_since() {
+        let db = Database::open_in_memory().unwrap();
+        store_type(&db, "old-1", "motion");
+        store_type(&db, "old-2", "motion");
+        db.conn
+            .lock()
+            .unwrap()
+            .execute("UPDATE events SET created_at = created_at - 3600", [])
+            .unwrap();
+        store_type(&db, "new-1", "motion");
+        store_type(&db, "new-2", "ring");
+
+        let all_time = db.get_event_type_summary().unwrap();
+        assert_eq!(all_time.iter().map(|s| s.count).sum::<i64>(), 4);
+
+        let cutoff = chrono::Utc::now().timestamp() - 60;
+        let mut recent = db.get_event_type_summary_created_since(cutoff).unwrap();
+        recent.sort_by(|a, b| a.event_type.cmp(&b.event_type));
+        let counts: Vec<_> = recent.iter().map(|s| (s.event_type.as_str(), s.count)).collect();
+        assert_eq!(counts, vec![("motion", 1), ("ring", 1)]);
+    }
+
+    #[test]
+    fn test_settings() {
+        let db = Database::open_in_memory().unwrap();
+
+        assert_eq!(db.get_setting("answer").unwrap(), None);
+        db.set_setting("answer", "41").unwrap();
+        db.set_setting("answer", "42").unwrap();
+        assert_eq!(db.get_setting("answer").unwrap().as_deref(), Some("42"));
+        assert_eq!(db.get_int_setting("answer").unwrap(), Some(42));
+        assert_eq!(db.get_bool_setting("answer").unwrap(), None);
+
+        assert!(!db.notifications_muted().unwrap());
+        db.set_notifications_muted(true).unwrap();
+        assert!(db.notifications_muted().unwrap());
+        assert_eq!(db.get_bool_setting(settings::NOTIFICATIONS_MUTED).unwrap(), Some(true));
+    }
 }
@@ -0,0 +1,108 @@
+//! Known entries of the `settings` key/value table
+//!
+//! Every setting that can be changed through the API is listed in
+//! [`SETTINGS`], along with how its value is validated and when a change
+//! takes effect.
+
+use serde::Serialize;
+
+/// Notification kill switch
+pub const NOTIFICATIONS_MUTED: &str = "notifications_muted";
+/// What happens to notifications while muted ("drop" or "hold")
+pub const MUTE_MODE: &str = "mute_mode";
+/// Live events buffered for SSE clients
+pub const SSE_BUFFER_SIZE: &str = "sse_buffer_size";
+
+/// Internal marker: the default `notify` rule for new event type
+/// announcements has been created (not changeable through the API)
+pub const NEW_EVENT_TYPE_RULE_SEEDED: &str = "new_event_type_rule_seeded";
+
+/// Largest accepted SSE buffer; the broadcast channel preallocates every slot
+pub const MAX_SSE_BUFFER_SIZE: u64 = 10_000;
+
+/// Type of a setting's value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SettingKind {
+    Bool,
+    /// A positive integer no larger than `max`
+    Integer { max: u64 },
+    /// One of a fixed set of strings
+    Choice(&'static [&'static str]),
+}
+
+/// When a change to a setting takes effect
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SettingScope {
+    /// Read on every use, so changes apply immediately
+    Runtime,
+    /// Read once at startup, so changes apply after a restart
+    Startup,
+}
+
+/// A setting that can be read and changed through the API
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct SettingDef {
+    pub key: &'static str,
+    pub kind: SettingKind,
+    pub scope: SettingScope,
+    pub description: &'static str,
+}
+
+pub const SETTINGS: &[SettingDef] = &[
+    SettingDef {
+        key: NOTIFICATIONS_MUTED,
+        kind: SettingKind::Bool,
+        scope: SettingScope::Runtime,
+        description: "Stop delivering all notifications",
+    },
+    SettingDef {
+        key: MUTE_MODE,
+        kind: SettingKind::Choice(&["drop", "hold"]),
+        scope: SettingScope::Runtime,
+        description: "Drop notifications while muted, or hold them for delivery on unmute (overrides MUTE_MODE)",
+    },
+    SettingDef {
+        key: SSE_BUFFER_SIZE,
+        kind: SettingKind::Integer { max: MAX_SSE_BUFFER_SIZE },
+        scope: SettingScope::Startup,
+        description: "Live events buffered for event stream clients (SSE_BUFFER_SIZE takes precedence)",
+    },
+];
+
+/// Look up a known setting
+pub fn setting_def(key: &str) -> Option<&'static SettingDef> {
+    SETTINGS.iter().find(|def| def.key == key)
+}
+
+impl SettingKind {
+    /// Validate a JSON value, returning it in stored form
+    pub fn parse(&self, value: &serde_json::Value) -> Result<String, String> {
+        match (self, value) {
+            (SettingKind::Bool, serde_json::Value::Bool(b)) => Ok(b.to_string()),
+            (SettingKind::Bool, _) => Err("expected true or false".to_string()),
+            (SettingKind::Integer { max }, serde_json::Value::Number(n)) => match n.as_u64() {
+                Some(n) if n > 0 && n <= *max => Ok(n.to_string()),
+                _ => Err(format!("expected a positive integer up to {}", max)),
+            },
+            (SettingKind::Integer { max }, _) => Err(format!("expected a positive integer up to {}", max)),
+            (SettingKind::Choice(choices), serde_json::Value::String(s)) if choices.contains(&s.as_str()) => {
+                Ok(s.clone())
+            }
+            (SettingKind::Choice(choices), _) => Err(format!("expected one of: {}", choices.join(", "))),
+        }
+    }
+
+    /// Convert a stored value back to JSON
+    pub fn to_json(&self, stored: &str) -> serde_json::Value {
+        match self {
+            SettingKind::Bool => serde_json::Value::Bool(stored == "true"),
+            SettingKind::Integer { .. } => stored
+                .parse::<u64>()
+                .map(serde_json::Value::from)
+                .unwrap_or(serde_json::Value::Null),
+            SettingKind::Choice(_) => serde_json::Value::String(stored.to_string()),
+        }
+    }
+}
@@ -4,6 +4,8 @@
 //! UniFi Protect, Network, and System APIs.
 
 pub mod db;
+pub mod notify;
+pub mod privacy;
 pub mod processor;
 pub mod unifi;
 pub mod web;
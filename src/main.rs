@@ -5,10 +5,20 @@ use std::sync::Arc;
 use tokio::sync::{broadcast, mpsc, Mutex};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use unifi_monitor::db::{Classification, Database};
-use unifi_monitor::processor::{EventProcessor, NotificationSender, ProcessorConfig};
-use unifi_monitor::unifi::{UnifiClient, UnifiConfig};
-use unifi_monitor::web::{self, auth::AuthState, FullAppState, SseEvent, TelegramConfig};
+use unifi_monitor::db::{settings, Classification, ClassificationMode, Database, VacuumPolicy, DEFAULT_MIGRATION_BACKUPS};
+use unifi_monitor::notify::{build_notifiers, run_digest, validate_recipients, DigestSchedule, NotifierConfig};
+use unifi_monitor::processor::{
+    EventProcessor, JsonlSink, MuteMode, NotificationSender, ProcessorConfig, NEW_EVENT_TYPE_EVENT_TYPE,
+};
+use unifi_monitor::unifi::types::{validate_ws_path, MAX_HTTP_RETRIES};
+use unifi_monitor::unifi::{
+    ConnectionTracker, DedupScope, EventSource, MissingIdPolicy, SeverityDefaults, UnifiClient, UnifiConfig,
+    UnifiEvent,
+};
+use unifi_monitor::web::{
+    self, auth::AuthState, BackfillStatus, FullAppState, SseConnections, SseEvent, TelegramConfig,
+    DEFAULT_MAX_SSE_CONNECTIONS,
+};
 
 /// Clean up old log files to stay under size limit
 fn cleanup_logs(log_dir: &str, max_size_mb: u64) -> anyhow::Result<()> {
@@ -74,6 +84,8 @@ fn cleanup_logs(log_dir: &str, max_size_mb: u64) -> anyhow::Result<()> {
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let started_at = chrono::Utc::now().timestamp();
+
     // Load .env file first (before logging setup to read LOG_DIR)
     dotenvy::dotenv().ok();
 
@@ -114,9 +126,8 @@ async fn main() -> anyhow::Result<()> {
     let username = std::env::var("UNIFI_USERNAME").expect("UNIFI_USERNAME required");
     let password = std::env::var("UNIFI_PASSWORD").expect("UNIFI_PASSWORD required");
 
-    // Telegram configuration (optional for now)
-    let telegram_token = std::env::var("TELEGRAM_BOT_TOKEN").ok();
-    let telegram_chat_id = std::env::var("TELEGRAM_CHAT_ID").ok();
+    // Notification backends (Telegram, webhook); any number may be enabled
+    let notifier_configs = NotifierConfig::from_env();
 
     // Database path
     let db_path = std::env::var("DATABASE_PATH").unwrap_or_else(|_| "data/unifi-monitor.db".to_string());
@@ -127,14 +138,36 @@ async fn main() -> anyhow::Result<()> {
         .and_then(|s| s.parse().ok())
         .unwrap_or(512.0);
 
+    // Stored (default) or query-time classification
+    let classification_mode = match std::env::var("CLASSIFICATION_MODE") {
+        Ok(mode) => ClassificationMode::from_str(&mode).unwrap_or_else(|| {
+            tracing::warn!("Invalid CLASSIFICATION_MODE '{}', using stored", mode);
+            ClassificationMode::Stored
+        }),
+        Err(_) => ClassificationMode::Stored,
+    };
+
     // Ensure data directory exists
     if let Some(parent) = std::path::Path::new(&db_path).parent() {
         std::fs::create_dir_all(parent)?;
     }
 
     // Open database
-    tracing::info!("Opening database at {}...", db_path);
-    let db = Database::open(&db_path)?;
+    tracing::info!("Opening database at {} ({} classification)...", db_path, classification_mode.as_str());
+    let mut vacuum_policy = VacuumPolicy::default();
+    if let Some(secs) = std::env::var("DB_VACUUM_MAX_SECS").ok().and_then(|s| s.parse().ok()) {
+        vacuum_policy.max_duration = std::time::Duration::from_secs(secs);
+    }
+    if let Some(secs) = std::env::var("DB_VACUUM_MIN_INTERVAL_SECS").ok().and_then(|s| s.parse().ok()) {
+        vacuum_policy.min_interval = std::time::Duration::from_secs(secs);
+    }
+    // Snapshot the file before schema changes on upgrade
+    let db = if std::env::var("BACKUP_BEFORE_MIGRATE").is_ok_and(|v| v == "true" || v == "1") {
+        Database::open_with_migration_backup(&db_path, classification_mode, DEFAULT_MIGRATION_BACKUPS)?
+    } else {
+        Database::open_with_mode(&db_path, classification_mode)?
+    };
+    let db = db.with_vacuum_policy(vacuum_policy);
 
     // Run cleanup on startup
     tracing::info!("Checking database size (max {}MB)...", db_max_size_mb);
@@ -156,15 +189,25 @@ async fn main() -> anyhow::Result<()> {
         }
     }
 
-    // Spawn periodic cleanup task (every hour)
+    // Spawn periodic cleanup task (every hour, plus up to 5 minutes of jitter so
+    // the VACUUM doesn't land at the same moment every hour)
     let cleanup_db = db.clone();
     tokio::spawn(async move {
         let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
         interval.tick().await; // Skip immediate tick
         loop {
             interval.tick().await;
+            let jitter = {
+                use rand::Rng;
+                rand::thread_rng().gen_range(0..300)
+            };
+            tokio::time::sleep(std::time::Duration::from_secs(jitter)).await;
             tracing::debug!("Running periodic database cleanup check");
-            match cleanup_db.cleanup_by_size(db_max_size_mb) {
+            let db = cleanup_db.clone();
+            let result = tokio::task::spawn_blocking(move || db.cleanup_by_size(db_max_size_mb))
+                .await
+                .expect("cleanup task panicked");
+            match result {
                 Ok(result) => {
                     if result.deleted_events > 0 {
                         tracing::info!(
@@ -198,11 +241,104 @@ async fn main() -> anyhow::Result<()> {
     // Create notification channel
     let (notify_tx, notify_rx) = mpsc::channel(100);
 
-    // Create broadcast channel for SSE (live event updates to frontend)
-    let (sse_tx, _) = broadcast::channel::<SseEvent>(100);
+    // Create broadcast channel for SSE (live event updates to frontend). Each
+    // slot is shared by all clients; a client that falls further behind than
+    // this is caught up from the database
+    let sse_buffer: usize = std::env::var("SSE_BUFFER_SIZE")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .or_else(|| {
+            db.get_int_setting(settings::SSE_BUFFER_SIZE)
+                .ok()
+                .flatten()
+                .and_then(|n| usize::try_from(n).ok())
+        })
+        .filter(|&n| n > 0)
+        .map(|n| {
+            let max = settings::MAX_SSE_BUFFER_SIZE as usize;
+            if n > max {
+                tracing::warn!(size = n, max, "SSE_BUFFER_SIZE too large, using the maximum");
+            }
+            n.min(max)
+        })
+        .unwrap_or(100);
+    let (sse_tx, _) = broadcast::channel::<SseEvent>(sse_buffer);
+
+    // Cap on concurrent SSE clients; each holds a broadcast receiver
+    let max_sse_connections = match std::env::var("SSE_MAX_CONNECTIONS") {
+        Ok(value) => match value.parse::<usize>() {
+            Ok(n) if n > 0 => n,
+            _ => {
+                tracing::warn!(
+                    "Invalid SSE_MAX_CONNECTIONS '{}', using {}",
+                    value,
+                    DEFAULT_MAX_SSE_CONNECTIONS
+                );
+                DEFAULT_MAX_SSE_CONNECTIONS
+            }
+        },
+        Err(_) => DEFAULT_MAX_SSE_CONNECTIONS,
+    };
+
+    // Hide hostnames and user names from summaries on shared displays
+    let privacy_mode = std::env::var("PRIVACY_MODE").is_ok_and(|v| v == "true" || v == "1");
+    if privacy_mode {
+        tracing::info!("Privacy mode enabled, redacting personal data from summaries");
+    }
 
     // Create event processor
-    let processor = EventProcessor::new(db.clone(), ProcessorConfig::default(), notify_tx);
+    let mut processor_config = ProcessorConfig::default();
+    if let Ok(types) = std::env::var("COMPACT_EVENT_TYPES") {
+        processor_config.compact_event_types = types
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+        tracing::info!(
+            "Compacting event types into per-entity state: {:?}",
+            processor_config.compact_event_types
+        );
+    }
+    if let Some(days) = std::env::var("MAX_EVENT_AGE_DAYS").ok().and_then(|s| s.parse::<i64>().ok()) {
+        if days > 0 {
+            processor_config.max_event_age = Some(chrono::Duration::days(days));
+            tracing::info!("Dropping incoming events older than {} days", days);
+        }
+    }
+    let mut processor = EventProcessor::new(db.clone(), processor_config, notify_tx);
+
+    // Channel for events generated by the monitor itself (e.g. notification failures)
+    let (internal_tx, mut internal_rx) = mpsc::channel::<UnifiEvent>(100);
+
+    // Heads-up when an event type nobody has classified shows up
+    if std::env::var("NOTIFY_NEW_EVENT_TYPES").is_ok_and(|v| v == "true" || v == "1") {
+        tracing::info!("New event types will be announced as {} events", NEW_EVENT_TYPE_EVENT_TYPE);
+        processor = processor.with_new_type_events(internal_tx.clone());
+        // Notify about them unless a rule already says otherwise. Seeded only
+        // once, so a rule the user later deletes isn't recreated.
+        if db.get_setting(settings::NEW_EVENT_TYPE_RULE_SEEDED)?.is_none() {
+            if db.matching_rule(NEW_EVENT_TYPE_EVENT_TYPE)?.is_none() {
+                db.set_rule(NEW_EVENT_TYPE_EVENT_TYPE, Classification::Notify)?;
+            }
+            db.set_setting(settings::NEW_EVENT_TYPE_RULE_SEEDED, "true")?;
+        }
+    }
+
+    // Optional JSONL export of every stored event (for external log pipelines)
+    if let Ok(jsonl_path) = std::env::var("EVENT_JSONL_PATH") {
+        match JsonlSink::new(&jsonl_path) {
+            Ok(sink) => {
+                tracing::info!("Exporting events as JSONL to {} (rotated daily)", jsonl_path);
+                processor = processor.with_jsonl_sink(sink);
+            }
+            Err(e) => {
+                tracing::warn!("Failed to open JSONL sink at {}: {}", jsonl_path, e);
+            }
+        }
+    }
+
+    // Shared with the web server for events injected via the API
+    let processor = Arc::new(processor);
 
     // Load any pending notifications from database
     processor.load_pending_notifications().await?;
@@ -274,20 +410,40 @@ async fn main() -> anyhow::Result<()> {
         tracing::info!("==================================================");
     }
 
-    // Build telegram config if both token and chat_id are set
-    let telegram_config = match (&telegram_token, &telegram_chat_id) {
-        (Some(token), Some(chat_id)) => Some(TelegramConfig {
-            token: token.clone(),
-            chat_id: chat_id.clone(),
+    // Telegram settings for the web UI's recipient status
+    let telegram_config = notifier_configs.iter().find_map(|config| match config {
+        NotifierConfig::Telegram { chat_ids, .. } => Some(TelegramConfig {
+            chat_ids: chat_ids.clone(),
+            recipients: Default::default(),
         }),
         _ => None,
-    };
+    });
+
+    // One set of backends for the sender, the digest and the web UI's test
+    // message, with mistyped recipients dropped before anything is sent
+    let mut notifiers = build_notifiers(&notifier_configs);
+    let statuses = validate_recipients(&mut notifiers).await;
+    if let Some(telegram) = &telegram_config {
+        *telegram.recipients.write().unwrap() = statuses;
+    }
+    let notifiers = Arc::new(notifiers);
+
+    // Filled in once the UniFi client has connected and backfilled
+    let backfill_status = BackfillStatus::default();
+    let connections = ConnectionTracker::default();
 
     let web_state = FullAppState {
         db: db.clone(),
         sse_tx: sse_tx.clone(),
         auth: auth_state,
         telegram: telegram_config,
+        notifiers: notifiers.clone(),
+        processor: processor.clone(),
+        backfill: backfill_status.clone(),
+        connections: connections.clone(),
+        started_at,
+        sse_connections: SseConnections::new(max_sse_connections),
+        privacy_mode,
     };
     tokio::spawn(async move {
         if let Err(e) = web::start_server_with_auth(web_state, &listen_addr, static_dir.as_deref()).await {
@@ -295,21 +451,67 @@ async fn main() -> anyhow::Result<()> {
         }
     });
 
-    // Start notification sender task if Telegram is configured
-    if let (Some(token), Some(chat_id)) = (telegram_token, telegram_chat_id) {
-        tracing::info!("Telegram notifications enabled");
-        let sender = NotificationSender::new(
+    // Scheduled summary through the same backends
+    if let Ok(schedule) = std::env::var("DIGEST_SCHEDULE") {
+        match DigestSchedule::parse(&schedule) {
+            Some(_) if notifier_configs.is_empty() => {
+                tracing::warn!("DIGEST_SCHEDULE set but no notification backend is configured");
+            }
+            Some(schedule) => {
+                tracing::info!("Sending digests {:?} (local time)", schedule);
+                tokio::spawn(run_digest(db.clone(), notifiers.clone(), schedule));
+            }
+            None => tracing::warn!(
+                "Invalid DIGEST_SCHEDULE '{}' (expected 'daily HH:MM' or 'weekly <day> HH:MM')",
+                schedule
+            ),
+        }
+    }
+
+    // Start notification sender task if any backend is configured
+    if !notifier_configs.is_empty() {
+        let ids: Vec<&str> = notifier_configs.iter().map(|c| c.id()).collect();
+        tracing::info!("Notification backends enabled: {}", ids.join(", "));
+        let mut sender = NotificationSender::new(
             db.clone(),
             notify_rx,
-            token,
-            chat_id,
+            notifiers,
             10, // max attempts
         );
-        tokio::spawn(async move {
-            sender.run().await;
-        });
+        if std::env::var("EMIT_NOTIFICATION_FAILURES").is_ok_and(|v| v == "true" || v == "1") {
+            tracing::info!("Notification failures will be emitted as events");
+            sender = sender.with_failure_events(internal_tx.clone());
+        }
+        if let Ok(base_url) = std::env::var("PUBLIC_BASE_URL") {
+            sender = sender.with_public_base_url(base_url);
+        }
+        if let Ok(mode) = std::env::var("MUTE_MODE") {
+            match MuteMode::from_str(&mode) {
+                Some(mode) => sender = sender.with_mute_mode(mode),
+                None => tracing::warn!("Invalid MUTE_MODE '{}', using drop", mode),
+            }
+        }
+        let quiet_secs: u64 = std::env::var("STARTUP_QUIET_PERIOD_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(30);
+        if quiet_secs > 0 {
+            tracing::info!(
+                "Suppressing non-critical notifications for {}s after startup and for backfilled events",
+                quiet_secs
+            );
+            sender = sender.with_startup_quiet_period(std::time::Duration::from_secs(quiet_secs));
+        }
+        tokio::spawn(sender.run());
     } else {
-        tracing::warn!("Telegram not configured (TELEGRAM_BOT_TOKEN and TELEGRAM_CHAT_ID required)");
+        if db
+            .get_all_rules()
+            .is_ok_and(|rules| rules.iter().any(|rule| rule.classification == Classification::Notify))
+        {
+            tracing::warn!("Rules classify events as notify, but no notification backend is configured");
+        } else {
+            tracing::info!("No notification backend configured");
+        }
         // Drain the channel so it doesn't block
         tokio::spawn(async move {
             let mut rx = notify_rx;
@@ -318,14 +520,103 @@ async fn main() -> anyhow::Result<()> {
     }
 
     // Connect to UniFi
-    let config = UnifiConfig::new(&host, &username, &password);
+    let mut config = UnifiConfig::new(&host, &username, &password);
+    if let Ok(user_agent) = std::env::var("UNIFI_USER_AGENT") {
+        config = config.with_user_agent(user_agent);
+    }
+    if let Some(secs) = std::env::var("UNIFI_HTTP_TIMEOUT_SECS").ok().and_then(|s| s.parse().ok()) {
+        config = config.with_request_timeout(std::time::Duration::from_secs(secs));
+    }
+    if let Some(secs) = std::env::var("UNIFI_CONNECT_TIMEOUT_SECS").ok().and_then(|s| s.parse().ok()) {
+        config = config.with_connect_timeout(std::time::Duration::from_secs(secs));
+    }
+    if let Some(retries) = std::env::var("UNIFI_HTTP_RETRIES").ok().and_then(|s| s.parse::<u32>().ok()) {
+        if retries > MAX_HTTP_RETRIES {
+            tracing::warn!("UNIFI_HTTP_RETRIES {} too large, using {}", retries, MAX_HTTP_RETRIES);
+        }
+        config = config.with_http_retries(retries);
+    }
+    if let Ok(endpoints) = std::env::var("UNIFI_SYSTEM_EVENT_ENDPOINTS") {
+        let endpoints: Vec<String> = endpoints
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+        if !endpoints.is_empty() {
+            config = config.with_system_event_endpoints(endpoints);
+        }
+    }
+    for (var, source) in [
+        ("UNIFI_PROTECT_WS_PATH", EventSource::Protect),
+        ("UNIFI_NETWORK_WS_PATH", EventSource::Network),
+        ("UNIFI_SYSTEM_WS_PATH", EventSource::System),
+    ] {
+        if let Ok(path) = std::env::var(var) {
+            match validate_ws_path(&path) {
+                Ok(()) => config = config.with_ws_path(source, path),
+                Err(e) => tracing::warn!("Invalid {} '{}' ({}), using the default", var, path, e),
+            }
+        }
+    }
+    if let Ok(spec) = std::env::var("REST_SEVERITY_DEFAULTS") {
+        match SeverityDefaults::parse(&spec) {
+            Some(defaults) => config = config.with_rest_severity_defaults(defaults),
+            None => tracing::warn!("Invalid REST_SEVERITY_DEFAULTS '{}', ignoring", spec),
+        }
+    }
+    if let Ok(patterns) = std::env::var("SYSTEM_STATE_EVENT_TYPES") {
+        let patterns: Vec<String> = patterns
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+        config = config.with_system_state_event_types(patterns);
+    }
+    if let Ok(spec) = std::env::var("STORE_UNCHANGED_UPDATES") {
+        match EventSource::parse_list(&spec) {
+            Some(sources) => config = config.with_store_unchanged_updates(sources),
+            None => tracing::warn!("Invalid STORE_UNCHANGED_UPDATES '{}', dropping unchanged updates", spec),
+        }
+    }
+    if let Ok(scope) = std::env::var("DEDUP_SCOPE") {
+        match DedupScope::from_str(&scope) {
+            Some(scope) => config = config.with_dedup_scope(scope),
+            None => tracing::warn!("Invalid DEDUP_SCOPE '{}', using global", scope),
+        }
+    }
+    if let Some(secs) = std::env::var("DEDUP_WINDOW_SECS").ok().and_then(|s| s.parse::<u64>().ok()) {
+        if secs > 0 {
+            tracing::info!("Collapsing same-entity events within {}s", secs);
+            config = config.with_dedup_window(std::time::Duration::from_secs(secs));
+        }
+    }
+    if let Ok(spec) = std::env::var("MISSING_ID_STRATEGY") {
+        match MissingIdPolicy::parse(&spec) {
+            Some(policy) => config = config.with_missing_id_policy(policy),
+            None => tracing::warn!("Invalid MISSING_ID_STRATEGY '{}', hashing event content", spec),
+        }
+    }
+    if std::env::var("SKIP_HISTORICAL_BACKFILL").is_ok_and(|v| v == "true" || v == "1") {
+        config = config.with_skip_historical_backfill(true);
+    }
     tracing::info!("Connecting to UniFi console at {}...", host);
-    let mut client = UnifiClient::connect(config, Some(db.clone())).await?;
+    let mut client = UnifiClient::connect_tracked(config, Some(db.clone()), connections).await?;
+    *backfill_status.write().unwrap() = Some(client.backfill_result().clone());
     tracing::info!("Connected. Listening for events...");
 
     // Process events
     let mut count = 0;
-    while let Some(event) = client.events().next().await {
+    // Only the notification sender's and processor's clones should keep the
+    // internal channel open
+    drop(internal_tx);
+    let mut events = client.events();
+    loop {
+        let event = tokio::select! {
+            Some(event) = events.next() => event,
+            Some(event) = internal_rx.recv() => event,
+            else => break,
+        };
+
         // Store and classify event
         let classification = processor.process(event.clone()).await?;
 
@@ -338,18 +629,23 @@ async fn main() -> anyhow::Result<()> {
         let local_ts = event.timestamp.with_timezone(&chrono::Local);
         let ts = local_ts.format("%H:%M:%S");
 
-        // Broadcast to SSE clients (ignore errors if no clients connected)
-        let _ = sse_tx.send(SseEvent {
-            id: event.id.clone(),
-            source: event.source.to_string(),
-            event_type: event.event_type.clone(),
-            severity: event.severity.map(|s| format!("{:?}", s).to_lowercase()),
-            summary: event.summary.clone(),
-            timestamp: event.timestamp.timestamp(),
-            classification: classification.as_str().to_string(),
-            notified: false,
-            created_at: chrono::Utc::now().timestamp(),
-        });
+        // Broadcast to SSE clients (ignore errors if no clients connected).
+        // Compacted events have no row for the dashboard to open, so only
+        // their entity state is kept
+        if !processor.is_compacted(&event.event_type) {
+            let sse_event = SseEvent {
+                id: event.id.clone(),
+                source: event.source.to_string(),
+                event_type: event.event_type.clone(),
+                severity: event.severity.map(|s| format!("{:?}", s).to_lowercase()),
+                summary: event.summary.clone(),
+                timestamp: event.timestamp.timestamp(),
+                classification: classification.as_str().to_string(),
+                notified: false,
+                created_at: chrono::Utc::now().timestamp(),
+            };
+            let _ = sse_tx.send(if privacy_mode { sse_event.redact(&event.raw) } else { sse_event });
+        }
 
         tracing::debug!(
             "[{}] {} {} | {} | {} [{}]",
@@ -0,0 +1,267 @@
+//! Scheduled summary of what happened over a period
+//!
+//! A heartbeat message with event counts by classification and the noisiest
+//! event types, sent through every configured backend on a daily or weekly
+//! schedule.
+
+use chrono::{DateTime, Datelike, Duration, NaiveTime, TimeZone, Weekday};
+use std::sync::Arc;
+use tracing::{error, info};
+
+use super::Notifier;
+use crate::db::{Classification, Database};
+
+/// Event types listed in a digest
+pub const DIGEST_TOP_TYPES: usize = 5;
+
+/// When digests are sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DigestSchedule {
+    /// Every day at this time
+    Daily(NaiveTime),
+    /// Every week on this day at this time
+    Weekly(Weekday, NaiveTime),
+}
+
+impl DigestSchedule {
+    /// Parse `daily HH:MM` or `weekly <day> HH:MM` (e.g. `weekly mon 08:00`)
+    pub fn parse(s: &str) -> Option<Self> {
+        let parts: Vec<&str> = s.split_whitespace().collect();
+        let time = |t: &str| NaiveTime::parse_from_str(t, "%H:%M").ok();
+        match parts.as_slice() {
+            [every, at] if every.eq_ignore_ascii_case("daily") => Some(DigestSchedule::Daily(time(at)?)),
+            [every, day, at] if every.eq_ignore_ascii_case("weekly") => {
+                Some(DigestSchedule::Weekly(day.parse().ok()?, time(at)?))
+            }
+            _ => None,
+        }
+    }
+
+    /// Length of the period each digest covers
+    pub fn period(&self) -> Duration {
+        match self {
+            DigestSchedule::Daily(_) => Duration::days(1),
+            DigestSchedule::Weekly(..) => Duration::weeks(1),
+        }
+    }
+
+    /// The first scheduled time strictly after `now`
+    pub fn next_after<Tz: TimeZone>(&self, now: &DateTime<Tz>) -> DateTime<Tz> {
+        let (days_ahead, time) = match *self {
+            DigestSchedule::Daily(time) => (0, time),
+            DigestSchedule::Weekly(day, time) => {
+                let ahead = (7 + day.num_days_from_monday() - now.weekday().num_days_from_monday()) % 7;
+                (ahead as i64, time)
+            }
+        };
+
+        let mut date = now.date_naive() + Duration::days(days_ahead);
+        loop {
+            // A time skipped by a DST change falls back to the next candidate
+            if let Some(candidate) = now.timezone().from_local_datetime(&date.and_time(time)).earliest() {
+                if candidate > *now {
+                    return candidate;
+                }
+            }
+            date += self.period();
+        }
+    }
+}
+
+/// Event counts over one digest period
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Digest {
+    /// Start of the period (unix seconds, inclusive)
+    pub since: i64,
+    /// End of the period (unix seconds, exclusive)
+    pub until: i64,
+    pub total: i64,
+    pub by_classification: Vec<(Classification, i64)>,
+    /// Noisiest event types, most events first
+    pub top_event_types: Vec<(String, i64)>,
+}
+
+impl Digest {
+    /// Summarize events with `since <= timestamp < until`
+    pub fn compute(db: &Database, since: i64, until: i64, top_n: usize) -> rusqlite::Result<Self> {
+        let mut summaries = db.get_event_type_summary_between(Some(since), Some(until))?;
+
+        let by_classification = [Classification::Notify, Classification::Unclassified, Classification::Ignored]
+            .into_iter()
+            .map(|c| {
+                let count = summaries.iter().filter(|s| s.classification == c).map(|s| s.count).sum();
+                (c, count)
+            })
+            .collect();
+
+        summaries.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.event_type.cmp(&b.event_type)));
+        Ok(Self {
+            since,
+            until,
+            total: summaries.iter().map(|s| s.count).sum(),
+            by_classification,
+            top_event_types: summaries
+                .into_iter()
+                .take(top_n)
+                .map(|s| (s.event_type, s.count))
+                .collect(),
+        })
+    }
+
+    /// Plain-text message body
+    pub fn text(&self) -> String {
+        let format_ts = |ts: i64| {
+            DateTime::from_timestamp(ts, 0)
+                .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_else(|| ts.to_string())
+        };
+
+        let counts: Vec<String> = self
+            .by_classification
+            .iter()
+            .map(|(c, count)| format!("{} {}", count, c.as_str()))
+            .collect();
+        let mut text = format!(
+            "Summary for {} to {} UTC\n{} events: {}",
+            format_ts(self.since),
+            format_ts(self.until),
+            self.total,
+            counts.join(", ")
+        );
+
+        if !self.top_event_types.is_empty() {
+            text.push_str("\n\nTop event types:");
+            for (event_type, count) in &self.top_event_types {
+                text.push_str(&format!("\n{}: {}", event_type, count));
+            }
+        }
+        text
+    }
+}
+
+/// Send a digest through every backend on `schedule`, forever
+///
+/// Each digest covers the period ending at its scheduled time. Nothing is
+/// sent while notifications are muted.
+pub async fn run_digest(db: Database, notifiers: Arc<Vec<Box<dyn Notifier>>>, schedule: DigestSchedule) {
+    loop {
+        let now = chrono::Local::now();
+        let next = schedule.next_after(&now);
+        tokio::time::sleep((next - now).to_std().unwrap_or_default()).await;
+
+        if db.notifications_muted().unwrap_or(false) {
+            info!("Notifications muted, skipping digest");
+            continue;
+        }
+
+        let until = next.timestamp();
+        let digest = match Digest::compute(&db, until - schedule.period().num_seconds(), until, DIGEST_TOP_TYPES) {
+            Ok(digest) => digest,
+            Err(e) => {
+                error!(error = %e, "Failed to compute digest");
+                continue;
+            }
+        };
+
+        let summary = format!("Digest: {} events", digest.total);
+        for notifier in notifiers.iter() {
+            let (status, error_msg) = match notifier.send_digest(&digest).await {
+                Ok(()) => ("sent", None),
+                Err(e) => {
+                    error!(backend = notifier.id(), error = %e, "Failed to send digest");
+                    ("failed", Some(format!("{}: {}", notifier.id(), e)))
+                }
+            };
+            if let Err(e) = db.log_notification(None, Some("digest"), Some(&summary), status, error_msg.as_deref()) {
+                error!(error = %e, "Failed to log digest");
+            }
+        }
+        info!(total = digest.total, "Digest sent");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unifi::types::{EventSource, UnifiEvent};
+    use chrono::Utc;
+
+    #[test]
+    fn test_parse_and_next_schedule() {
+        let eight = NaiveTime::from_hms_opt(8, 0, 0).unwrap();
+        assert_eq!(DigestSchedule::parse("daily 08:00"), Some(DigestSchedule::Daily(eight)));
+        assert_eq!(
+            DigestSchedule::parse("weekly Mon 08:00"),
+            Some(DigestSchedule::Weekly(Weekday::Mon, eight))
+        );
+        assert_eq!(DigestSchedule::parse("daily 25:00"), None);
+        assert_eq!(DigestSchedule::parse("hourly"), None);
+
+        // Wednesday 2024-01-03 09:30 UTC
+        let now = Utc.with_ymd_and_hms(2024, 1, 3, 9, 30, 0).unwrap();
+        assert_eq!(
+            DigestSchedule::Daily(eight).next_after(&now),
+            Utc.with_ymd_and_hms(2024, 1, 4, 8, 0, 0).unwrap()
+        );
+        assert_eq!(
+            DigestSchedule::Weekly(Weekday::Wed, eight).next_after(&now),
+            Utc.with_ymd_and_hms(2024, 1, 10, 8, 0, 0).unwrap()
+        );
+        assert_eq!(
+            DigestSchedule::Weekly(Weekday::Fri, eight).next_after(&now),
+            Utc.with_ymd_and_hms(2024, 1, 5, 8, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_digest_content() {
+        let db = Database::open_in_memory().unwrap();
+        db.set_rule("motion", Classification::Notify).unwrap();
+        db.set_rule("sta:sync", Classification::Ignored).unwrap();
+
+        let day_start = Utc.with_ymd_and_hms(2024, 1, 3, 0, 0, 0).unwrap();
+        let store = |id: &str, event_type: &str, hours: i64| {
+            db.store_event(&UnifiEvent {
+                id: id.to_string(),
+                timestamp: day_start + Duration::hours(hours),
+                source: EventSource::Network,
+                event_type: event_type.to_string(),
+                summary: "Test".to_string(),
+                severity: None,
+                raw: serde_json::json!({}),
+            })
+            .unwrap();
+        };
+        for i in 0..4 {
+            store(&format!("sync-{}", i), "sta:sync", i);
+        }
+        store("motion-1", "motion", 2);
+        store("motion-2", "motion", 3);
+        store("new-1", "EVT_NEW", 5);
+        // Outside the period
+        store("old", "motion", -1);
+        store("later", "motion", 24);
+
+        let since = day_start.timestamp();
+        let digest = Digest::compute(&db, since, since + 86_400, 2).unwrap();
+        assert_eq!(digest.total, 7);
+        assert_eq!(
+            digest.by_classification,
+            vec![
+                (Classification::Notify, 2),
+                (Classification::Unclassified, 1),
+                (Classification::Ignored, 4),
+            ]
+        );
+        assert_eq!(
+            digest.top_event_types,
+            vec![("sta:sync".to_string(), 4), ("motion".to_string(), 2)]
+        );
+        assert_eq!(
+            digest.text(),
+            "Summary for 2024-01-03 00:00 to 2024-01-04 00:00 UTC\n\
+             7 events: 2 notify, 1 unclassified, 4 ignored\n\n\
+             Top event types:\nsta:sync: 4\nmotion: 2"
+        );
+    }
+}
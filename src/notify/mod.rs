@@ -0,0 +1,274 @@
+//! Notification backends and their configuration
+//!
+//! Each backend implements [`Notifier`]. [`NotifierConfig`] lists the enabled
+//! backends with their settings, and [`build_notifiers`] turns that list into
+//! the backends the notification sender delivers through.
+
+pub mod digest;
+pub mod telegram;
+pub mod webhook;
+
+use futures_util::future::BoxFuture;
+use tracing::warn;
+
+use crate::db::StoredEvent;
+
+pub use digest::{run_digest, Digest, DigestSchedule};
+pub use telegram::{RecipientCheck, RecipientStatus, RouteMatch, TelegramNotifier, TelegramRoute};
+pub use webhook::{sign_webhook_body, WebhookNotifier, WEBHOOK_SIGNATURE_HEADER};
+
+/// Time allowed to connect to a backend
+const CONNECT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Time allowed for a whole backend request, so a hung endpoint can't stall
+/// the notification queue
+const REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// HTTP client shared by the backends' constructors
+fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .unwrap_or_default()
+}
+
+/// A notification backend
+pub trait Notifier: Send + Sync {
+    /// Short name of the backend (e.g. "telegram")
+    fn id(&self) -> &str;
+
+    /// Deliver one event; `link` points at the event in the dashboard
+    fn send<'a>(&'a self, event: &'a StoredEvent, link: Option<&'a str>) -> BoxFuture<'a, Result<(), NotifyError>>;
+
+    /// Deliver one event to the recipients not in `delivered`, adding each
+    /// that succeeds, so a retry after a partial failure reaches only the rest
+    ///
+    /// Backends with a single recipient keep the default, which calls `send`.
+    fn send_remaining<'a>(
+        &'a self,
+        event: &'a StoredEvent,
+        link: Option<&'a str>,
+        _delivered: &'a mut Vec<String>,
+    ) -> BoxFuture<'a, Result<(), NotifyError>> {
+        self.send(event, link)
+    }
+
+    /// Deliver a scheduled summary
+    fn send_digest<'a>(&'a self, digest: &'a Digest) -> BoxFuture<'a, Result<(), NotifyError>>;
+
+    /// Deliver a test message to the backend's default recipients
+    fn send_test(&self) -> BoxFuture<'_, Result<(), NotifyError>>;
+
+    /// Check the backend's recipients at startup, dropping ones it can't reach
+    fn validate(&mut self) -> BoxFuture<'_, Vec<RecipientStatus>> {
+        Box::pin(async { Vec::new() })
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum NotifyError {
+    #[error("Request failed: {0}")]
+    Request(String),
+    #[error("API error: {0}")]
+    Api(String),
+}
+
+/// An enabled notification backend and its settings
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NotifierConfig {
+    Telegram {
+        token: String,
+        /// Default recipients for events no route matches
+        chat_ids: Vec<String>,
+        routes: Vec<TelegramRoute>,
+    },
+    Webhook {
+        url: String,
+        /// Signs each body (see `sign_webhook_body`) when set
+        secret: Option<String>,
+    },
+}
+
+impl NotifierConfig {
+    pub fn id(&self) -> &'static str {
+        match self {
+            NotifierConfig::Telegram { .. } => "telegram",
+            NotifierConfig::Webhook { .. } => "webhook",
+        }
+    }
+
+    /// Backends enabled by environment variables
+    pub fn from_env() -> Vec<Self> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// Backends enabled by the given variables
+    ///
+    /// A backend missing a required setting is skipped with a warning, as are
+    /// invalid optional entries (e.g. a malformed Telegram route).
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Vec<Self> {
+        let var = |name: &str| var(name).map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        let mut configs = Vec::new();
+
+        match (var("TELEGRAM_BOT_TOKEN"), var("TELEGRAM_CHAT_ID")) {
+            (Some(token), Some(chat_ids)) => {
+                let routes = var("TELEGRAM_ROUTES")
+                    .map(|routes| {
+                        routes
+                            .split(',')
+                            .map(str::trim)
+                            .filter(|s| !s.is_empty())
+                            .filter_map(|s| {
+                                let route = TelegramRoute::parse(s);
+                                if route.is_none() {
+                                    warn!("Ignoring invalid TELEGRAM_ROUTES entry '{}'", s);
+                                }
+                                route
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                configs.push(NotifierConfig::Telegram {
+                    token,
+                    chat_ids: split_list(&chat_ids),
+                    routes,
+                });
+            }
+            (None, None) => {}
+            _ => warn!("Telegram not configured (TELEGRAM_BOT_TOKEN and TELEGRAM_CHAT_ID required)"),
+        }
+
+        if let Some(url) = var("WEBHOOK_URL") {
+            match url::Url::parse(&url) {
+                Ok(_) => configs.push(NotifierConfig::Webhook {
+                    url,
+                    // WEBHOOK_SECRET is the older name
+                    secret: var("WEBHOOK_HMAC_SECRET").or_else(|| var("WEBHOOK_SECRET")),
+                }),
+                Err(e) => warn!("Ignoring invalid WEBHOOK_URL '{}': {}", url, e),
+            }
+        }
+
+        configs
+    }
+}
+
+fn split_list(s: &str) -> Vec<String> {
+    s.split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Build the backends for a list of configs
+pub fn build_notifiers(configs: &[NotifierConfig]) -> Vec<Box<dyn Notifier>> {
+    configs
+        .iter()
+        .map(|config| -> Box<dyn Notifier> {
+            match config {
+                NotifierConfig::Telegram { token, chat_ids, routes } => {
+                    Box::new(TelegramNotifier::new(token.clone(), chat_ids.clone()).with_routes(routes.clone()))
+                }
+                NotifierConfig::Webhook { url, secret } => Box::new(WebhookNotifier::new(url.clone(), secret.clone())),
+            }
+        })
+        .collect()
+}
+
+/// Validate every backend's recipients, dropping unreachable ones
+pub async fn validate_recipients(notifiers: &mut [Box<dyn Notifier>]) -> Vec<RecipientStatus> {
+    let mut statuses = Vec::new();
+    for notifier in notifiers {
+        statuses.extend(notifier.validate().await);
+    }
+    statuses
+}
+
+/// Dashboard URL for an event
+pub fn event_link(base_url: &str, event_id: &str) -> String {
+    let id: String = url::form_urlencoded::byte_serialize(event_id.as_bytes()).collect();
+    format!("{}/events/{}", base_url.trim_end_matches('/'), id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn vars(pairs: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let map: HashMap<String, String> = pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        move |name| map.get(name).cloned()
+    }
+
+    #[test]
+    fn test_parse_multi_backend_config() {
+        let configs = NotifierConfig::from_vars(vars(&[
+            ("TELEGRAM_BOT_TOKEN", "123:abc"),
+            ("TELEGRAM_CHAT_ID", "-1001, -1002"),
+            ("TELEGRAM_ROUTES", "protect=-1003:7,bogus"),
+            ("WEBHOOK_URL", "https://hooks.example.com/unifi"),
+            ("WEBHOOK_HMAC_SECRET", "s3cret"),
+            ("WEBHOOK_SECRET", "old"),
+        ]));
+
+        assert_eq!(
+            configs,
+            vec![
+                NotifierConfig::Telegram {
+                    token: "123:abc".to_string(),
+                    chat_ids: vec!["-1001".to_string(), "-1002".to_string()],
+                    routes: vec![TelegramRoute::parse("protect=-1003:7").unwrap()],
+                },
+                NotifierConfig::Webhook {
+                    url: "https://hooks.example.com/unifi".to_string(),
+                    secret: Some("s3cret".to_string()),
+                },
+            ]
+        );
+
+        let notifiers = build_notifiers(&configs);
+        let ids: Vec<&str> = notifiers.iter().map(|n| n.id()).collect();
+        assert_eq!(ids, vec!["telegram", "webhook"]);
+    }
+
+    #[test]
+    fn test_parse_webhook_secret_fallback() {
+        let configs = NotifierConfig::from_vars(vars(&[
+            ("WEBHOOK_URL", "https://hooks.example.com/unifi"),
+            ("WEBHOOK_SECRET", "old"),
+        ]));
+        assert_eq!(
+            configs,
+            vec![NotifierConfig::Webhook {
+                url: "https://hooks.example.com/unifi".to_string(),
+                secret: Some("old".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_skips_incomplete_backends() {
+        // Telegram needs both settings; the webhook URL must parse
+        let configs = NotifierConfig::from_vars(vars(&[
+            ("TELEGRAM_BOT_TOKEN", "123:abc"),
+            ("WEBHOOK_URL", "not a url"),
+        ]));
+        assert!(configs.is_empty());
+
+        let configs = NotifierConfig::from_vars(vars(&[("WEBHOOK_URL", "http://localhost:9000/hook")]));
+        assert_eq!(
+            configs,
+            vec![NotifierConfig::Webhook {
+                url: "http://localhost:9000/hook".to_string(),
+                secret: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_event_link_encodes_id() {
+        assert_eq!(event_link("https://unifi.local/", "test-123"), "https://unifi.local/events/test-123");
+        assert_eq!(event_link("https://unifi.local", "a/b c"), "https://unifi.local/events/a%2Fb+c");
+    }
+}
@@ -0,0 +1,505 @@
+//! Telegram Bot API backend
+
+use futures_util::future::BoxFuture;
+use tracing::{error, info, warn};
+
+use super::{Digest, Notifier, NotifyError};
+use crate::db::StoredEvent;
+use crate::unifi::types::EventSource;
+
+/// Default Telegram Bot API base URL
+const TELEGRAM_API_BASE: &str = "https://api.telegram.org";
+
+/// Sent by `/api/notifications/test`
+const TEST_MESSAGE: &str = "🧪 *Test Notification*\n\nThis is a test message from UniFi Monitor\\. If you see this, your Telegram integration is working correctly\\!";
+
+/// Events a Telegram route applies to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouteMatch {
+    /// All events from a source
+    Source(EventSource),
+    /// Event types starting with this prefix
+    EventTypePrefix(String),
+}
+
+/// Sends matching events to a specific Telegram chat (and optional forum topic)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TelegramRoute {
+    pub matcher: RouteMatch,
+    pub chat_id: String,
+    /// Forum topic (`message_thread_id`) within the chat
+    pub thread_id: Option<i64>,
+}
+
+impl TelegramRoute {
+    /// Parse `selector=chat_id[:thread_id]`, where the selector is a source
+    /// name (`protect`, `network`, `system`) or an event type prefix
+    pub fn parse(s: &str) -> Option<Self> {
+        let (selector, target) = s.split_once('=')?;
+        let selector = selector.trim();
+        let target = target.trim();
+        if selector.is_empty() || target.is_empty() {
+            return None;
+        }
+
+        let matcher = match EventSource::from_str(selector) {
+            Some(source) => RouteMatch::Source(source),
+            None => RouteMatch::EventTypePrefix(selector.to_string()),
+        };
+
+        let (chat_id, thread_id) = match target.split_once(':') {
+            Some((chat_id, thread_id)) => (chat_id, Some(thread_id.parse().ok()?)),
+            None => (target, None),
+        };
+        if chat_id.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            matcher,
+            chat_id: chat_id.to_string(),
+            thread_id,
+        })
+    }
+
+    fn matches(&self, event: &StoredEvent) -> bool {
+        match &self.matcher {
+            RouteMatch::Source(source) => event.source == *source,
+            RouteMatch::EventTypePrefix(prefix) => event.event_type.starts_with(prefix.as_str()),
+        }
+    }
+}
+
+/// Pick the chats and topic for an event: the first matching route, or every default recipient
+fn resolve_telegram_targets<'a>(
+    routes: &'a [TelegramRoute],
+    default_chat_ids: &'a [String],
+    event: &StoredEvent,
+) -> Vec<(&'a str, Option<i64>)> {
+    match routes.iter().find(|route| route.matches(event)) {
+        Some(route) => vec![(route.chat_id.as_str(), route.thread_id)],
+        None => default_chat_ids.iter().map(|id| (id.as_str(), None)).collect(),
+    }
+}
+
+/// Recipient as recorded in a partial delivery: `chat_id[:thread_id]`
+fn recipient_key(chat_id: &str, thread_id: Option<i64>) -> String {
+    match thread_id {
+        Some(thread_id) => format!("{}:{}", chat_id, thread_id),
+        None => chat_id.to_string(),
+    }
+}
+
+/// Outcome of checking a Telegram recipient with `getChat` at startup
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecipientCheck {
+    /// The bot can reach this chat
+    Reachable,
+    /// Telegram rejected the chat (e.g. wrong ID, bot not a member)
+    Rejected(String),
+    /// Couldn't check (e.g. network error); kept in case it's transient
+    Unverified(String),
+}
+
+impl RecipientCheck {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RecipientCheck::Reachable => "reachable",
+            RecipientCheck::Rejected(_) => "rejected",
+            RecipientCheck::Unverified(_) => "unverified",
+        }
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        match self {
+            RecipientCheck::Reachable => None,
+            RecipientCheck::Rejected(e) | RecipientCheck::Unverified(e) => Some(e),
+        }
+    }
+}
+
+/// Startup validation result for one Telegram chat
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecipientStatus {
+    pub chat_id: String,
+    pub check: RecipientCheck,
+}
+
+/// Sends notifications through a Telegram bot
+pub struct TelegramNotifier {
+    token: String,
+    /// Default recipients for events no route matches
+    chat_ids: Vec<String>,
+    /// Per-event chat routing, checked in order before the default chat
+    routes: Vec<TelegramRoute>,
+    api_base: String,
+    client: reqwest::Client,
+}
+
+impl TelegramNotifier {
+    pub fn new(token: String, chat_ids: Vec<String>) -> Self {
+        Self {
+            token,
+            chat_ids,
+            routes: Vec::new(),
+            api_base: TELEGRAM_API_BASE.to_string(),
+            client: super::http_client(),
+        }
+    }
+
+    /// Route events to other chats/topics; unmatched events use the default chat
+    pub fn with_routes(mut self, routes: Vec<TelegramRoute>) -> Self {
+        self.routes = routes;
+        self
+    }
+
+    /// Override the Telegram API base URL (for testing)
+    pub fn with_api_base(mut self, api_base: impl Into<String>) -> Self {
+        self.api_base = api_base.into();
+        self
+    }
+
+    /// Check every configured chat with Telegram's `getChat` and drop the ones
+    /// Telegram rejects, so a mistyped chat ID doesn't fail every notification
+    ///
+    /// Routes to a rejected chat are removed (their events fall back to the
+    /// default recipients). If every default recipient is rejected they are
+    /// all kept, so notifications keep failing loudly rather than vanishing.
+    pub async fn validate_recipients(&mut self) -> Vec<RecipientStatus> {
+        let mut chat_ids: Vec<String> = Vec::new();
+        for chat_id in self.chat_ids.iter().chain(self.routes.iter().map(|r| &r.chat_id)) {
+            if !chat_ids.contains(chat_id) {
+                chat_ids.push(chat_id.clone());
+            }
+        }
+
+        let mut statuses = Vec::with_capacity(chat_ids.len());
+        for chat_id in chat_ids {
+            let check = self.check_recipient(&chat_id).await;
+            match &check {
+                RecipientCheck::Reachable => info!(chat_id, "Telegram recipient reachable"),
+                RecipientCheck::Rejected(e) => warn!(chat_id, error = %e, "Telegram recipient rejected, dropping it"),
+                RecipientCheck::Unverified(e) => warn!(chat_id, error = %e, "Could not verify Telegram recipient"),
+            }
+            statuses.push(RecipientStatus { chat_id, check });
+        }
+
+        self.retain_valid_recipients(&statuses);
+        statuses
+    }
+
+    async fn check_recipient(&self, chat_id: &str) -> RecipientCheck {
+        let url = format!("{}/bot{}/getChat", self.api_base, self.token);
+        match self
+            .client
+            .post(&url)
+            .json(&serde_json::json!({ "chat_id": chat_id }))
+            .send()
+            .await
+        {
+            Ok(response) if response.status().is_success() => RecipientCheck::Reachable,
+            Ok(response) => {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                let error = format!("{}: {}", status, body);
+                // Only a definite answer about the chat drops it; rate limits
+                // and server errors may pass
+                let rejected = status == reqwest::StatusCode::FORBIDDEN
+                    || (status == reqwest::StatusCode::BAD_REQUEST && body.contains("chat not found"));
+                if rejected {
+                    RecipientCheck::Rejected(error)
+                } else {
+                    RecipientCheck::Unverified(error)
+                }
+            }
+            Err(e) => RecipientCheck::Unverified(e.to_string()),
+        }
+    }
+
+    /// Send a MarkdownV2 message to each chat (and optional topic) not in
+    /// `delivered`, adding each one that succeeds
+    ///
+    /// Every chat is tried even if an earlier one fails; the error lists each
+    /// failure as "<chat>: <error>".
+    async fn send_message(
+        &self,
+        message: &str,
+        targets: &[(&str, Option<i64>)],
+        delivered: &mut Vec<String>,
+    ) -> Result<(), NotifyError> {
+        let mut errors = Vec::new();
+        for &(chat_id, thread_id) in targets {
+            let recipient = recipient_key(chat_id, thread_id);
+            if delivered.contains(&recipient) {
+                continue;
+            }
+            match self.send_to_chat(message, chat_id, thread_id).await {
+                Ok(()) => delivered.push(recipient),
+                Err(NotifyError::Request(e) | NotifyError::Api(e)) => errors.push(format!("{}: {}", recipient, e)),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(NotifyError::Api(errors.join("; ")))
+        }
+    }
+
+    async fn send_to_chat(&self, message: &str, chat_id: &str, thread_id: Option<i64>) -> Result<(), NotifyError> {
+        let url = format!("{}/bot{}/sendMessage", self.api_base, self.token);
+        let mut payload = serde_json::json!({
+            "chat_id": chat_id,
+            "text": message,
+            "parse_mode": "MarkdownV2"
+        });
+        if let Some(thread_id) = thread_id {
+            payload["message_thread_id"] = thread_id.into();
+        }
+
+        let response = self
+            .client
+            .post(&url)
+            .json(&payload)
+            .send()
+            .await
+            .map_err(|e| NotifyError::Request(e.to_string()))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(NotifyError::Api(format!("{}: {}", status, body)));
+        }
+        Ok(())
+    }
+
+    /// Drop recipients (and routes) whose chat Telegram rejected
+    fn retain_valid_recipients(&mut self, statuses: &[RecipientStatus]) {
+        let rejected = |chat_id: &String| {
+            statuses
+                .iter()
+                .any(|s| &s.chat_id == chat_id && matches!(s.check, RecipientCheck::Rejected(_)))
+        };
+
+        if self.chat_ids.iter().all(rejected) {
+            if !self.chat_ids.is_empty() {
+                error!("No default Telegram recipient is reachable");
+            }
+        } else {
+            self.chat_ids.retain(|id| !rejected(id));
+        }
+        self.routes.retain(|route| !rejected(&route.chat_id));
+    }
+}
+
+impl Notifier for TelegramNotifier {
+    fn id(&self) -> &str {
+        "telegram"
+    }
+
+    fn send<'a>(&'a self, event: &'a StoredEvent, link: Option<&'a str>) -> BoxFuture<'a, Result<(), NotifyError>> {
+        Box::pin(async move { self.send_remaining(event, link, &mut Vec::new()).await })
+    }
+
+    fn send_remaining<'a>(
+        &'a self,
+        event: &'a StoredEvent,
+        link: Option<&'a str>,
+        delivered: &'a mut Vec<String>,
+    ) -> BoxFuture<'a, Result<(), NotifyError>> {
+        let message = telegram_message(event, link);
+        let targets = resolve_telegram_targets(&self.routes, &self.chat_ids, event);
+        Box::pin(async move { self.send_message(&message, &targets, delivered).await })
+    }
+
+    fn send_digest<'a>(&'a self, digest: &'a Digest) -> BoxFuture<'a, Result<(), NotifyError>> {
+        let message = format!("📊 *Digest*\n\n{}", escape_markdown(&digest.text()));
+        let targets: Vec<_> = self.chat_ids.iter().map(|id| (id.as_str(), None)).collect();
+        Box::pin(async move { self.send_message(&message, &targets, &mut Vec::new()).await })
+    }
+
+    fn send_test(&self) -> BoxFuture<'_, Result<(), NotifyError>> {
+        // Every default recipient is tried, so one bad chat doesn't hide the rest
+        let targets: Vec<_> = self.chat_ids.iter().map(|id| (id.as_str(), None)).collect();
+        Box::pin(async move { self.send_message(TEST_MESSAGE, &targets, &mut Vec::new()).await })
+    }
+
+    fn validate(&mut self) -> BoxFuture<'_, Vec<RecipientStatus>> {
+        Box::pin(self.validate_recipients())
+    }
+}
+
+/// Format a notification as Telegram MarkdownV2, with an optional link to the event
+fn telegram_message(event: &StoredEvent, link: Option<&str>) -> String {
+    let timestamp_str = chrono::DateTime::from_timestamp(event.timestamp, 0)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_else(|| "unknown time".to_string());
+    let mut message = format!(
+        "🔔 *{}*\n\n{}\n\n_Source: {} \\| {}_",
+        escape_markdown(&event.event_type),
+        escape_markdown(&event.summary),
+        escape_markdown(&event.source.to_string()),
+        escape_markdown(&timestamp_str)
+    );
+
+    if let Some(link) = link {
+        // Inside a MarkdownV2 link target only ')' and '\' need escaping
+        let target = link.replace('\\', "\\\\").replace(')', "\\)");
+        message.push_str(&format!("\n\n[Open event]({})", target));
+    }
+
+    message
+}
+
+/// Escape special characters for Telegram MarkdownV2
+fn escape_markdown(text: &str) -> String {
+    let special_chars = ['_', '*', '[', ']', '(', ')', '~', '`', '>', '#', '+', '-', '=', '|', '{', '}', '.', '!'];
+    let mut result = String::with_capacity(text.len() * 2);
+    for c in text.chars() {
+        if special_chars.contains(&c) {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_markdown() {
+        assert_eq!(escape_markdown("hello"), "hello");
+        assert_eq!(escape_markdown("hello_world"), "hello\\_world");
+        assert_eq!(escape_markdown("test.event"), "test\\.event");
+    }
+
+    #[test]
+    fn test_telegram_route_resolution() {
+        let routes: Vec<TelegramRoute> = ["protect=-1001:42", "EVT_AP_=-1002", "network=-1003"]
+            .iter()
+            .map(|s| TelegramRoute::parse(s).unwrap())
+            .collect();
+        assert_eq!(routes[0].matcher, RouteMatch::Source(EventSource::Protect));
+        assert_eq!(routes[1].matcher, RouteMatch::EventTypePrefix("EVT_AP_".to_string()));
+        let defaults = vec!["default".to_string(), "backup".to_string()];
+
+        let protect = StoredEvent::test_notify("motion");
+        assert_eq!(resolve_telegram_targets(&routes, &defaults, &protect), vec![("-1001", Some(42))]);
+
+        // First matching route wins
+        let mut ap_lost = StoredEvent::test_notify("EVT_AP_LOST_CONTACT");
+        ap_lost.source = EventSource::Network;
+        assert_eq!(resolve_telegram_targets(&routes, &defaults, &ap_lost), vec![("-1002", None)]);
+
+        let mut other_network = StoredEvent::test_notify("EVT_SW_LOST_CONTACT");
+        other_network.source = EventSource::Network;
+        assert_eq!(resolve_telegram_targets(&routes, &defaults, &other_network), vec![("-1003", None)]);
+
+        // No match goes to every default recipient
+        let mut system = StoredEvent::test_notify("backup");
+        system.source = EventSource::System;
+        assert_eq!(
+            resolve_telegram_targets(&routes, &defaults, &system),
+            vec![("default", None), ("backup", None)]
+        );
+
+        assert!(TelegramRoute::parse("protect").is_none());
+        assert!(TelegramRoute::parse("protect=-1001:topic").is_none());
+        assert!(TelegramRoute::parse("=-1001").is_none());
+    }
+
+    fn recipient(chat_id: &str, check: RecipientCheck) -> RecipientStatus {
+        RecipientStatus {
+            chat_id: chat_id.to_string(),
+            check,
+        }
+    }
+
+    #[test]
+    fn test_retain_valid_recipients() {
+        let mut notifier = TelegramNotifier::new(
+            "token".into(),
+            vec!["good".into(), "typo".into(), "offline".into()],
+        )
+        .with_routes(vec![
+            TelegramRoute::parse("protect=typo:3").unwrap(),
+            TelegramRoute::parse("network=good").unwrap(),
+        ]);
+
+        notifier.retain_valid_recipients(&[
+            recipient("good", RecipientCheck::Reachable),
+            recipient("typo", RecipientCheck::Rejected("400: chat not found".into())),
+            recipient("offline", RecipientCheck::Unverified("timeout".into())),
+        ]);
+        // Rejected recipients and routes are dropped; unverified ones are kept
+        assert_eq!(notifier.chat_ids, vec!["good".to_string(), "offline".to_string()]);
+        assert_eq!(notifier.routes.len(), 1);
+        assert_eq!(notifier.routes[0].chat_id, "good");
+
+        // If every default recipient is rejected, keep them so failures stay visible
+        let mut notifier = TelegramNotifier::new("token".into(), vec!["typo".into()]);
+        notifier.retain_valid_recipients(&[recipient("typo", RecipientCheck::Rejected("400".into()))]);
+        assert_eq!(notifier.chat_ids, vec!["typo".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_validate_recipients_with_get_chat() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/bottoken/getChat"))
+            .and(body_partial_json(serde_json::json!({"chat_id": "good"})))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+        for (chat_id, status, body) in [
+            ("typo", 400, "Bad Request: chat not found"),
+            ("kicked", 403, "Forbidden: bot was kicked from the group chat"),
+            ("limited", 429, "Too Many Requests: retry after 5"),
+            ("flaky", 502, "Bad Gateway"),
+        ] {
+            Mock::given(method("POST"))
+                .and(path("/bottoken/getChat"))
+                .and(body_partial_json(serde_json::json!({"chat_id": chat_id})))
+                .respond_with(ResponseTemplate::new(status).set_body_string(body))
+                .mount(&server)
+                .await;
+        }
+
+        let chat_ids = ["good", "typo", "kicked", "limited", "flaky"];
+        let mut notifier = TelegramNotifier::new("token".into(), chat_ids.iter().map(|id| id.to_string()).collect())
+            .with_api_base(server.uri());
+
+        let statuses = notifier.validate().await;
+        let checks: Vec<_> = statuses.iter().map(|s| (s.chat_id.as_str(), s.check.as_str())).collect();
+        assert_eq!(
+            checks,
+            vec![
+                ("good", "reachable"),
+                ("typo", "rejected"),
+                ("kicked", "rejected"),
+                ("limited", "unverified"),
+                ("flaky", "unverified"),
+            ]
+        );
+        assert!(statuses[1].check.error().unwrap().contains("chat not found"));
+        // Rate limits and server errors keep the chat
+        assert_eq!(notifier.chat_ids, vec!["good", "limited", "flaky"]);
+    }
+
+    #[test]
+    fn test_notification_deep_link() {
+        let event = StoredEvent::test_notify("motion");
+
+        let link = super::super::event_link("https://unifi.local/", &event.id);
+        let message = telegram_message(&event, Some(&link));
+        assert!(message.ends_with("\n\n[Open event](https://unifi.local/events/test-123)"));
+
+        // No link without a base URL
+        assert!(!telegram_message(&event, None).contains("Open event"));
+    }
+}
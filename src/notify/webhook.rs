@@ -0,0 +1,164 @@
+//! Generic HTTP webhook backend
+
+use futures_util::future::BoxFuture;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use super::{Digest, Notifier, NotifyError};
+use crate::db::StoredEvent;
+
+/// Header carrying the HMAC signature of a webhook request body
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "X-Signature";
+
+/// Sign a webhook request body with HMAC-SHA256
+///
+/// Returns `sha256=<lowercase hex digest>` over the exact body bytes, the same
+/// scheme as GitHub's `X-Hub-Signature-256`. Receivers verify by computing the
+/// HMAC of the raw body with the shared secret and comparing in constant time.
+pub fn sign_webhook_body(secret: &[u8], body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    let digest = mac.finalize().into_bytes();
+
+    let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("sha256={}", hex)
+}
+
+/// POSTs each event as JSON to a URL, signed when a secret is configured
+pub struct WebhookNotifier {
+    url: String,
+    secret: Option<String>,
+    client: reqwest::Client,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: String, secret: Option<String>) -> Self {
+        Self {
+            url,
+            secret,
+            client: super::http_client(),
+        }
+    }
+}
+
+/// JSON body sent for an event
+fn webhook_body(event: &StoredEvent, link: Option<&str>) -> serde_json::Value {
+    serde_json::json!({
+        "id": event.id,
+        "source": event.source,
+        "event_type": event.event_type,
+        "severity": event.severity,
+        "summary": event.summary,
+        "timestamp": event.timestamp,
+        "classification": event.classification.as_str(),
+        "link": link,
+    })
+}
+
+impl Notifier for WebhookNotifier {
+    fn id(&self) -> &str {
+        "webhook"
+    }
+
+    fn send<'a>(&'a self, event: &'a StoredEvent, link: Option<&'a str>) -> BoxFuture<'a, Result<(), NotifyError>> {
+        Box::pin(self.post(webhook_body(event, link)))
+    }
+
+    fn send_digest<'a>(&'a self, digest: &'a Digest) -> BoxFuture<'a, Result<(), NotifyError>> {
+        Box::pin(self.post(digest_body(digest)))
+    }
+
+    fn send_test(&self) -> BoxFuture<'_, Result<(), NotifyError>> {
+        Box::pin(self.post(serde_json::json!({
+            "type": "test",
+            "text": "This is a test message from UniFi Monitor",
+        })))
+    }
+}
+
+impl WebhookNotifier {
+    async fn post(&self, body: serde_json::Value) -> Result<(), NotifyError> {
+        let body = body.to_string();
+
+        let mut request = self
+            .client
+            .post(&self.url)
+            .header("Content-Type", "application/json");
+        if let Some(secret) = &self.secret {
+            request = request.header(WEBHOOK_SIGNATURE_HEADER, sign_webhook_body(secret.as_bytes(), body.as_bytes()));
+        }
+
+        let response = request
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| NotifyError::Request(e.to_string()))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(NotifyError::Api(format!("{}: {}", status, body)));
+        }
+
+        Ok(())
+    }
+}
+
+/// JSON body sent for a digest, told apart from events by `type`
+fn digest_body(digest: &Digest) -> serde_json::Value {
+    let counts: serde_json::Map<String, serde_json::Value> = digest
+        .by_classification
+        .iter()
+        .map(|(c, count)| (c.as_str().to_string(), (*count).into()))
+        .collect();
+    let top: Vec<serde_json::Value> = digest
+        .top_event_types
+        .iter()
+        .map(|(event_type, count)| serde_json::json!({"event_type": event_type, "count": count}))
+        .collect();
+
+    serde_json::json!({
+        "type": "digest",
+        "since": digest.since,
+        "until": digest.until,
+        "total": digest.total,
+        "by_classification": counts,
+        "top_event_types": top,
+        "text": digest.text(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_webhook_body() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign_webhook_body(b"Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_webhook_sends_signed_body() {
+        use wiremock::matchers::{body_partial_json, header_exists, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .and(header_exists(WEBHOOK_SIGNATURE_HEADER))
+            .and(body_partial_json(serde_json::json!({"id": "test-123", "event_type": "motion"})))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let event = StoredEvent::test_notify("motion");
+
+        let notifier = WebhookNotifier::new(format!("{}/hook", server.uri()), Some("secret".to_string()));
+        notifier.send(&event, None).await.unwrap();
+    }
+}
@@ -0,0 +1,96 @@
+//! Redaction of personal data (hostnames, user names, addresses) in events
+//!
+//! [`anonymize_payload`] scrubs a raw payload, e.g. for test fixtures.
+//! [`redact_summary`] hides the same fields where they appear in a summary,
+//! for privacy mode on shared displays.
+
+/// Payload fields that identify people or their devices
+pub const SENSITIVE_KEYS: &[&str] = &["mac", "ip", "hostname", "name", "id", "userId", "user"];
+
+/// Replacement for sensitive values in summaries
+pub const REDACTED: &str = "REDACTED";
+
+/// Values shorter than this aren't redacted from summaries, where they would
+/// mostly match unrelated text
+const MIN_REDACTED_LEN: usize = 3;
+
+/// Replace every sensitive field in a payload (at any depth) with a placeholder
+pub fn anonymize_payload(value: &serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(obj) => obj
+            .iter()
+            .map(|(key, value)| {
+                let value = if SENSITIVE_KEYS.contains(&key.as_str()) {
+                    serde_json::Value::String(format!("REDACTED_{}", key.to_uppercase()))
+                } else {
+                    anonymize_payload(value)
+                };
+                (key.clone(), value)
+            })
+            .collect(),
+        serde_json::Value::Array(items) => items.iter().map(anonymize_payload).collect(),
+        other => other.clone(),
+    }
+}
+
+/// String values of the sensitive fields in a payload, at any depth
+pub fn sensitive_values(value: &serde_json::Value) -> Vec<String> {
+    let mut values = Vec::new();
+    collect_sensitive_values(value, &mut values);
+    values
+}
+
+fn collect_sensitive_values(value: &serde_json::Value, values: &mut Vec<String>) {
+    match value {
+        serde_json::Value::Object(obj) => {
+            for (key, value) in obj {
+                match value.as_str() {
+                    Some(s) if SENSITIVE_KEYS.contains(&key.as_str()) => values.push(s.to_string()),
+                    _ => collect_sensitive_values(value, values),
+                }
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                collect_sensitive_values(item, values);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Replace the payload's sensitive values where they appear in a summary
+/// (e.g. "Client sync: laptop-alice" becomes "Client sync: REDACTED")
+pub fn redact_summary(summary: &str, payload: &serde_json::Value) -> String {
+    let mut values: Vec<String> = sensitive_values(payload)
+        .into_iter()
+        .filter(|v| v.chars().count() >= MIN_REDACTED_LEN)
+        .collect();
+    // Longest first, so a value containing another is replaced whole
+    values.sort_by_key(|v| std::cmp::Reverse(v.len()));
+
+    let mut redacted = summary.to_string();
+    for value in values {
+        redacted = redacted.replace(&value, REDACTED);
+    }
+    redacted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_summary() {
+        let payload = serde_json::json!({
+            "data": [{"hostname": "laptop-alice", "mac": "aa:bb:cc:dd:ee:ff", "id": "7", "rssi": -60}]
+        });
+        assert_eq!(redact_summary("Client sync: laptop-alice", &payload), "Client sync: REDACTED");
+        // Short values and non-sensitive fields are left alone
+        assert_eq!(redact_summary("Signal -60 on channel 7", &payload), "Signal -60 on channel 7");
+
+        let anonymized = anonymize_payload(&payload);
+        assert_eq!(anonymized["data"][0]["hostname"], "REDACTED_HOSTNAME");
+        assert_eq!(anonymized["data"][0]["rssi"], -60);
+    }
+}
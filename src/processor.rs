@@ -1,32 +1,131 @@
 //! Event processor - stores events and queues notifications
 
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc;
 use tracing::{debug, error, info, warn};
+use tracing_appender::non_blocking::{NonBlocking, WorkerGuard};
 
-use crate::db::{Classification, Database, StoredEvent};
+use crate::db::{settings, Classification, Database, StoredEvent};
+use crate::notify::{event_link, Notifier};
+use crate::unifi::types::{extract_key_fields, generate_event_id, EventSource, Severity};
 use crate::unifi::UnifiEvent;
 
+/// Event type of the synthetic event emitted when a notification is given up on
+pub const NOTIFICATION_FAILED_EVENT_TYPE: &str = "monitor.notification_failed";
+
+/// Event type of the synthetic event emitted when an event type is first seen
+pub const NEW_EVENT_TYPE_EVENT_TYPE: &str = "monitor.new_event_type";
+
+/// Upper bound on the delay between notification retries
+pub const MAX_RETRY_BACKOFF_SECS: i64 = 60;
+
+/// Delay before the retry that follows failed attempt number `attempts`
+/// (1s, doubling up to [`MAX_RETRY_BACKOFF_SECS`])
+fn retry_backoff_secs(attempts: i32) -> i64 {
+    (1i64 << (attempts - 1).clamp(0, 6)).min(MAX_RETRY_BACKOFF_SECS)
+}
+
 /// Event processor configuration
 #[derive(Debug, Clone)]
 pub struct ProcessorConfig {
     /// Maximum notification retry attempts
     pub max_notify_attempts: i32,
+    /// Event types kept as one row per entity (see `Database::upsert_entity_state`)
+    /// instead of being appended to the event history
+    pub compact_event_types: Vec<String>,
+    /// Drop events whose timestamp is older than this at ingestion, before
+    /// they are stored (e.g. months-old history from the backfill). This only
+    /// filters incoming events; nothing already stored is deleted.
+    pub max_event_age: Option<chrono::Duration>,
 }
 
 impl Default for ProcessorConfig {
     fn default() -> Self {
         Self {
             max_notify_attempts: 10,
+            compact_event_types: Vec::new(),
+            max_event_age: None,
+        }
+    }
+}
+
+/// Append-only JSONL export of processed events (rotated daily)
+///
+/// Writes go through a non-blocking worker, so a slow or full disk never
+/// stalls ingestion; lines are dropped instead.
+pub struct JsonlSink {
+    writer: NonBlocking,
+    /// Flushes pending lines when the sink is dropped
+    _guard: WorkerGuard,
+}
+
+impl JsonlSink {
+    /// Create a sink writing to `path`, rotated daily as `path.YYYY-MM-DD`
+    pub fn new<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let path = path.as_ref();
+        let dir = path
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or_else(|| Path::new("."));
+        let file_name = path.file_name().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "EVENT_JSONL_PATH has no file name")
+        })?;
+
+        std::fs::create_dir_all(dir)?;
+
+        let appender = tracing_appender::rolling::daily(dir, file_name);
+        let (writer, guard) = tracing_appender::non_blocking(appender);
+
+        Ok(Self {
+            writer,
+            _guard: guard,
+        })
+    }
+
+    /// Append an event as a single JSON line (best-effort)
+    pub fn write(&self, event: &StoredEvent) {
+        let mut line = jsonl_line(event).to_string();
+        line.push('\n');
+
+        let mut writer = self.writer.clone();
+        if let Err(e) = writer.write_all(line.as_bytes()) {
+            warn!(id = event.id, error = %e, "Failed to write event to JSONL sink");
         }
     }
 }
 
+/// JSON representation of an event for the JSONL sink
+fn jsonl_line(event: &StoredEvent) -> serde_json::Value {
+    serde_json::json!({
+        "id": event.id,
+        "source": event.source,
+        "event_type": event.event_type,
+        "severity": event.severity,
+        "summary": event.summary,
+        "timestamp": event.timestamp,
+        "classification": event.classification.as_str(),
+        "created_at": event.created_at,
+        "payload": event.payload,
+    })
+}
+
 /// Event processor - receives events, stores them, and queues notifications
 pub struct EventProcessor {
     db: Database,
     config: ProcessorConfig,
     /// Channel to send events that need notification
     notify_tx: mpsc::Sender<StoredEvent>,
+    /// Optional JSONL export of every stored event
+    jsonl_sink: Option<JsonlSink>,
+    /// Where to emit `monitor.new_event_type` events (if enabled)
+    new_type_tx: Option<mpsc::Sender<UnifiEvent>>,
+    /// Types already announced, so each is announced once per run
+    announced_types: Mutex<HashSet<String>>,
+    /// Events from before this time (unix seconds) aren't announced
+    announce_since: i64,
 }
 
 impl EventProcessor {
@@ -40,19 +139,86 @@ impl EventProcessor {
             db,
             config,
             notify_tx,
+            jsonl_sink: None,
+            new_type_tx: None,
+            announced_types: Mutex::new(HashSet::new()),
+            announce_since: chrono::Utc::now().timestamp(),
+        }
+    }
+
+    /// Emit a `monitor.new_event_type` System event into the event pipeline
+    /// when an event type is stored for the first time with no rule for it
+    ///
+    /// Events from before startup (e.g. the historical backfill) aren't
+    /// announced. Neither is anything while the database starts out empty,
+    /// since every type is new then; that run only learns the baseline.
+    pub fn with_new_type_events(mut self, new_type_tx: mpsc::Sender<UnifiEvent>) -> Self {
+        match self.db.count_events(&crate::db::EventFilter::default()) {
+            Ok(0) => info!("No stored events yet, new event types will be announced from the next run"),
+            Ok(_) => self.new_type_tx = Some(new_type_tx),
+            Err(e) => warn!("Failed to count stored events, not announcing new event types: {}", e),
         }
+        self
+    }
+
+    /// Also append every stored event to a JSONL sink
+    pub fn with_jsonl_sink(mut self, sink: JsonlSink) -> Self {
+        self.jsonl_sink = Some(sink);
+        self
+    }
+
+    /// Whether events of this type only update their entity's state, with
+    /// no row of their own in `events`
+    pub fn is_compacted(&self, event_type: &str) -> bool {
+        self.config.compact_event_types.iter().any(|t| t == event_type)
     }
 
     /// Process an incoming event
     /// - Stores it in the database
     /// - Applies classification rules
+    /// - Appends it to the JSONL sink (if configured)
     /// - Queues for notification if classified as "notify"
+    ///
+    /// Events older than `max_event_age` are dropped and reported as
+    /// `Suppressed`, since like suppressed events they are never stored.
     pub async fn process(&self, event: UnifiEvent) -> Result<Classification, ProcessorError> {
+        if let Some(max_age) = self.config.max_event_age {
+            if event.timestamp < chrono::Utc::now() - max_age {
+                debug!(
+                    id = event.id,
+                    event_type = event.event_type,
+                    timestamp = %event.timestamp,
+                    "Dropped event older than the ingestion cutoff"
+                );
+                return Ok(Classification::Suppressed);
+            }
+        }
+
+        // Compacted types only update the entity's current state (never notified)
+        if self.is_compacted(&event.event_type) {
+            let classification = self.db.get_classification(&event.event_type)?;
+            if classification != Classification::Suppressed {
+                let entity_id = entity_key(&event);
+                let count = self.db.upsert_entity_state(&event, &entity_id)?;
+                debug!(
+                    event_type = event.event_type,
+                    entity_id,
+                    count,
+                    "Compacted event into entity state"
+                );
+            }
+            return Ok(classification);
+        }
+
         // Store event and get classification
-        let classification = self
+        let outcome = self
             .db
-            .store_event(&event)
+            .store_event_detailed(&event)
             .map_err(ProcessorError::Database)?;
+        let classification = outcome.classification;
+        if outcome.new_event_type {
+            self.announce_new_type(&event);
+        }
 
         // Skip logging for suppressed events
         if classification != Classification::Suppressed {
@@ -64,22 +230,34 @@ impl EventProcessor {
             );
         }
 
+        // Suppressed events are not stored, so there is nothing to export or notify
+        if classification == Classification::Suppressed {
+            return Ok(classification);
+        }
+
+        let stored = StoredEvent {
+            id: event.id.clone(),
+            source: event.source,
+            event_type: event.event_type.clone(),
+            severity: event.severity,
+            payload: event.raw.clone(),
+            summary: event.summary.clone(),
+            timestamp: event.timestamp.timestamp(),
+            classification,
+            notified: false,
+            notify_attempts: 0,
+            created_at: chrono::Utc::now().timestamp(),
+            pinned: false,
+            acknowledged_at: None,
+        };
+
+        // A duplicate ID was already exported when it was first stored
+        if let Some(sink) = self.jsonl_sink.as_ref().filter(|_| outcome.inserted) {
+            sink.write(&stored);
+        }
+
         // If notify, queue for notification
         if classification == Classification::Notify {
-            let stored = StoredEvent {
-                id: event.id.clone(),
-                source: event.source,
-                event_type: event.event_type.clone(),
-                severity: event.severity,
-                payload: event.raw.clone(),
-                summary: event.summary.clone(),
-                timestamp: event.timestamp.timestamp(),
-                classification,
-                notified: false,
-                notify_attempts: 0,
-                created_at: chrono::Utc::now().timestamp(),
-            };
-
             if let Err(e) = self.notify_tx.send(stored).await {
                 error!("Failed to queue notification: {}", e);
             }
@@ -88,6 +266,30 @@ impl EventProcessor {
         Ok(classification)
     }
 
+    /// Emit a `monitor.new_event_type` event for a type seen for the first time
+    fn announce_new_type(&self, event: &UnifiEvent) {
+        let Some(ref new_type_tx) = self.new_type_tx else {
+            return;
+        };
+        // Announcements (and the monitor's own events) are not announced
+        if event.event_type.starts_with("monitor.") {
+            return;
+        }
+        if event.timestamp.timestamp() < self.announce_since {
+            debug!(event_type = event.event_type, "Not announcing new event type from before startup");
+            return;
+        }
+        if !self.announced_types.lock().unwrap().insert(event.event_type.clone()) {
+            return;
+        }
+
+        info!(event_type = event.event_type, "New event type observed");
+        // The pipeline may be waiting on this call, so never block on a full channel
+        if let Err(e) = new_type_tx.try_send(new_event_type_event(event)) {
+            warn!(event_type = event.event_type, error = %e, "Failed to emit new event type event");
+        }
+    }
+
     /// Load pending notifications from database and queue them
     /// Call this on startup to handle any notifications that were queued but not sent
     pub async fn load_pending_notifications(&self) -> Result<usize, ProcessorError> {
@@ -123,6 +325,41 @@ impl EventProcessor {
     }
 }
 
+/// Entity a compacted event describes: its first identifying payload field,
+/// falling back to the event ID
+fn entity_key(event: &UnifiEvent) -> String {
+    extract_key_fields(&event.raw)
+        .into_iter()
+        .next()
+        .unwrap_or_else(|| event.id.clone())
+}
+
+/// Build the synthetic System event announcing a first-seen event type
+fn new_event_type_event(event: &UnifiEvent) -> UnifiEvent {
+    let timestamp = chrono::Utc::now();
+    let id = generate_event_id(
+        EventSource::System,
+        NEW_EVENT_TYPE_EVENT_TYPE,
+        timestamp,
+        &[&event.event_type],
+    );
+
+    UnifiEvent {
+        id,
+        timestamp,
+        source: EventSource::System,
+        event_type: NEW_EVENT_TYPE_EVENT_TYPE.to_string(),
+        summary: format!("New event type observed: {}", event.event_type),
+        severity: Some(Severity::Info),
+        raw: serde_json::json!({
+            "event_type": event.event_type,
+            "source": event.source,
+            "event_id": event.id,
+            "event_summary": event.summary,
+        }),
+    }
+}
+
 /// Errors that can occur during event processing
 #[derive(Debug, thiserror::Error)]
 pub enum ProcessorError {
@@ -130,227 +367,876 @@ pub enum ProcessorError {
     Database(#[from] rusqlite::Error),
 }
 
-/// Notification sender task - sends Telegram notifications
+/// What the sender does with notifications while they are muted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MuteMode {
+    /// Mark them notified and log them as suppressed
+    #[default]
+    Drop,
+    /// Leave them pending so they're delivered on unmute
+    Hold,
+}
+
+impl MuteMode {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "drop" => Some(MuteMode::Drop),
+            "hold" => Some(MuteMode::Hold),
+            _ => None,
+        }
+    }
+}
+
+/// Notification sender task - delivers notifications through every configured backend
 pub struct NotificationSender {
     db: Database,
     notify_rx: mpsc::Receiver<StoredEvent>,
-    telegram_token: String,
-    telegram_chat_id: String,
+    notifiers: Arc<Vec<Box<dyn Notifier>>>,
     max_attempts: i32,
+    /// Dashboard URL used to link each notification to its event
+    public_base_url: Option<String>,
+    /// Where to emit `monitor.notification_failed` events (if enabled)
+    failure_tx: Option<mpsc::Sender<UnifiEvent>>,
+    /// When this sender was created (unix seconds)
+    started_at: i64,
+    /// Quiet period after startup during which delivery is suppressed
+    quiet_period: Option<std::time::Duration>,
+    mute_mode: MuteMode,
 }
 
 impl NotificationSender {
     pub fn new(
         db: Database,
         notify_rx: mpsc::Receiver<StoredEvent>,
-        telegram_token: String,
-        telegram_chat_id: String,
+        notifiers: Arc<Vec<Box<dyn Notifier>>>,
         max_attempts: i32,
     ) -> Self {
         Self {
             db,
             notify_rx,
-            telegram_token,
-            telegram_chat_id,
+            notifiers,
             max_attempts,
+            public_base_url: None,
+            failure_tx: None,
+            started_at: chrono::Utc::now().timestamp(),
+            quiet_period: None,
+            mute_mode: MuteMode::default(),
         }
     }
 
+    /// Choose whether muted notifications are dropped or held until unmute
+    pub fn with_mute_mode(mut self, mode: MuteMode) -> Self {
+        self.mute_mode = mode;
+        self
+    }
+
+    /// Suppress delivery of events that arrive within `period` of startup, or
+    /// that happened before startup (e.g. surfaced by the historical backfill)
+    ///
+    /// Suppressed events are marked notified and logged as "suppressed".
+    /// Critical events and notifications still pending from a previous run are
+    /// always delivered.
+    pub fn with_startup_quiet_period(mut self, period: std::time::Duration) -> Self {
+        self.quiet_period = Some(period);
+        self
+    }
+
+    /// Emit a `monitor.notification_failed` System event into the event
+    /// pipeline whenever a notification exhausts its retries
+    pub fn with_failure_events(mut self, failure_tx: mpsc::Sender<UnifiEvent>) -> Self {
+        self.failure_tx = Some(failure_tx);
+        self
+    }
+
+    /// Link each notification to `<base_url>/events/<id>` in the dashboard
+    pub fn with_public_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.public_base_url = Some(base_url.into());
+        self
+    }
+
     /// Run the notification sender task
+    ///
+    /// Delivers queued notifications as they arrive, and failed ones when
+    /// their scheduled retry is due.
     pub async fn run(mut self) {
         info!("Notification sender started");
 
-        while let Some(event) = self.notify_rx.recv().await {
-            self.send_notification(event).await;
+        loop {
+            let retry_wait = match self.db.next_notification_retry_at() {
+                Ok(next) => next.map(|at| (at - chrono::Utc::now().timestamp()).max(0) as u64),
+                Err(e) => {
+                    error!(error = %e, "Failed to read notification retry schedule");
+                    Some(MAX_RETRY_BACKOFF_SECS as u64)
+                }
+            };
+            let retry_due = async {
+                match retry_wait {
+                    Some(secs) => tokio::time::sleep(std::time::Duration::from_secs(secs)).await,
+                    None => std::future::pending().await,
+                }
+            };
+
+            tokio::select! {
+                event = self.notify_rx.recv() => match event {
+                    Some(event) => self.send_notification(event).await,
+                    None => break,
+                },
+                _ = retry_due => {
+                    self.send_due_retries(chrono::Utc::now().timestamp()).await;
+                }
+            }
         }
 
         info!("Notification sender stopped");
     }
 
+    /// Whether delivery of this event falls under the startup quiet period
+    fn in_quiet_period(&self, event: &StoredEvent) -> bool {
+        let Some(period) = self.quiet_period else {
+            return false;
+        };
+
+        if event.severity == Some(Severity::Critical) || event.created_at < self.started_at {
+            return false;
+        }
+
+        let quiet_until = self.started_at + period.as_secs() as i64;
+        chrono::Utc::now().timestamp() < quiet_until || event.timestamp < self.started_at
+    }
+
     async fn send_notification(&self, event: StoredEvent) {
-        let mut attempts = event.notify_attempts;
-        let mut backoff_secs = 1u64;
+        let muted = self.db.notifications_muted().unwrap_or_else(|e| {
+            error!(error = %e, "Failed to read mute setting, delivering anyway");
+            false
+        });
+        if muted {
+            match self.current_mute_mode() {
+                MuteMode::Drop => self.suppress(&event, "notifications muted"),
+                MuteMode::Hold => {
+                    // Unscheduled, so it's requeued with the other held notifications on unmute
+                    if let Err(e) = self.db.clear_notification_retry(&event.id) {
+                        error!(id = event.id, error = %e, "Failed to clear notification retry");
+                    }
+                    debug!(id = event.id, "Notifications muted, leaving notification pending");
+                }
+            }
+            return;
+        }
 
-        loop {
-            attempts += 1;
+        if self.in_quiet_period(&event) {
+            self.suppress(&event, "startup quiet period");
+            return;
+        }
+
+        // Backends (and recipients) that already delivered this event aren't retried
+        let delivered_ids = self.db.get_delivered_backends(&event.id).unwrap_or_else(|e| {
+            error!(id = event.id, error = %e, "Failed to load delivered backends");
+            Vec::new()
+        });
+        let mut delivered: Vec<Delivery> = self
+            .notifiers
+            .iter()
+            .map(|n| Delivery::load(n.id(), &delivered_ids))
+            .collect();
+        let attempts = event.notify_attempts + 1;
 
-            match self.try_send_telegram(&event).await {
-                Ok(()) => {
-                    // Success - mark as notified and log
-                    if let Err(e) = self.db.mark_notified(&event.id) {
-                        error!(id = event.id, error = %e, "Failed to mark event as notified");
+        match self.try_send(&event, &mut delivered).await {
+            Ok(()) => {
+                // Success - mark as notified and log
+                if let Err(e) = self.db.mark_notified(&event.id) {
+                    error!(id = event.id, error = %e, "Failed to mark event as notified");
+                }
+                if let Err(e) = self.db.log_notification(
+                    Some(&event.id),
+                    Some(&event.event_type),
+                    Some(&event.summary),
+                    "sent",
+                    None,
+                ) {
+                    error!(error = %e, "Failed to log notification");
+                }
+                info!(
+                    id = event.id,
+                    event_type = event.event_type,
+                    "Notification sent"
+                );
+            }
+            Err(error_msg) => {
+                warn!(
+                    id = event.id,
+                    attempt = attempts,
+                    error = %error_msg,
+                    "Failed to send notification"
+                );
+
+                // Update attempts in database
+                if let Err(db_err) = self.db.increment_notify_attempts(&event.id) {
+                    error!(error = %db_err, "Failed to increment notify attempts");
+                }
+
+                if attempts >= self.max_attempts {
+                    if let Err(db_err) = self.db.clear_notification_retry(&event.id) {
+                        error!(error = %db_err, "Failed to clear notification retry");
                     }
-                    if let Err(e) = self.db.log_notification(
+                    // Log final failure
+                    if let Err(log_err) = self.db.log_notification(
                         Some(&event.id),
                         Some(&event.event_type),
                         Some(&event.summary),
-                        "sent",
-                        None,
+                        "failed",
+                        Some(&error_msg),
                     ) {
-                        error!(error = %e, "Failed to log notification");
+                        error!(error = %log_err, "Failed to log notification failure");
                     }
-                    info!(
+                    error!(
                         id = event.id,
-                        event_type = event.event_type,
-                        "Notification sent"
+                        attempts,
+                        "Giving up on notification after max attempts"
                     );
+                    self.emit_failure_event(&event, attempts, &error_msg);
                     return;
                 }
-                Err(e) => {
-                    let error_msg = e.to_string();
-                    warn!(
-                        id = event.id,
-                        attempt = attempts,
-                        error = %e,
-                        "Failed to send notification"
-                    );
-
-                    // Update attempts in database
-                    if let Err(db_err) = self.db.increment_notify_attempts(&event.id) {
-                        error!(error = %db_err, "Failed to increment notify attempts");
-                    }
 
-                    if attempts >= self.max_attempts {
-                        // Log final failure
-                        if let Err(log_err) = self.db.log_notification(
-                            Some(&event.id),
-                            Some(&event.event_type),
-                            Some(&event.summary),
-                            "failed",
-                            Some(&error_msg),
-                        ) {
-                            error!(error = %log_err, "Failed to log notification failure");
-                        }
-                        error!(
-                            id = event.id,
-                            attempts,
-                            "Giving up on notification after max attempts"
-                        );
-                        return;
-                    }
-
-                    // Exponential backoff
-                    tokio::time::sleep(tokio::time::Duration::from_secs(backoff_secs)).await;
-                    backoff_secs = (backoff_secs * 2).min(60);
+                // Exponential backoff, persisted so the schedule survives restarts
+                let backoff_secs = retry_backoff_secs(attempts);
+                let next_attempt_at = chrono::Utc::now().timestamp() + backoff_secs;
+                let delivered_ids: Vec<String> = self
+                    .notifiers
+                    .iter()
+                    .zip(&delivered)
+                    .flat_map(|(n, delivery)| delivery.ids(n.id()))
+                    .collect();
+                let delivered_ids: Vec<&str> = delivered_ids.iter().map(String::as_str).collect();
+                if let Err(db_err) =
+                    self.db
+                        .schedule_notification_retry(&event.id, next_attempt_at, backoff_secs, &delivered_ids)
+                {
+                    error!(error = %db_err, "Failed to schedule notification retry");
                 }
+                debug!(id = event.id, backoff_secs, "Notification retry scheduled");
             }
         }
     }
 
-    async fn try_send_telegram(&self, event: &StoredEvent) -> Result<(), TelegramError> {
-        let timestamp_str = chrono::DateTime::from_timestamp(event.timestamp, 0)
-            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S UTC").to_string())
-            .unwrap_or_else(|| "unknown time".to_string());
-        let message = format!(
-            "🔔 *{}*\n\n{}\n\n_Source: {} \\| {}_",
-            escape_markdown(&event.event_type),
-            escape_markdown(&event.summary),
-            escape_markdown(&event.source.to_string()),
-            escape_markdown(&timestamp_str)
-        );
-
-        let url = format!(
-            "https://api.telegram.org/bot{}/sendMessage",
-            self.telegram_token
-        );
-
-        let client = reqwest::Client::new();
-        let response = client
-            .post(&url)
-            .json(&serde_json::json!({
-                "chat_id": self.telegram_chat_id,
-                "text": message,
-                "parse_mode": "MarkdownV2"
-            }))
-            .send()
-            .await
-            .map_err(|e| TelegramError::Request(e.to_string()))?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            return Err(TelegramError::Api(format!("{}: {}", status, body)));
-        }
-
-        Ok(())
+    /// Attempt every notification whose scheduled retry is due at `now`
+    async fn send_due_retries(&self, now: i64) -> usize {
+        let due = match self.db.get_due_notifications(now) {
+            Ok(due) => due,
+            Err(e) => {
+                error!(error = %e, "Failed to load due notification retries");
+                return 0;
+            }
+        };
+
+        let count = due.len();
+        for event in due {
+            self.send_notification(event).await;
+        }
+        count
     }
-}
 
-/// Send a test notification to Telegram
-pub async fn send_test_notification(
-    db: &Database,
-    telegram_token: &str,
-    telegram_chat_id: &str,
-) -> Result<(), TelegramError> {
-    let message = "🧪 *Test Notification*\n\nThis is a test message from UniFi Monitor\\. If you see this, your Telegram integration is working correctly\\!";
-
-    let url = format!(
-        "https://api.telegram.org/bot{}/sendMessage",
-        telegram_token
-    );
+    /// Mute mode from the settings table, falling back to the configured one
+    fn current_mute_mode(&self) -> MuteMode {
+        match self.db.get_setting(settings::MUTE_MODE) {
+            Ok(Some(mode)) => MuteMode::from_str(&mode).unwrap_or(self.mute_mode),
+            Ok(None) => self.mute_mode,
+            Err(e) => {
+                error!(error = %e, "Failed to read mute mode setting");
+                self.mute_mode
+            }
+        }
+    }
 
-    let client = reqwest::Client::new();
-    let response = client
-        .post(&url)
-        .json(&serde_json::json!({
-            "chat_id": telegram_chat_id,
-            "text": message,
-            "parse_mode": "MarkdownV2"
-        }))
-        .send()
-        .await
-        .map_err(|e| TelegramError::Request(e.to_string()))?;
+    /// Mark an event notified without delivering it, logging it as suppressed
+    fn suppress(&self, event: &StoredEvent, reason: &str) {
+        if let Err(e) = self.db.mark_notified(&event.id) {
+            error!(id = event.id, error = %e, "Failed to mark event as notified");
+        }
+        if let Err(e) = self.db.log_notification(
+            Some(&event.id),
+            Some(&event.event_type),
+            Some(&event.summary),
+            "suppressed",
+            Some(reason),
+        ) {
+            error!(error = %e, "Failed to log notification");
+        }
+        info!(id = event.id, event_type = event.event_type, reason, "Notification suppressed");
+    }
 
-    if !response.status().is_success() {
-        let status = response.status();
-        let body = response.text().await.unwrap_or_default();
-        let error_msg = format!("{}: {}", status, body);
+    /// Emit a `monitor.notification_failed` event for a notification we gave up on
+    fn emit_failure_event(&self, event: &StoredEvent, attempts: i32, error_msg: &str) {
+        let Some(ref failure_tx) = self.failure_tx else {
+            return;
+        };
 
-        // Log failure
-        if let Err(e) = db.log_notification(None, None, Some("Test notification"), "failed", Some(&error_msg)) {
-            error!(error = %e, "Failed to log test notification failure");
+        // A failure event failing to notify must not produce another failure event
+        if event.event_type == NOTIFICATION_FAILED_EVENT_TYPE {
+            warn!(id = event.id, "Not emitting failure event for a failed failure notification");
+            return;
         }
 
-        return Err(TelegramError::Api(error_msg));
+        // The main loop may be blocked queueing a notification for this
+        // sender, so never wait on a full channel
+        let failure = notification_failed_event(event, attempts, error_msg);
+        if let Err(e) = failure_tx.try_send(failure) {
+            warn!(id = event.id, error = %e, "Failed to emit notification failure event");
+        }
     }
 
-    // Log success
-    if let Err(e) = db.log_notification(None, None, Some("Test notification"), "sent", None) {
-        error!(error = %e, "Failed to log test notification");
+    /// Send to every backend not yet done in `delivered`, recording each
+    /// success (of a whole backend, or of some of its recipients)
+    ///
+    /// Fails if any backend failed, with each failure as "<backend>: <error>".
+    async fn try_send(&self, event: &StoredEvent, delivered: &mut [Delivery]) -> Result<(), String> {
+        let link = self
+            .public_base_url
+            .as_deref()
+            .map(|base| event_link(base, &event.id));
+
+        let mut errors = Vec::new();
+        for (notifier, delivery) in self.notifiers.iter().zip(delivered.iter_mut()) {
+            if delivery.complete {
+                continue;
+            }
+            match notifier.send_remaining(event, link.as_deref(), &mut delivery.recipients).await {
+                Ok(()) => delivery.complete = true,
+                Err(e) => errors.push(format!("{}: {}", notifier.id(), e)),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        }
     }
+}
 
-    Ok(())
+/// What one backend has delivered of a notification, persisted between
+/// retries as `<backend>` once complete, else one `<backend>:<recipient>` each
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Delivery {
+    complete: bool,
+    recipients: Vec<String>,
 }
 
-/// Escape special characters for Telegram MarkdownV2
-fn escape_markdown(text: &str) -> String {
-    let special_chars = ['_', '*', '[', ']', '(', ')', '~', '`', '>', '#', '+', '-', '=', '|', '{', '}', '.', '!'];
-    let mut result = String::with_capacity(text.len() * 2);
-    for c in text.chars() {
-        if special_chars.contains(&c) {
-            result.push('\\');
+impl Delivery {
+    fn load(backend: &str, ids: &[String]) -> Self {
+        let prefix = format!("{}:", backend);
+        Self {
+            complete: ids.iter().any(|id| id == backend),
+            recipients: ids
+                .iter()
+                .filter_map(|id| id.strip_prefix(&prefix))
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+
+    fn ids(&self, backend: &str) -> Vec<String> {
+        if self.complete {
+            vec![backend.to_string()]
+        } else {
+            self.recipients.iter().map(|r| format!("{}:{}", backend, r)).collect()
         }
-        result.push(c);
     }
-    result
 }
 
-#[derive(Debug, thiserror::Error)]
-pub enum TelegramError {
-    #[error("Request failed: {0}")]
-    Request(String),
-    #[error("API error: {0}")]
-    Api(String),
+/// Build the synthetic System event for a notification that exhausted its retries
+fn notification_failed_event(event: &StoredEvent, attempts: i32, error_msg: &str) -> UnifiEvent {
+    let timestamp = chrono::Utc::now();
+    let id = generate_event_id(
+        EventSource::System,
+        NOTIFICATION_FAILED_EVENT_TYPE,
+        timestamp,
+        &[&event.id],
+    );
+
+    UnifiEvent {
+        id,
+        timestamp,
+        source: EventSource::System,
+        event_type: NOTIFICATION_FAILED_EVENT_TYPE.to_string(),
+        summary: format!("Notification failed for {}: {}", event.event_type, event.summary),
+        severity: Some(Severity::Error),
+        raw: serde_json::json!({
+            "event_id": event.id,
+            "event_type": event.event_type,
+            "event_summary": event.summary,
+            "attempts": attempts,
+            "error": error_msg,
+        }),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::notify::TelegramNotifier;
+
+    fn telegram(server: &wiremock::MockServer) -> Box<dyn Notifier> {
+        Box::new(TelegramNotifier::new("token".into(), vec!["chat".into()]).with_api_base(server.uri()))
+    }
+
+    #[tokio::test]
+    async fn test_give_up_emits_failure_event() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+
+        let db = Database::open_in_memory().unwrap();
+        let (_notify_tx, notify_rx) = mpsc::channel(1);
+        let (failure_tx, mut failure_rx) = mpsc::channel(10);
+        let sender = NotificationSender::new(db, notify_rx, Arc::new(vec![telegram(&server)]), 1)
+            .with_failure_events(failure_tx);
+
+        sender.send_notification(StoredEvent::test_notify("motion")).await;
+        let failure = failure_rx.try_recv().unwrap();
+        assert_eq!(failure.event_type, NOTIFICATION_FAILED_EVENT_TYPE);
+        assert_eq!(failure.source, EventSource::System);
+        assert_eq!(failure.severity, Some(Severity::Error));
+        assert_eq!(failure.raw["event_id"], "test-123");
+
+        // A failed failure notification doesn't loop
+        sender.send_notification(StoredEvent::test_notify(NOTIFICATION_FAILED_EVENT_TYPE)).await;
+        assert!(failure_rx.try_recv().is_err());
+    }
+
+    fn store_notify_event(db: &Database, id: &str) -> StoredEvent {
+        db.set_rule("motion", Classification::Notify).unwrap();
+        db.store_event(&UnifiEvent {
+            id: id.to_string(),
+            timestamp: chrono::Utc::now(),
+            source: EventSource::Protect,
+            event_type: "motion".to_string(),
+            summary: "Motion detected".to_string(),
+            severity: None,
+            raw: serde_json::json!({}),
+        })
+        .unwrap();
+        db.get_pending_notifications()
+            .unwrap()
+            .into_iter()
+            .find(|e| e.id == id)
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_retry_skips_backends_that_delivered() {
+        use crate::notify::WebhookNotifier;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+
+        let db = Database::open_in_memory().unwrap();
+        let (_notify_tx, notify_rx) = mpsc::channel(1);
+        let webhook = Box::new(WebhookNotifier::new(format!("{}/hook", server.uri()), None));
+        let sender = NotificationSender::new(db.clone(), notify_rx, Arc::new(vec![webhook, telegram(&server)]), 2);
+
+        sender.send_notification(store_notify_event(&db, "retry-1")).await;
+        assert_eq!(db.get_delivered_backends("retry-1").unwrap(), vec!["webhook"]);
+
+        let retry_at = db.get_scheduled_retries().unwrap()[0].next_attempt_at;
+        assert_eq!(sender.send_due_retries(retry_at).await, 1);
+
+        let history = db.get_notification_history(10).unwrap();
+        assert_eq!(history[0].status, "failed");
+        assert!(history[0].error_message.as_deref().unwrap().starts_with("telegram: "));
+        assert!(db.get_scheduled_retries().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_retry_skips_telegram_chats_that_delivered() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/bottoken/sendMessage"))
+            .and(body_partial_json(serde_json::json!({"chat_id": "good"})))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/bottoken/sendMessage"))
+            .and(body_partial_json(serde_json::json!({"chat_id": "broken"})))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let db = Database::open_in_memory().unwrap();
+        let (_notify_tx, notify_rx) = mpsc::channel(1);
+        // The failing chat comes first, so the working one must still be tried
+        let telegram = TelegramNotifier::new("token".into(), vec!["broken".into(), "good".into()])
+            .with_api_base(server.uri());
+        let sender = NotificationSender::new(db.clone(), notify_rx, Arc::new(vec![Box::new(telegram)]), 2);
+
+        sender.send_notification(store_notify_event(&db, "retry-1")).await;
+        assert_eq!(db.get_delivered_backends("retry-1").unwrap(), vec!["telegram:good"]);
+
+        // The retry only reaches the chat that failed
+        let retry_at = db.get_scheduled_retries().unwrap()[0].next_attempt_at;
+        assert_eq!(sender.send_due_retries(retry_at).await, 1);
+        let history = db.get_notification_history(10).unwrap();
+        assert_eq!(history[0].status, "failed");
+        assert!(history[0].error_message.as_deref().unwrap().starts_with("telegram: API error: broken: 500"));
+    }
+
+    #[tokio::test]
+    async fn test_retry_schedule_transitions() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let db = Database::open_in_memory().unwrap();
+        let (_notify_tx, notify_rx) = mpsc::channel(1);
+        let sender = NotificationSender::new(db.clone(), notify_rx, Arc::new(vec![telegram(&server)]), 5);
+
+        // First failure: scheduled 1s out, and no longer pending for requeue
+        let before = chrono::Utc::now().timestamp();
+        sender.send_notification(store_notify_event(&db, "retry-1")).await;
+        let retry = db.get_scheduled_retries().unwrap().remove(0);
+        assert_eq!((retry.event_id.as_str(), retry.attempts, retry.backoff_secs), ("retry-1", 1, 1));
+        assert!(retry.next_attempt_at > before);
+        assert!(db.get_pending_notifications().unwrap().is_empty());
+        assert_eq!(db.next_notification_retry_at().unwrap(), Some(retry.next_attempt_at));
+
+        // Not yet due
+        assert_eq!(sender.send_due_retries(retry.next_attempt_at - 1).await, 0);
+
+        // Second failure doubles the backoff
+        assert_eq!(sender.send_due_retries(retry.next_attempt_at).await, 1);
+        let retry = db.get_scheduled_retries().unwrap().remove(0);
+        assert_eq!((retry.attempts, retry.backoff_secs), (2, 2));
+
+        // Success clears the schedule
+        assert_eq!(sender.send_due_retries(retry.next_attempt_at).await, 1);
+        assert!(db.get_scheduled_retries().unwrap().is_empty());
+        assert_eq!(db.next_notification_retry_at().unwrap(), None);
+        let history = db.get_notification_history(10).unwrap();
+        assert_eq!(history.iter().filter(|n| n.status == "sent").count(), 1);
+
+        assert_eq!(retry_backoff_secs(4), 8);
+        assert_eq!(retry_backoff_secs(10), MAX_RETRY_BACKOFF_SECS);
+    }
+
+    #[tokio::test]
+    async fn test_mute_stops_delivery_until_unmuted() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let db = Database::open_in_memory().unwrap();
+        db.set_rule("motion", Classification::Notify).unwrap();
+        let (_notify_tx, notify_rx) = mpsc::channel(1);
+        let sender = NotificationSender::new(db.clone(), notify_rx, Arc::new(vec![telegram(&server)]), 1);
+
+        // Dropped while muted
+        db.set_notifications_muted(true).unwrap();
+        sender.send_notification(StoredEvent::test_notify("motion")).await;
+        let history = db.get_notification_history(10).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].status, "suppressed");
+        assert_eq!(history[0].error_message.as_deref(), Some("notifications muted"));
+
+        // Held while muted: stays pending for redelivery on unmute
+        let (_notify_tx, notify_rx) = mpsc::channel(1);
+        let holding = NotificationSender::new(db.clone(), notify_rx, Arc::new(vec![telegram(&server)]), 1)
+            .with_mute_mode(MuteMode::Hold);
+        let event = UnifiEvent {
+            id: "held-1".to_string(),
+            timestamp: chrono::Utc::now(),
+            source: EventSource::Protect,
+            event_type: "motion".to_string(),
+            summary: "Motion detected".to_string(),
+            severity: None,
+            raw: serde_json::json!({}),
+        };
+        db.store_event(&event).unwrap();
+        let pending = db.get_pending_notifications().unwrap();
+        assert_eq!(pending.len(), 1);
+        holding.send_notification(pending[0].clone()).await;
+        assert_eq!(db.get_pending_notifications().unwrap().len(), 1);
+
+        // Unmuted: delivery resumes
+        db.set_notifications_muted(false).unwrap();
+        holding.send_notification(db.get_pending_notifications().unwrap().remove(0)).await;
+        assert!(db.get_pending_notifications().unwrap().is_empty());
+        sender.send_notification(StoredEvent::test_notify("motion")).await;
+        let history = db.get_notification_history(10).unwrap();
+        assert_eq!(history.iter().filter(|n| n.status == "sent").count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_startup_quiet_period_suppresses_delivery() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let db = Database::open_in_memory().unwrap();
+        let (_notify_tx, notify_rx) = mpsc::channel(1);
+        let sender = NotificationSender::new(db.clone(), notify_rx, Arc::new(vec![telegram(&server)]), 1)
+            .with_startup_quiet_period(std::time::Duration::from_secs(60));
+
+        // Backfilled event stored after startup: suppressed
+        let mut event = StoredEvent::test_notify("motion");
+        event.created_at = chrono::Utc::now().timestamp();
+        sender.send_notification(event.clone()).await;
+
+        // Pending from a previous run: still delivered
+        let mut pending = StoredEvent::test_notify("motion");
+        pending.id = "pending-1".to_string();
+        sender.send_notification(pending).await;
+
+        let history = db.get_notification_history(10).unwrap();
+        assert!(history.iter().any(|n| n.event_id.as_deref() == Some("test-123") && n.status == "suppressed"));
+        assert!(history.iter().any(|n| n.event_id.as_deref() == Some("pending-1") && n.status == "sent"));
+    }
+
+    #[tokio::test]
+    async fn test_compacted_event_types_not_appended() {
+        let db = Database::open_in_memory().unwrap();
+        let (notify_tx, _notify_rx) = mpsc::channel(10);
+        let config = ProcessorConfig {
+            compact_event_types: vec!["sta:sync".to_string()],
+            ..Default::default()
+        };
+        let processor = EventProcessor::new(db.clone(), config, notify_tx);
+
+        for i in 0..3 {
+            let event = UnifiEvent {
+                id: format!("network-{}", i),
+                timestamp: chrono::Utc::now(),
+                source: EventSource::Network,
+                event_type: "sta:sync".to_string(),
+                summary: "Client sync".to_string(),
+                severity: None,
+                raw: serde_json::json!({"data": [{"mac": "aa:bb:cc"}]}),
+            };
+            processor.process(event).await.unwrap();
+        }
+
+        assert_eq!(db.count_events(&crate::db::EventFilter::default()).unwrap(), 0);
+        let states = db.get_entity_states(None, 10).unwrap();
+        assert_eq!(states.len(), 1);
+        assert_eq!(states[0].entity_id, "aa:bb:cc");
+        assert_eq!(states[0].change_count, 3);
+        // So the live feed doesn't announce rows that don't exist
+        assert!(processor.is_compacted("sta:sync"));
+        assert!(!processor.is_compacted("EVT_SW_Disconnected"));
+    }
+
+    #[tokio::test]
+    async fn test_drops_events_older_than_max_age() {
+        let db = Database::open_in_memory().unwrap();
+        let (notify_tx, _notify_rx) = mpsc::channel(10);
+        let config = ProcessorConfig {
+            max_event_age: Some(chrono::Duration::days(30)),
+            ..Default::default()
+        };
+        let processor = EventProcessor::new(db.clone(), config, notify_tx);
+
+        let event = |id: &str, age_days: i64| UnifiEvent {
+            id: id.to_string(),
+            timestamp: chrono::Utc::now() - chrono::Duration::days(age_days),
+            source: EventSource::Network,
+            event_type: "EVT_SW_Disconnected".to_string(),
+            summary: "Switch disconnected".to_string(),
+            severity: None,
+            raw: serde_json::json!({}),
+        };
+
+        let classification = processor.process(event("old", 45)).await.unwrap();
+        assert_eq!(classification, Classification::Suppressed);
+        assert!(!db.event_exists("old").unwrap());
+
+        let classification = processor.process(event("recent", 1)).await.unwrap();
+        assert_eq!(classification, Classification::Unclassified);
+        assert!(db.event_exists("recent").unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_new_event_type_announced_once() {
+        let db = Database::open_in_memory().unwrap();
+        let (notify_tx, _notify_rx) = mpsc::channel(10);
+        let (new_type_tx, mut new_type_rx) = mpsc::channel(10);
+        db.store_event(&UnifiEvent {
+            id: "baseline".to_string(),
+            timestamp: chrono::Utc::now(),
+            source: EventSource::Network,
+            event_type: "known".to_string(),
+            summary: "Seen before".to_string(),
+            severity: None,
+            raw: serde_json::json!({}),
+        })
+        .unwrap();
+        let processor =
+            EventProcessor::new(db.clone(), ProcessorConfig::default(), notify_tx).with_new_type_events(new_type_tx);
+
+        // Backfilled history isn't announced
+        let backfilled = UnifiEvent {
+            id: "old".to_string(),
+            timestamp: chrono::Utc::now() - chrono::Duration::hours(1),
+            source: EventSource::Network,
+            event_type: "old.type".to_string(),
+            summary: "From history".to_string(),
+            severity: None,
+            raw: serde_json::json!({}),
+        };
+        processor.process(backfilled).await.unwrap();
+        assert!(new_type_rx.try_recv().is_err());
+
+        for i in 0..3 {
+            let event = UnifiEvent {
+                id: format!("evt-{}", i),
+                timestamp: chrono::Utc::now(),
+                source: EventSource::Network,
+                event_type: "foo.bar".to_string(),
+                summary: "Something new".to_string(),
+                severity: None,
+                raw: serde_json::json!({}),
+            };
+            processor.process(event).await.unwrap();
+        }
+
+        let announcement = new_type_rx.try_recv().unwrap();
+        assert_eq!(announcement.event_type, NEW_EVENT_TYPE_EVENT_TYPE);
+        assert_eq!(announcement.summary, "New event type observed: foo.bar");
+        assert_eq!(announcement.raw["event_id"], "evt-0");
+        assert!(new_type_rx.try_recv().is_err());
+
+        // The announcement passing through the pipeline isn't itself announced
+        processor.process(announcement).await.unwrap();
+        assert!(new_type_rx.try_recv().is_err());
+
+        // Nor is a type announced again once its events are gone
+        db.bulk_update_events(
+            &crate::db::EventFilter { event_types: vec!["foo.bar".to_string()], ..Default::default() },
+            crate::db::BulkEventAction::Delete,
+        )
+        .unwrap();
+        let event = UnifiEvent {
+            id: "evt-3".to_string(),
+            timestamp: chrono::Utc::now(),
+            source: EventSource::Network,
+            event_type: "foo.bar".to_string(),
+            summary: "Something new".to_string(),
+            severity: None,
+            raw: serde_json::json!({}),
+        };
+        processor.process(event).await.unwrap();
+        assert!(new_type_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_new_event_types_not_announced_on_empty_database() {
+        let db = Database::open_in_memory().unwrap();
+        let (notify_tx, _notify_rx) = mpsc::channel(10);
+        let (new_type_tx, mut new_type_rx) = mpsc::channel(10);
+        let processor =
+            EventProcessor::new(db.clone(), ProcessorConfig::default(), notify_tx).with_new_type_events(new_type_tx);
+
+        for i in 0..3 {
+            let event = UnifiEvent {
+                id: format!("evt-{}", i),
+                timestamp: chrono::Utc::now(),
+                source: EventSource::Network,
+                event_type: format!("type.{}", i),
+                summary: "First run".to_string(),
+                severity: None,
+                raw: serde_json::json!({}),
+            };
+            processor.process(event).await.unwrap();
+        }
+        assert!(new_type_rx.try_recv().is_err());
+    }
 
     #[test]
-    fn test_escape_markdown() {
-        assert_eq!(escape_markdown("hello"), "hello");
-        assert_eq!(escape_markdown("hello_world"), "hello\\_world");
-        assert_eq!(escape_markdown("test.event"), "test\\.event");
+    fn test_jsonl_line_includes_classification() {
+        let event = StoredEvent {
+            severity: Some(Severity::Warning),
+            payload: serde_json::json!({"test": true}),
+            ..StoredEvent::test_notify("motion")
+        };
+
+        let line = jsonl_line(&event);
+        assert_eq!(line["classification"], "notify");
+        assert_eq!(line["source"], "protect");
+        assert_eq!(line["severity"], "warning");
+        assert_eq!(line["payload"]["test"], true);
+        assert!(!line.to_string().contains('\n'));
+    }
+
+    #[tokio::test]
+    async fn test_jsonl_skips_duplicate_events() {
+        let dir = std::env::temp_dir().join(format!("unifi-monitor-jsonl-{}", uuid::Uuid::new_v4()));
+        let db = Database::open_in_memory().unwrap();
+        let (notify_tx, _notify_rx) = mpsc::channel(10);
+        let processor = EventProcessor::new(db.clone(), ProcessorConfig::default(), notify_tx)
+            .with_jsonl_sink(JsonlSink::new(dir.join("events.jsonl")).unwrap());
+
+        let event = UnifiEvent {
+            id: "network-1".to_string(),
+            timestamp: chrono::Utc::now(),
+            source: EventSource::Network,
+            event_type: "EVT_SW_Disconnected".to_string(),
+            summary: "Switch disconnected".to_string(),
+            severity: None,
+            raw: serde_json::json!({}),
+        };
+        // Redelivered after a reconnect, for example
+        processor.process(event.clone()).await.unwrap();
+        processor.process(event).await.unwrap();
+        drop(processor);
+
+        let mut lines = 0;
+        for entry in std::fs::read_dir(&dir).unwrap() {
+            lines += std::fs::read_to_string(entry.unwrap().path()).unwrap().lines().count();
+        }
+        assert_eq!(lines, 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
     }
 }
@@ -1,10 +1,12 @@
 use reqwest::{
     cookie::{CookieStore, Jar},
-    Client,
+    header::SET_COOKIE,
+    Client, RequestBuilder, Response,
 };
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tracing::{debug, info, instrument};
+use std::time::Duration;
+use tracing::{debug, info, instrument, warn};
 use url::Url;
 
 use super::error::UnifiError;
@@ -49,6 +51,36 @@ pub struct NvrInfo {
     pub version: String,
 }
 
+/// Base delay between GET retries (doubled on each attempt)
+const RETRY_BACKOFF_MS: u64 = 250;
+
+/// Doublings of the retry delay before it stops growing (16s)
+const MAX_BACKOFF_DOUBLINGS: u32 = 6;
+
+/// Send an idempotent request, retrying transient failures
+/// (timeouts, connection errors and 5xx responses) up to `retries` times
+async fn send_with_retry<F>(build: F, retries: u32) -> Result<Response, reqwest::Error>
+where
+    F: Fn() -> RequestBuilder,
+{
+    let mut attempt = 0;
+    loop {
+        match build().send().await {
+            Ok(resp) if resp.status().is_server_error() && attempt < retries => {
+                warn!(status = %resp.status(), attempt = attempt + 1, "Transient HTTP error, retrying");
+            }
+            Ok(resp) => return Ok(resp),
+            Err(e) if (e.is_timeout() || e.is_connect()) && attempt < retries => {
+                warn!(error = %e, attempt = attempt + 1, "HTTP request failed, retrying");
+            }
+            Err(e) => return Err(e),
+        }
+
+        tokio::time::sleep(Duration::from_millis(RETRY_BACKOFF_MS << attempt.min(MAX_BACKOFF_DOUBLINGS))).await;
+        attempt += 1;
+    }
+}
+
 /// Login request body
 #[derive(Debug, Serialize)]
 struct LoginRequest<'a> {
@@ -68,9 +100,28 @@ impl UnifiSession {
 
         let client = Client::builder()
             .cookie_provider(jar.clone())
+            .user_agent(&config.user_agent)
+            .connect_timeout(config.connect_timeout)
+            .timeout(config.request_timeout)
             .danger_accept_invalid_certs(!config.verify_ssl)
             .build()?;
 
+        Self::authenticate(config, client, jar).await
+    }
+
+    /// Like `login`, but send every request through a pre-built client (custom
+    /// TLS, proxy or timeouts, or a mock server in tests)
+    ///
+    /// The client's own settings apply in place of the config's user agent,
+    /// timeouts and `verify_ssl`. It should keep cookies (`cookie_store(true)`
+    /// or a `cookie_provider`) so later requests stay authenticated; the login
+    /// cookies are also recorded here for the WebSocket handshakes.
+    #[instrument(skip(config, client), fields(host = %config.host))]
+    pub async fn login_with_client(config: UnifiConfig, client: Client) -> Result<Self, UnifiError> {
+        Self::authenticate(config, client, Arc::new(Jar::default())).await
+    }
+
+    async fn authenticate(config: UnifiConfig, client: Client, jar: Arc<Jar>) -> Result<Self, UnifiError> {
         let base_url = config.base_url();
 
         // UniFi OS doesn't require CSRF token for initial login
@@ -100,6 +151,10 @@ impl UnifiSession {
             )));
         }
 
+        // Already in the jar if it's the client's cookie provider; a custom
+        // client keeps its own copy
+        jar.set_cookies(&mut resp.headers().get_all(SET_COOKIE).iter(), &Url::parse(&base_url)?);
+
         // Get CSRF token from response headers
         let csrf_token = resp
             .headers()
@@ -136,12 +191,11 @@ impl UnifiSession {
         let url = format!("{}/proxy/protect/api/bootstrap", self.config.base_url());
 
         debug!("Fetching Protect bootstrap");
-        let resp = self
-            .client
-            .get(&url)
-            .header("x-csrf-token", &self.csrf_token)
-            .send()
-            .await?;
+        let resp = send_with_retry(
+            || self.client.get(&url).header("x-csrf-token", &self.csrf_token),
+            self.config.http_retries,
+        )
+        .await?;
 
         if !resp.status().is_success() {
             let status = resp.status();
@@ -165,12 +219,11 @@ impl UnifiSession {
     /// Make an authenticated GET request
     pub async fn get(&self, path: &str) -> Result<reqwest::Response, UnifiError> {
         let url = format!("{}{}", self.config.base_url(), path);
-        let resp = self
-            .client
-            .get(&url)
-            .header("x-csrf-token", &self.csrf_token)
-            .send()
-            .await?;
+        let resp = send_with_retry(
+            || self.client.get(&url).header("x-csrf-token", &self.csrf_token),
+            self.config.http_retries,
+        )
+        .await?;
         Ok(resp)
     }
 
@@ -186,15 +239,18 @@ impl UnifiSession {
 
         debug!("Fetching network events from {}", url);
 
-        let mut req = self.client.get(&url);
-        req = req.header("x-csrf-token", &self.csrf_token);
+        let build = || {
+            let mut req = self.client.get(&url);
+            req = req.header("x-csrf-token", &self.csrf_token);
 
-        // Add limit parameter if specified
-        if let Some(limit) = limit {
-            req = req.query(&[("_limit", limit.to_string())]);
-        }
+            // Add limit parameter if specified
+            if let Some(limit) = limit {
+                req = req.query(&[("_limit", limit.to_string())]);
+            }
+            req
+        };
 
-        let resp = req.send().await?;
+        let resp = send_with_retry(build, self.config.http_retries).await?;
 
         if !resp.status().is_success() {
             let status = resp.status();
@@ -217,72 +273,99 @@ impl UnifiSession {
     }
 
     /// Fetch system logs/events
+    ///
+    /// Tries each of `config.system_event_endpoints` in order until one succeeds.
     #[instrument(skip(self))]
     pub async fn get_system_events(&self, limit: Option<u32>) -> Result<Vec<serde_json::Value>, UnifiError> {
-        // Try the system logs endpoint
-        let url = format!(
-            "{}/api/system/logs",
-            self.config.base_url()
-        );
+        fetch_system_events(
+            &self.client,
+            &self.config.base_url(),
+            &self.csrf_token,
+            &self.config.system_event_endpoints,
+            limit,
+            self.config.http_retries,
+        )
+        .await
+    }
+}
 
+/// Try each system event endpoint in order, returning events from the first that succeeds
+async fn fetch_system_events(
+    client: &Client,
+    base_url: &str,
+    csrf_token: &str,
+    endpoints: &[String],
+    limit: Option<u32>,
+    retries: u32,
+) -> Result<Vec<serde_json::Value>, UnifiError> {
+    let mut last_error = None;
+
+    for endpoint in endpoints {
+        let url = format!("{}{}", base_url, endpoint);
         debug!("Fetching system events from {}", url);
 
-        let mut req = self.client.get(&url);
-        req = req.header("x-csrf-token", &self.csrf_token);
+        let build = || {
+            let mut req = client.get(&url);
+            req = req.header("x-csrf-token", csrf_token);
 
-        if let Some(limit) = limit {
-            req = req.query(&[("limit", limit.to_string())]);
-        }
-
-        let resp = req.send().await?;
-
-        if !resp.status().is_success() {
-            // Try alternative endpoint
-            let alt_url = format!(
-                "{}/proxy/network/api/s/default/stat/alarm",
-                self.config.base_url()
-            );
-            debug!("Trying alternative endpoint: {}", alt_url);
-
-            let resp = self.client
-                .get(&alt_url)
-                .header("x-csrf-token", &self.csrf_token)
-                .send()
-                .await?;
-
-            if !resp.status().is_success() {
-                let status = resp.status();
-                let body = resp.text().await.unwrap_or_default();
-                return Err(UnifiError::InvalidResponse(format!(
-                    "System events fetch failed with status {}: {}",
-                    status, body
-                )));
+            if let Some(limit) = limit {
+                req = req.query(&[("limit", limit.to_string())]);
             }
+            req
+        };
 
-            #[derive(Deserialize)]
-            struct EventsResponse {
-                data: Vec<serde_json::Value>,
+        let resp = match send_with_retry(build, retries).await {
+            Ok(resp) => resp,
+            Err(e) => {
+                debug!(endpoint = %endpoint, error = %e, "System events endpoint failed");
+                last_error = Some(e.into());
+                continue;
             }
+        };
 
-            let events: EventsResponse = resp.json().await?;
-            return Ok(events.data);
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            debug!(endpoint = %endpoint, status = %status, "System events endpoint returned error");
+            last_error = Some(UnifiError::InvalidResponse(format!(
+                "System events fetch failed with status {}: {}",
+                status, body
+            )));
+            continue;
         }
 
-        // Try to parse as array or object with data field
         let body = resp.text().await?;
-        if let Ok(events) = serde_json::from_str::<Vec<serde_json::Value>>(&body) {
-            return Ok(events);
+        match parse_system_events(&body) {
+            Ok(events) => {
+                info!(endpoint = %endpoint, count = events.len(), "Fetched system events");
+                return Ok(events);
+            }
+            Err(e) => {
+                debug!(endpoint = %endpoint, error = %e, "Failed to parse system events");
+                last_error = Some(e.into());
+            }
         }
+    }
 
-        #[derive(Deserialize)]
-        struct EventsResponse {
-            data: Option<Vec<serde_json::Value>>,
-            logs: Option<Vec<serde_json::Value>>,
-        }
+    Err(last_error.unwrap_or_else(|| {
+        UnifiError::InvalidResponse("No system event endpoints configured".to_string())
+    }))
+}
 
-        let parsed: EventsResponse = serde_json::from_str(&body)?;
-        Ok(parsed.data.or(parsed.logs).unwrap_or_default())
+/// Parse a system events response: a bare array, or an object with a `data` or `logs` array
+fn parse_system_events(body: &str) -> Result<Vec<serde_json::Value>, serde_json::Error> {
+    if let Ok(events) = serde_json::from_str::<Vec<serde_json::Value>>(body) {
+        return Ok(events);
     }
+
+    #[derive(Deserialize)]
+    struct EventsResponse {
+        data: Option<Vec<serde_json::Value>>,
+        logs: Option<Vec<serde_json::Value>>,
+    }
+
+    let parsed: EventsResponse = serde_json::from_str(body)?;
+    Ok(parsed.data.or(parsed.logs).unwrap_or_default())
 }
 
 #[cfg(test)]
@@ -294,4 +377,127 @@ mod tests {
         let config = UnifiConfig::new("192.168.1.1", "admin", "password");
         assert_eq!(config.base_url(), "https://192.168.1.1");
     }
+
+    #[tokio::test]
+    async fn test_send_with_retry_recovers_from_server_error() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let client = Client::new();
+        let resp = send_with_retry(|| client.get(server.uri()), 2).await.unwrap();
+        assert_eq!(resp.status(), 200);
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_send_with_retry_times_out() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+            .mount(&server)
+            .await;
+
+        let client = Client::builder()
+            .timeout(Duration::from_millis(100))
+            .build()
+            .unwrap();
+        let err = send_with_retry(|| client.get(server.uri()), 1).await.unwrap_err();
+        assert!(err.is_timeout());
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_system_events_falls_back_to_next_endpoint() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/system/logs"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/custom/events"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "logs": [{"key": "test.event"}]
+            })))
+            .mount(&server)
+            .await;
+
+        let endpoints = vec!["/api/system/logs".to_string(), "/custom/events".to_string()];
+        let events = fetch_system_events(&Client::new(), &server.uri(), "", &endpoints, Some(10), 0)
+            .await
+            .unwrap();
+        assert_eq!(events, vec![serde_json::json!({"key": "test.event"})]);
+
+        // All endpoints failing returns the last error
+        let endpoints = vec!["/api/system/logs".to_string()];
+        let err = fetch_system_events(&Client::new(), &server.uri(), "", &endpoints, None, 0)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, UnifiError::InvalidResponse(_)));
+    }
+
+    #[tokio::test]
+    async fn test_login_with_client() {
+        use wiremock::matchers::{body_partial_json, header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/auth/login"))
+            .and(body_partial_json(serde_json::json!({"username": "admin", "password": "secret"})))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("x-csrf-token", "csrf-123")
+                    .insert_header("set-cookie", "TOKEN=session-abc; Path=/"),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/proxy/protect/api/bootstrap"))
+            .and(header("cookie", "TOKEN=session-abc"))
+            .and(header("x-csrf-token", "csrf-123"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"lastUpdateId": "upd-1"})))
+            .mount(&server)
+            .await;
+
+        let config = UnifiConfig::new("console.local", "admin", "secret").with_base_url(server.uri());
+        let client = Client::builder().cookie_store(true).build().unwrap();
+        let session = UnifiSession::login_with_client(config, client).await.unwrap();
+        assert_eq!(session.csrf_token, "csrf-123");
+        assert_eq!(session.get_cookie_header(), "TOKEN=session-abc");
+
+        let bootstrap = session.get_protect_bootstrap().await.unwrap();
+        assert_eq!(bootstrap.last_update_id, "upd-1");
+
+        // Rejected credentials
+        let config = UnifiConfig::new("console.local", "admin", "wrong").with_base_url(server.uri());
+        let err = UnifiSession::login_with_client(config, Client::new()).await.unwrap_err();
+        assert!(matches!(err, UnifiError::AuthFailed(_)));
+    }
+
+    #[test]
+    fn test_config_user_agent() {
+        let config = UnifiConfig::new("192.168.1.1", "admin", "password");
+        assert_eq!(config.user_agent, format!("unifi-monitor/{}", env!("CARGO_PKG_VERSION")));
+
+        let config = config.with_user_agent("custom/1.0");
+        assert_eq!(config.user_agent, "custom/1.0");
+    }
 }
@@ -1,22 +1,112 @@
 use futures_util::Stream;
-use std::collections::{HashMap, HashSet};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::hash::{Hash, Hasher};
 use std::sync::Arc;
 use tokio::sync::{mpsc, Mutex};
 use tokio::task::JoinHandle;
+use tokio_stream::wrappers::ReceiverStream;
 use tracing::{debug, error, info, instrument, trace, warn};
 
 use super::auth::UnifiSession;
 use super::error::UnifiError;
 use super::network::connect_network_websocket;
-use super::protect::connect_protect_websocket;
+use super::protect::{connect_protect_websocket, CameraTracker};
 use super::system::connect_system_websocket;
-use super::types::{extract_key_fields, generate_event_id, EventSource, Severity, UnifiConfig, UnifiEvent};
+use super::types::{extract_key_fields, unifi_event_id, DedupScope, EventSource, Severity, UnifiConfig, UnifiEvent};
 
 use crate::db::Database;
 
 /// Shared state for event deduplication (by event ID)
-pub type SeenEvents = Arc<Mutex<HashSet<String>>>;
+pub type SeenEvents = Arc<Mutex<SeenSet>>;
+
+/// Maximum IDs remembered per dedup set; the oldest are forgotten first
+/// (the database check still catches anything already stored)
+const MAX_SEEN_PER_SET: usize = 100_000;
+
+/// Event IDs seen by this process, either in one global set or one set per source
+#[derive(Debug, Default)]
+pub struct SeenSet {
+    scope: DedupScope,
+    /// Keyed by source when scoped per source, otherwise a single `None` entry
+    sets: HashMap<Option<EventSource>, BoundedSet>,
+    /// Window for semantic dedup, if enabled
+    window: Option<chrono::Duration>,
+    /// Timestamp of the last accepted event per (source, event type, entity)
+    last_accepted: HashMap<(EventSource, String, String), chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Default)]
+struct BoundedSet {
+    ids: HashSet<String>,
+    order: VecDeque<String>,
+}
+
+impl SeenSet {
+    pub fn new(scope: DedupScope) -> Self {
+        Self {
+            scope,
+            ..Default::default()
+        }
+    }
+
+    /// Also collapse events with the same source, type and entity whose
+    /// timestamps fall within `window` of the last one accepted
+    ///
+    /// The window runs from the last accepted event, so a condition that keeps
+    /// being re-reported still surfaces once per window. Events without an
+    /// identifiable entity are only deduplicated by ID.
+    pub fn with_window(mut self, window: std::time::Duration) -> Self {
+        self.window = chrono::Duration::from_std(window).ok();
+        self
+    }
+
+    /// Record an event ID; returns false if it was already seen in its scope
+    pub fn insert(&mut self, source: EventSource, id: &str) -> bool {
+        let key = match self.scope {
+            DedupScope::Global => None,
+            DedupScope::PerSource => Some(source),
+        };
+        let set = self.sets.entry(key).or_default();
+
+        if !set.ids.insert(id.to_string()) {
+            return false;
+        }
+        set.order.push_back(id.to_string());
+        if set.order.len() > MAX_SEEN_PER_SET {
+            if let Some(oldest) = set.order.pop_front() {
+                set.ids.remove(&oldest);
+            }
+        }
+        true
+    }
+
+    /// Record an event for semantic dedup; returns false if an event with the
+    /// same source, type and entity was accepted within the window
+    pub fn accept_in_window(&mut self, event: &UnifiEvent) -> bool {
+        let Some(window) = self.window else {
+            return true;
+        };
+        let Some(entity) = extract_key_fields(&event.raw).into_iter().next() else {
+            return true;
+        };
+
+        let key = (event.source, event.event_type.clone(), entity);
+        if let Some(last) = self.last_accepted.get(&key) {
+            if (event.timestamp - *last).abs() < window {
+                return false;
+            }
+        }
+        self.last_accepted.insert(key, event.timestamp);
+
+        // Entries outside the window can no longer suppress anything
+        if self.last_accepted.len() > MAX_SEEN_PER_SET {
+            let now = event.timestamp;
+            self.last_accepted.retain(|_, last| (now - *last).abs() < window);
+        }
+        true
+    }
+}
 
 /// Shared state for tracking entity states (to filter unchanged updates)
 /// Key: entity_id, Value: hash of last known state
@@ -48,11 +138,188 @@ pub async fn state_changed(tracker: &StateTracker, entity_id: &str, new_state: &
     }
 }
 
+/// Whether a state update should be passed on: true if the entity's state
+/// changed (or is new), and always when `keep_unchanged` is set. The tracker
+/// is updated either way.
+pub async fn keep_state_update(
+    tracker: &StateTracker,
+    entity_id: &str,
+    new_state: &serde_json::Value,
+    keep_unchanged: bool,
+) -> bool {
+    state_changed(tracker, entity_id, new_state).await || keep_unchanged
+}
+
+/// Check if an event is new, returns true if it hasn't been seen by this process
+/// and (when a database is available) isn't already stored from a previous run
+pub async fn is_new_event(seen_events: &SeenEvents, db: Option<&Database>, event: &UnifiEvent) -> bool {
+    let event_id = event.id.as_str();
+    let mut seen = seen_events.lock().await;
+    if !seen.insert(event.source, event_id) {
+        trace!("Skipping duplicate event: {}", event_id);
+        return false;
+    }
+    if !seen.accept_in_window(event) {
+        trace!("Skipping event within dedup window: {}", event_id);
+        return false;
+    }
+    drop(seen);
+
+    if let Some(db) = db {
+        match db.event_exists(event_id) {
+            Ok(true) => {
+                trace!("Skipping already stored event: {}", event_id);
+                return false;
+            }
+            Ok(false) => {}
+            Err(e) => {
+                warn!(error = %e, "Failed to check event existence, treating as new");
+            }
+        }
+    }
+
+    true
+}
+
+/// Outcome of the startup historical backfill
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HistoricalFetchResult {
+    /// Backfill was disabled by configuration
+    pub skipped: bool,
+    /// New network events loaded
+    pub network: usize,
+    /// New system events loaded
+    pub system: usize,
+    /// Errors from sources that failed to load
+    pub errors: Vec<String>,
+    /// When the backfill finished (unix seconds)
+    pub completed_at: i64,
+}
+
+impl HistoricalFetchResult {
+    /// Total number of events loaded across sources
+    pub fn total(&self) -> usize {
+        self.network + self.system
+    }
+}
+
+/// Connection state of one WebSocket source
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SourceConnection {
+    /// A connection attempt is in progress or established
+    pub connected: bool,
+    /// Connection attempts after the first one
+    pub reconnects: u64,
+    /// When the current connection started (unix seconds)
+    pub connected_since: Option<i64>,
+    /// Error that ended the most recent failed connection
+    pub last_error: Option<String>,
+    /// When `last_error` happened (unix seconds)
+    pub last_error_at: Option<i64>,
+    /// WebSocket frames received, across all connections
+    pub frames: u64,
+    /// WebSocket payload bytes received, across all connections
+    pub bytes: u64,
+    /// Average bytes per second over the last [`THROUGHPUT_WINDOW_SECS`]
+    pub bytes_per_sec: f64,
+    /// Bytes received per second, oldest first, within the throughput window
+    #[serde(skip)]
+    recent_bytes: VecDeque<(i64, u64)>,
+}
+
+/// Window over which `SourceConnection::bytes_per_sec` is averaged
+pub const THROUGHPUT_WINDOW_SECS: i64 = 60;
+
+/// Shared per-source connection tracking, updated by the reconnect loops
+#[derive(Debug, Clone, Default)]
+pub struct ConnectionTracker {
+    sources: Arc<std::sync::Mutex<HashMap<EventSource, SourceConnection>>>,
+}
+
+impl ConnectionTracker {
+    /// Record the start of a connection attempt (any after the first counts as a reconnect)
+    pub fn connecting(&self, source: EventSource) {
+        let mut sources = self.sources.lock().unwrap();
+        let state = match sources.entry(source) {
+            std::collections::hash_map::Entry::Occupied(entry) => {
+                let state = entry.into_mut();
+                state.reconnects += 1;
+                state
+            }
+            std::collections::hash_map::Entry::Vacant(entry) => entry.insert(SourceConnection::default()),
+        };
+        state.connected = true;
+        state.connected_since = Some(chrono::Utc::now().timestamp());
+    }
+
+    /// Record the end of a connection, with the error that ended it if any
+    pub fn disconnected(&self, source: EventSource, error: Option<String>) {
+        let mut sources = self.sources.lock().unwrap();
+        let state = sources.entry(source).or_default();
+        state.connected = false;
+        state.connected_since = None;
+        if let Some(error) = error {
+            state.last_error = Some(error);
+            state.last_error_at = Some(chrono::Utc::now().timestamp());
+        }
+    }
+
+    /// Record a WebSocket frame received with a payload of `bytes`
+    pub fn frame_received(&self, source: EventSource, bytes: usize) {
+        self.frame_received_at(source, bytes, chrono::Utc::now().timestamp());
+    }
+
+    fn frame_received_at(&self, source: EventSource, bytes: usize, now: i64) {
+        let mut sources = self.sources.lock().unwrap();
+        let state = sources.entry(source).or_default();
+        state.frames += 1;
+        state.bytes += bytes as u64;
+        match state.recent_bytes.back_mut() {
+            Some((second, total)) if *second == now => *total += bytes as u64,
+            _ => state.recent_bytes.push_back((now, bytes as u64)),
+        }
+        while state
+            .recent_bytes
+            .front()
+            .is_some_and(|(second, _)| *second <= now - THROUGHPUT_WINDOW_SECS)
+        {
+            state.recent_bytes.pop_front();
+        }
+    }
+
+    /// Current state of every source that has attempted to connect
+    pub fn snapshot(&self) -> Vec<(EventSource, SourceConnection)> {
+        self.snapshot_at(chrono::Utc::now().timestamp())
+    }
+
+    fn snapshot_at(&self, now: i64) -> Vec<(EventSource, SourceConnection)> {
+        let sources = self.sources.lock().unwrap();
+        let mut snapshot: Vec<_> = sources
+            .iter()
+            .map(|(source, state)| {
+                let mut state = state.clone();
+                let recent: u64 = state
+                    .recent_bytes
+                    .iter()
+                    .filter(|(second, _)| *second > now - THROUGHPUT_WINDOW_SECS)
+                    .map(|(_, bytes)| bytes)
+                    .sum();
+                state.bytes_per_sec = recent as f64 / THROUGHPUT_WINDOW_SECS as f64;
+                (*source, state)
+            })
+            .collect();
+        snapshot.sort_by_key(|(source, _)| source.to_string());
+        snapshot
+    }
+}
+
 /// Unified client for all UniFi event sources
 pub struct UnifiClient {
     session: Arc<UnifiSession>,
     event_rx: mpsc::Receiver<UnifiEvent>,
     handles: Vec<JoinHandle<()>>,
+    backfill: HistoricalFetchResult,
+    connections: ConnectionTracker,
     // These fields are cloned and passed to spawned tasks; kept here for ownership
     _seen_events: SeenEvents,
     _state_tracker: StateTracker,
@@ -61,8 +328,17 @@ pub struct UnifiClient {
 
 impl UnifiClient {
     /// Connect to UniFi console and start event collection
-    #[instrument(skip(config, db), fields(host = %config.host))]
     pub async fn connect(config: UnifiConfig, db: Option<Database>) -> Result<Self, UnifiError> {
+        Self::connect_tracked(config, db, ConnectionTracker::default()).await
+    }
+
+    /// Like `connect`, recording each WebSocket's connection state in `connections`
+    #[instrument(skip(config, db, connections), fields(host = %config.host))]
+    pub async fn connect_tracked(
+        config: UnifiConfig,
+        db: Option<Database>,
+        connections: ConnectionTracker,
+    ) -> Result<Self, UnifiError> {
         // Authenticate
         let session = Arc::new(UnifiSession::login(config).await?);
 
@@ -75,7 +351,13 @@ impl UnifiClient {
         let (event_tx, event_rx) = mpsc::channel(1000);
 
         // Create shared set for deduplication
-        let seen_events: SeenEvents = Arc::new(Mutex::new(HashSet::new()));
+        let seen_events: SeenEvents = {
+            let mut seen = SeenSet::new(session.config.dedup_scope);
+            if let Some(window) = session.config.dedup_window {
+                seen = seen.with_window(window);
+            }
+            Arc::new(Mutex::new(seen))
+        };
 
         // Create state tracker to filter unchanged "update" events
         let state_tracker: StateTracker = Arc::new(Mutex::new(HashMap::new()));
@@ -99,12 +381,21 @@ impl UnifiClient {
         let tx_clone = event_tx.clone();
         let seen_clone = seen_events.clone();
         let state_clone = state_tracker.clone();
+        let db_clone = db.clone();
+        let connections_clone = connections.clone();
         handles.push(tokio::spawn(async move {
             loop {
                 info!("Starting Network WebSocket connection");
-                match connect_network_websocket(&session_clone, tx_clone.clone(), seen_clone.clone(), state_clone.clone()).await {
-                    Ok(_) => info!("Network WebSocket disconnected normally"),
-                    Err(e) => error!("Network WebSocket error: {}", e),
+                connections_clone.connecting(EventSource::Network);
+                match connect_network_websocket(&session_clone, tx_clone.clone(), seen_clone.clone(), state_clone.clone(), db_clone.clone(), &connections_clone).await {
+                    Ok(_) => {
+                        info!("Network WebSocket disconnected normally");
+                        connections_clone.disconnected(EventSource::Network, None);
+                    }
+                    Err(e) => {
+                        error!("Network WebSocket error: {}", e);
+                        connections_clone.disconnected(EventSource::Network, Some(e.to_string()));
+                    }
                 }
                 warn!("Network WebSocket disconnected, reconnecting in 5s...");
                 tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
@@ -116,12 +407,21 @@ impl UnifiClient {
         let tx_clone = event_tx.clone();
         let seen_clone = seen_events.clone();
         let state_clone = state_tracker.clone();
+        let db_clone = db.clone();
+        let connections_clone = connections.clone();
         handles.push(tokio::spawn(async move {
             loop {
                 info!("Starting System WebSocket connection");
-                match connect_system_websocket(&session_clone, tx_clone.clone(), seen_clone.clone(), state_clone.clone()).await {
-                    Ok(_) => info!("System WebSocket disconnected normally"),
-                    Err(e) => error!("System WebSocket error: {}", e),
+                connections_clone.connecting(EventSource::System);
+                match connect_system_websocket(&session_clone, tx_clone.clone(), seen_clone.clone(), state_clone.clone(), db_clone.clone(), &connections_clone).await {
+                    Ok(_) => {
+                        info!("System WebSocket disconnected normally");
+                        connections_clone.disconnected(EventSource::System, None);
+                    }
+                    Err(e) => {
+                        error!("System WebSocket error: {}", e);
+                        connections_clone.disconnected(EventSource::System, Some(e.to_string()));
+                    }
                 }
                 warn!("System WebSocket disconnected, reconnecting in 5s...");
                 tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
@@ -135,8 +435,10 @@ impl UnifiClient {
         let state_clone = state_tracker.clone();
         let db_clone = db.clone();
         let bootstrap_update_id = bootstrap_update_id.clone();
+        let connections_clone = connections.clone();
         handles.push(tokio::spawn(async move {
             let mut rapid_disconnect_count = 0u32;
+            let camera_tracker = CameraTracker::default();
 
             loop {
                 // Query database for latest lastUpdateId on each reconnect
@@ -183,13 +485,20 @@ impl UnifiClient {
                 };
 
                 info!("Starting Protect WebSocket connection");
+                connections_clone.connecting(EventSource::Protect);
                 let start_time = std::time::Instant::now();
 
-                match connect_protect_websocket(&session_clone, &current_update_id, tx_clone.clone(), seen_clone.clone(), state_clone.clone(), db_clone.clone())
+                match connect_protect_websocket(&session_clone, &current_update_id, tx_clone.clone(), seen_clone.clone(), state_clone.clone(), camera_tracker.clone(), db_clone.clone(), &connections_clone)
                     .await
                 {
-                    Ok(_) => info!("Protect WebSocket disconnected normally"),
-                    Err(e) => error!("Protect WebSocket error: {}", e),
+                    Ok(_) => {
+                        info!("Protect WebSocket disconnected normally");
+                        connections_clone.disconnected(EventSource::Protect, None);
+                    }
+                    Err(e) => {
+                        error!("Protect WebSocket error: {}", e);
+                        connections_clone.disconnected(EventSource::Protect, Some(e.to_string()));
+                    }
                 }
 
                 // Check if connection was very short (< 5 seconds = likely invalid lastUpdateId)
@@ -212,15 +521,34 @@ impl UnifiClient {
         }));
 
         // Now fetch historical events from REST API
-        // These will be deduplicated against any events already received via WebSocket
-        info!("Fetching historical events from REST API...");
-        let historical_count = Self::fetch_historical_events(&session, &event_tx, &seen_events).await;
-        info!(count = historical_count, "Loaded historical events");
+        // These will be deduplicated against any events already received via WebSocket,
+        // and against events already stored by a previous run
+        let backfill = if session.config.skip_historical_backfill {
+            info!("Historical backfill disabled, relying on live WebSockets only");
+            HistoricalFetchResult {
+                skipped: true,
+                completed_at: chrono::Utc::now().timestamp(),
+                ..Default::default()
+            }
+        } else {
+            info!("Fetching historical events from REST API...");
+            let result = Self::fetch_historical_events(&session, &event_tx, &seen_events, db.as_ref()).await;
+            info!(
+                count = result.total(),
+                network = result.network,
+                system = result.system,
+                errors = result.errors.len(),
+                "Loaded historical events"
+            );
+            result
+        };
 
         Ok(Self {
             session,
             event_rx,
             handles,
+            backfill,
+            connections,
             _seen_events: seen_events,
             _state_tracker: state_tracker,
             _db: db,
@@ -232,61 +560,62 @@ impl UnifiClient {
         session: &UnifiSession,
         event_tx: &mpsc::Sender<UnifiEvent>,
         seen_events: &SeenEvents,
-    ) -> usize {
-        let mut count = 0;
+        db: Option<&Database>,
+    ) -> HistoricalFetchResult {
+        let mut result = HistoricalFetchResult::default();
 
         // Fetch network events
         match session.get_network_events(Some(1000)).await {
             Ok(events) => {
                 for raw in events {
-                    if let Some(event) = Self::parse_network_event(&raw) {
-                        let mut seen = seen_events.lock().await;
-                        if seen.insert(event.id.clone()) {
-                            drop(seen);
+                    if let Some(event) = Self::parse_network_event(&raw, &session.config) {
+                        if is_new_event(seen_events, db, &event).await {
                             if event_tx.send(event).await.is_err() {
                                 warn!("Event channel closed while loading historical events");
-                                return count;
+                                result.completed_at = chrono::Utc::now().timestamp();
+                                return result;
                             }
-                            count += 1;
+                            result.network += 1;
                         }
                     }
                 }
-                debug!(count = count, "Loaded network events");
+                debug!(count = result.network, "Loaded network events");
             }
             Err(e) => {
                 warn!("Failed to fetch network events: {}", e);
+                result.errors.push(format!("network: {}", e));
             }
         }
 
         // Fetch system events
         match session.get_system_events(Some(500)).await {
             Ok(events) => {
-                let before = count;
                 for raw in events {
-                    if let Some(event) = Self::parse_system_event(&raw) {
-                        let mut seen = seen_events.lock().await;
-                        if seen.insert(event.id.clone()) {
-                            drop(seen);
+                    if let Some(event) = Self::parse_system_event(&raw, &session.config) {
+                        if is_new_event(seen_events, db, &event).await {
                             if event_tx.send(event).await.is_err() {
                                 warn!("Event channel closed while loading historical events");
-                                return count;
+                                result.completed_at = chrono::Utc::now().timestamp();
+                                return result;
                             }
-                            count += 1;
+                            result.system += 1;
                         }
                     }
                 }
-                debug!(count = count - before, "Loaded system events");
+                debug!(count = result.system, "Loaded system events");
             }
             Err(e) => {
                 warn!("Failed to fetch system events: {}", e);
+                result.errors.push(format!("system: {}", e));
             }
         }
 
-        count
+        result.completed_at = chrono::Utc::now().timestamp();
+        result
     }
 
     /// Parse a raw network event from REST API
-    fn parse_network_event(raw: &serde_json::Value) -> Option<UnifiEvent> {
+    fn parse_network_event(raw: &serde_json::Value, config: &UnifiConfig) -> Option<UnifiEvent> {
         let event_type = raw.get("key")
             .and_then(|v| v.as_str())
             .or_else(|| raw.get("type").and_then(|v| v.as_str()))
@@ -311,18 +640,14 @@ impl UnifiClient {
 
         let severity = match event_type {
             "EVT_LAN_CLIENT_BLOCKED" | "EVT_AP_LOST_CONTACT" => Some(Severity::Warning),
-            _ => None,
+            _ => config.rest_severity_defaults.for_event(EventSource::Network, event_type),
         };
 
         // Generate content-based ID for deduplication
-        // Use UniFi's _id if available, otherwise extract key fields
-        let key_fields = if let Some(unifi_id) = raw.get("_id").and_then(|v| v.as_str()) {
-            vec![unifi_id.to_string()]
-        } else {
-            extract_key_fields(raw)
-        };
-        let key_refs: Vec<&str> = key_fields.iter().map(|s| s.as_str()).collect();
-        let id = generate_event_id(EventSource::Network, event_type, timestamp, &key_refs);
+        // Use UniFi's _id if available, otherwise apply the missing-ID strategy
+        let unifi_id = raw.get("_id").and_then(|v| v.as_str());
+        let missing_id = config.missing_id.for_backfill(EventSource::Network);
+        let id = unifi_event_id(EventSource::Network, event_type, timestamp, unifi_id, raw, missing_id)?;
 
         Some(UnifiEvent {
             id,
@@ -336,7 +661,7 @@ impl UnifiClient {
     }
 
     /// Parse a raw system event from REST API
-    fn parse_system_event(raw: &serde_json::Value) -> Option<UnifiEvent> {
+    fn parse_system_event(raw: &serde_json::Value, config: &UnifiConfig) -> Option<UnifiEvent> {
         let event_type = raw.get("key")
             .and_then(|v| v.as_str())
             .or_else(|| raw.get("type").and_then(|v| v.as_str()))
@@ -365,14 +690,10 @@ impl UnifiClient {
         };
 
         // Generate content-based ID for deduplication
-        // Use UniFi's _id if available, otherwise extract key fields
-        let key_fields = if let Some(unifi_id) = raw.get("_id").and_then(|v| v.as_str()) {
-            vec![unifi_id.to_string()]
-        } else {
-            extract_key_fields(raw)
-        };
-        let key_refs: Vec<&str> = key_fields.iter().map(|s| s.as_str()).collect();
-        let id = generate_event_id(EventSource::System, event_type, timestamp, &key_refs);
+        // Use UniFi's _id if available, otherwise apply the missing-ID strategy
+        let unifi_id = raw.get("_id").and_then(|v| v.as_str());
+        let missing_id = config.missing_id.for_backfill(EventSource::System);
+        let id = unifi_event_id(EventSource::System, event_type, timestamp, unifi_id, raw, missing_id)?;
 
         Some(UnifiEvent {
             id,
@@ -380,16 +701,41 @@ impl UnifiClient {
             source: EventSource::System,
             event_type: event_type.to_string(),
             summary,
-            severity: None,
+            severity: config.rest_severity_defaults.for_event(EventSource::System, event_type),
             raw: raw.clone(),
         })
     }
 
     /// Get the event stream
+    ///
+    /// The stream borrows the client mutably, so it can't outlive it or be
+    /// moved into another task. See [`take_events`](Self::take_events) for an
+    /// owned stream.
     pub fn events(&mut self) -> impl Stream<Item = UnifiEvent> + '_ {
         futures_util::stream::poll_fn(move |cx| self.event_rx.poll_recv(cx))
     }
 
+    /// Take the event receiver as an owned, `Send + 'static` stream
+    ///
+    /// The stream can be spawned or selected over independently of the
+    /// client. The client must still be kept alive: dropping it stops the
+    /// WebSocket tasks, which ends the stream. Afterwards [`events`](Self::events)
+    /// yields nothing, and a second call returns an already-ended stream.
+    pub fn take_events(&mut self) -> ReceiverStream<UnifiEvent> {
+        let (_, closed) = mpsc::channel(1);
+        ReceiverStream::new(std::mem::replace(&mut self.event_rx, closed))
+    }
+
+    /// Result of the historical backfill performed on connect
+    pub fn backfill_result(&self) -> &HistoricalFetchResult {
+        &self.backfill
+    }
+
+    /// Per-source WebSocket connection state
+    pub fn connections(&self) -> &ConnectionTracker {
+        &self.connections
+    }
+
     /// Get a reference to the session for direct API calls
     pub fn session(&self) -> &UnifiSession {
         &self.session
@@ -403,3 +749,233 @@ impl Drop for UnifiClient {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unifi::types::{validate_ws_path, SeverityDefaults};
+
+    #[tokio::test]
+    async fn test_stored_event_not_reemitted() {
+        let db = Database::open_in_memory().unwrap();
+        let event = UnifiEvent {
+            id: "network-0000000000000001".to_string(),
+            timestamp: chrono::Utc::now(),
+            source: EventSource::Network,
+            event_type: "EVT_AP_LOST_CONTACT".to_string(),
+            summary: "AP lost contact".to_string(),
+            severity: Some(Severity::Warning),
+            raw: serde_json::json!({}),
+        };
+        db.store_event(&event).unwrap();
+
+        // Fresh seen set, as after a restart
+        let seen_events: SeenEvents = Arc::new(Mutex::new(SeenSet::default()));
+        assert!(!is_new_event(&seen_events, Some(&db), &event).await);
+        let other = UnifiEvent {
+            id: "network-0000000000000002".to_string(),
+            ..event.clone()
+        };
+        assert!(is_new_event(&seen_events, Some(&db), &other).await);
+
+        // Without a database, only the in-memory set is consulted
+        let seen_events: SeenEvents = Arc::new(Mutex::new(SeenSet::default()));
+        assert!(is_new_event(&seen_events, None, &event).await);
+        assert!(!is_new_event(&seen_events, None, &event).await);
+    }
+
+    #[test]
+    fn test_connection_tracker_counts_reconnects() {
+        let tracker = ConnectionTracker::default();
+        tracker.connecting(EventSource::Protect);
+        let (_, state) = tracker.snapshot().pop().unwrap();
+        assert!(state.connected);
+        assert_eq!(state.reconnects, 0);
+        assert!(state.connected_since.is_some());
+
+        tracker.disconnected(EventSource::Protect, Some("WebSocket error: reset".to_string()));
+        let (_, state) = tracker.snapshot().pop().unwrap();
+        assert!(!state.connected);
+        assert!(state.connected_since.is_none());
+        assert_eq!(state.last_error.as_deref(), Some("WebSocket error: reset"));
+
+        // A clean disconnect keeps the last error
+        tracker.connecting(EventSource::Protect);
+        tracker.disconnected(EventSource::Protect, None);
+        tracker.connecting(EventSource::Protect);
+        tracker.connecting(EventSource::Network);
+
+        let snapshot = tracker.snapshot();
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(snapshot[0].0, EventSource::Network);
+        assert_eq!(snapshot[0].1.reconnects, 0);
+        assert_eq!(snapshot[1].0, EventSource::Protect);
+        assert_eq!(snapshot[1].1.reconnects, 2);
+        assert!(snapshot[1].1.connected);
+        assert_eq!(snapshot[1].1.last_error.as_deref(), Some("WebSocket error: reset"));
+    }
+
+    #[test]
+    fn test_connection_tracker_counts_frames() {
+        let tracker = ConnectionTracker::default();
+        let now = 1_700_000_000;
+        tracker.frame_received_at(EventSource::Protect, 1_000, now - 120);
+        tracker.frame_received_at(EventSource::Protect, 2_000, now - 30);
+        tracker.frame_received_at(EventSource::Protect, 1_000, now - 30);
+        tracker.frame_received_at(EventSource::Network, 600, now);
+
+        let snapshot = tracker.snapshot_at(now);
+        let (source, network) = &snapshot[0];
+        assert_eq!(*source, EventSource::Network);
+        assert_eq!((network.frames, network.bytes), (1, 600));
+        assert_eq!(network.bytes_per_sec, 10.0);
+
+        // Totals include everything; the rate only the last minute
+        let (source, protect) = &snapshot[1];
+        assert_eq!(*source, EventSource::Protect);
+        assert_eq!((protect.frames, protect.bytes), (3, 4_000));
+        assert_eq!(protect.bytes_per_sec, 50.0);
+        assert_eq!(protect.recent_bytes.len(), 1);
+
+        // Counting frames doesn't count as connecting
+        assert!(!protect.connected);
+        assert_eq!(tracker.snapshot_at(now + 60)[1].1.bytes_per_sec, 0.0);
+    }
+
+    #[test]
+    fn test_taken_events_stream_is_owned() {
+        // Compile-time check: the stream doesn't borrow the client and can be spawned
+        fn assert_owned<S: Stream<Item = UnifiEvent> + Send + 'static>(_: fn(&mut UnifiClient) -> S) {}
+        assert_owned(UnifiClient::take_events);
+    }
+
+    #[test]
+    fn test_rest_severity_defaults() {
+        let defaults = SeverityDefaults::parse("system=info, network:EVT_AP_=error, network:EVT_=warning").unwrap();
+        let config = UnifiConfig::new("192.168.1.1", "admin", "pw").with_rest_severity_defaults(defaults);
+
+        let network = |key: &str| {
+            let raw = serde_json::json!({"key": key, "_id": "n1", "time": 1_700_000_000});
+            UnifiClient::parse_network_event(&raw, &config).unwrap().severity
+        };
+        // The longest matching prefix wins, and parser-assigned severities are kept
+        assert_eq!(network("EVT_AP_RESTARTED"), Some(Severity::Error));
+        assert_eq!(network("EVT_SW_RESTARTED"), Some(Severity::Warning));
+        assert_eq!(network("EVT_AP_LOST_CONTACT"), Some(Severity::Warning));
+        assert_eq!(network("sta:roam"), None);
+
+        let raw = serde_json::json!({"key": "BACKUP_COMPLETED", "_id": "s1"});
+        assert_eq!(UnifiClient::parse_system_event(&raw, &config).unwrap().severity, Some(Severity::Info));
+        let raw = serde_json::json!({"key": "BACKUP_COMPLETED", "_id": "s1"});
+        let unconfigured = UnifiConfig::new("192.168.1.1", "admin", "pw");
+        assert_eq!(UnifiClient::parse_system_event(&raw, &unconfigured).unwrap().severity, None);
+
+        assert_eq!(SeverityDefaults::parse("network=loud"), None);
+        assert_eq!(SeverityDefaults::parse("camera=info"), None);
+        assert_eq!(SeverityDefaults::parse("network"), None);
+    }
+
+    #[test]
+    fn test_ws_path_overrides() {
+        let config = UnifiConfig::new("192.168.1.1", "admin", "pw").with_ws_path(EventSource::Protect, "/protect/ws");
+        assert_eq!(config.ws_url(EventSource::Protect), "wss://192.168.1.1/protect/ws");
+        assert_eq!(
+            config.ws_url(EventSource::Network),
+            "wss://192.168.1.1/proxy/network/wss/s/default/events"
+        );
+        assert_eq!(config.ws_url(EventSource::System), "wss://192.168.1.1/api/ws/system");
+
+        assert!(validate_ws_path("/proxy/protect/ws/updates").is_ok());
+        assert!(validate_ws_path("proxy/protect").is_err());
+        assert!(validate_ws_path("//evil.example/ws").is_err());
+        assert!(validate_ws_path("/ws?lastUpdateId=1").is_err());
+        assert!(validate_ws_path("/ws path").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_store_unchanged_updates() {
+        let config = UnifiConfig::new("192.168.1.1", "admin", "pw")
+            .with_store_unchanged_updates(EventSource::parse_list("Network, protect").unwrap());
+        assert!(config.stores_unchanged_updates(EventSource::Network));
+        assert!(config.stores_unchanged_updates(EventSource::Protect));
+        assert!(!config.stores_unchanged_updates(EventSource::System));
+        assert_eq!(EventSource::parse_list("all").unwrap(), EventSource::ALL.to_vec());
+        assert_eq!(EventSource::parse_list("false").unwrap(), Vec::new());
+        assert_eq!(EventSource::parse_list("network,bogus"), None);
+
+        let update = serde_json::json!({"mac": "aa:bb", "state": 1});
+
+        // Default: a repeated identical update is dropped
+        let tracker = StateTracker::default();
+        assert!(keep_state_update(&tracker, "device:aa:bb", &update, false).await);
+        assert!(!keep_state_update(&tracker, "device:aa:bb", &update, false).await);
+
+        // Kept: every repeat passes, and a later change is still tracked
+        let tracker = StateTracker::default();
+        assert!(keep_state_update(&tracker, "device:aa:bb", &update, true).await);
+        assert!(keep_state_update(&tracker, "device:aa:bb", &update, true).await);
+        let changed = serde_json::json!({"mac": "aa:bb", "state": 2});
+        assert!(keep_state_update(&tracker, "device:aa:bb", &changed, true).await);
+        assert!(!keep_state_update(&tracker, "device:aa:bb", &changed, false).await);
+    }
+
+    #[test]
+    fn test_dedup_scope_isolates_sources() {
+        // Global: the same ID from another source is a duplicate
+        let mut seen = SeenSet::new(DedupScope::Global);
+        assert!(seen.insert(EventSource::Network, "custom-1"));
+        assert!(!seen.insert(EventSource::Protect, "custom-1"));
+
+        // Per source: sources don't suppress each other, but still dedup themselves
+        let mut seen = SeenSet::new(DedupScope::PerSource);
+        assert!(seen.insert(EventSource::Network, "custom-1"));
+        assert!(seen.insert(EventSource::Protect, "custom-1"));
+        assert!(!seen.insert(EventSource::Protect, "custom-1"));
+        assert!(!seen.insert(EventSource::Network, "custom-1"));
+    }
+
+    #[test]
+    fn test_dedup_window_collapses_same_entity() {
+        let base = chrono::Utc::now();
+        let event = |id: &str, secs: i64, mac: &str| UnifiEvent {
+            id: id.to_string(),
+            timestamp: base + chrono::Duration::seconds(secs),
+            source: EventSource::Protect,
+            event_type: "motion".to_string(),
+            summary: "Motion detected".to_string(),
+            severity: None,
+            raw: serde_json::json!({"camera": mac}),
+        };
+
+        // Disabled by default
+        let mut seen = SeenSet::default();
+        assert!(seen.accept_in_window(&event("a", 0, "cam1")));
+        assert!(seen.accept_in_window(&event("b", 1, "cam1")));
+
+        let mut seen = SeenSet::default().with_window(std::time::Duration::from_secs(60));
+        assert!(seen.accept_in_window(&event("a", 0, "cam1")));
+        // Re-reported with a new ID inside the window
+        assert!(!seen.accept_in_window(&event("b", 30, "cam1")));
+        // Another entity isn't affected
+        assert!(seen.accept_in_window(&event("c", 30, "cam2")));
+        // The window runs from the last accepted event, not the last suppressed one
+        assert!(seen.accept_in_window(&event("d", 61, "cam1")));
+
+        // Events without an entity only dedup by ID
+        let mut anonymous = event("e", 62, "cam1");
+        anonymous.raw = serde_json::json!({});
+        assert!(seen.accept_in_window(&anonymous));
+        assert!(seen.accept_in_window(&anonymous));
+    }
+
+    #[test]
+    fn test_seen_set_is_bounded() {
+        let mut seen = SeenSet::default();
+        for i in 0..=MAX_SEEN_PER_SET {
+            assert!(seen.insert(EventSource::Network, &i.to_string()));
+        }
+        // The oldest ID was evicted, the newest is still remembered
+        assert!(seen.insert(EventSource::Network, "0"));
+        assert!(!seen.insert(EventSource::Network, &MAX_SEEN_PER_SET.to_string()));
+    }
+}
@@ -7,6 +7,9 @@ pub mod system;
 pub mod types;
 
 pub use auth::{BootstrapResponse, UnifiSession};
-pub use client::{SeenEvents, StateTracker, UnifiClient};
+pub use client::{
+    ConnectionTracker, HistoricalFetchResult, SeenEvents, SeenSet, SourceConnection, StateTracker, UnifiClient,
+};
 pub use error::UnifiError;
-pub use types::{EventSource, UnifiConfig, UnifiEvent};
+pub use protect::CameraTracker;
+pub use types::{DedupScope, EventSource, MissingIdPolicy, MissingIdStrategy, SeverityDefaults, UnifiConfig, UnifiEvent};
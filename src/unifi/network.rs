@@ -19,9 +19,11 @@ use tokio_tungstenite::{
 use tracing::{error, info, trace, warn};
 
 use super::auth::UnifiSession;
-use super::client::{state_changed, SeenEvents, StateTracker};
+use super::client::{is_new_event, keep_state_update, ConnectionTracker, SeenEvents, StateTracker};
 use super::error::UnifiError;
-use super::types::{extract_key_fields, generate_event_id, EventSource, Severity, UnifiEvent};
+use super::types::{unifi_event_id, EventSource, MissingIdStrategy, Severity, UnifiEvent};
+
+use crate::db::Database;
 
 /// Meta information in network events
 #[derive(Debug, Deserialize)]
@@ -43,8 +45,8 @@ struct RawNetworkEvent {
     /// Meta information containing message type
     meta: Option<EventMeta>,
 
-    /// Event data
-    #[serde(default)]
+    /// Event data (usually an array, but some messages send a single object)
+    #[serde(default, deserialize_with = "one_or_many")]
     data: Vec<serde_json::Value>,
 
     /// Timestamp (milliseconds)
@@ -61,19 +63,27 @@ pub async fn connect_network_websocket(
     event_tx: mpsc::Sender<UnifiEvent>,
     seen_events: SeenEvents,
     state_tracker: StateTracker,
+    db: Option<Database>,
+    connections: &ConnectionTracker,
 ) -> Result<(), UnifiError> {
-    let ws_url = format!(
-        "wss://{}/proxy/network/wss/s/default/events",
-        session.config.host
-    );
+    let ws_url = session.config.ws_url(EventSource::Network);
+
+    let missing_id = session.config.missing_id.for_source(EventSource::Network);
+    let store_unchanged = session.config.stores_unchanged_updates(EventSource::Network);
 
     info!("Connecting to Network WebSocket: {}", ws_url);
 
-    // Build request with authentication cookie
+    // Build request with User-Agent and authentication cookie
     let mut request = ws_url
         .into_client_request()
         .map_err(|e| UnifiError::WebSocket(e.to_string()))?;
 
+    request.headers_mut().insert(
+        "User-Agent",
+        HeaderValue::from_str(&session.config.user_agent)
+            .map_err(|e| UnifiError::WebSocket(e.to_string()))?,
+    );
+
     let cookie_header = session.get_cookie_header();
     if !cookie_header.is_empty() {
         request.headers_mut().insert(
@@ -91,37 +101,41 @@ pub async fn connect_network_websocket(
 
     let connector = Connector::NativeTls(tls_connector);
 
-    let (ws_stream, _) = connect_async_tls_with_config(request, None, false, Some(connector))
-        .await
-        .map_err(|e| UnifiError::WebSocket(e.to_string()))?;
+    let (ws_stream, _) = tokio::time::timeout(
+        session.config.connect_timeout,
+        connect_async_tls_with_config(request, None, false, Some(connector)),
+    )
+    .await
+    .map_err(|_| UnifiError::ConnectionFailed("WebSocket handshake timed out".to_string()))?
+    .map_err(|e| UnifiError::WebSocket(e.to_string()))?;
 
     let (mut write, mut read) = ws_stream.split();
 
     info!("Network WebSocket connected");
 
     while let Some(msg) = read.next().await {
+        if let Ok(frame) = &msg {
+            connections.frame_received(EventSource::Network, frame.len());
+        }
+
         match msg {
             Ok(Message::Text(text)) => {
                 trace!("Network event: {}", text);
 
                 match serde_json::from_str::<RawNetworkEvent>(&text) {
                     Ok(raw) => {
-                        if let Some((event, is_sync, entity_id, state_data)) = parse_network_event(raw, &text) {
+                        if let Some((event, is_sync, entity_id, state_data)) = parse_network_event(raw, &text, missing_id) {
                             // For sync events (sta:sync, device:sync), check if state actually changed
-                            if is_sync {
-                                if !state_changed(&state_tracker, &entity_id, &state_data).await {
-                                    trace!("Skipping unchanged sync for {}", entity_id);
-                                    continue;
-                                }
+                            // unless unchanged syncs are kept
+                            if is_sync && !keep_state_update(&state_tracker, &entity_id, &state_data, store_unchanged).await {
+                                trace!("Skipping unchanged sync for {}", entity_id);
+                                continue;
                             }
 
-                            // Deduplicate against seen events
-                            let mut seen = seen_events.lock().await;
-                            if !seen.insert(event.id.clone()) {
-                                trace!("Skipping duplicate event: {}", event.id);
+                            // Deduplicate against seen and already stored events
+                            if !is_new_event(&seen_events, db.as_ref(), &event).await {
                                 continue;
                             }
-                            drop(seen);
 
                             if event_tx.send(event).await.is_err() {
                                 warn!("Event channel closed, stopping Network WebSocket");
@@ -154,8 +168,24 @@ pub async fn connect_network_websocket(
     Ok(())
 }
 
+/// Accept `data` as an array, a single object, or null
+fn one_or_many<'de, D>(deserializer: D) -> Result<Vec<serde_json::Value>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::Array(items) => items,
+        serde_json::Value::Null => Vec::new(),
+        other => vec![other],
+    })
+}
+
 /// Returns (event, is_sync_event, entity_id, state_data) for state tracking
-fn parse_network_event(raw: RawNetworkEvent, original: &str) -> Option<(UnifiEvent, bool, String, serde_json::Value)> {
+fn parse_network_event(
+    raw: RawNetworkEvent,
+    original: &str,
+    missing_id: MissingIdStrategy,
+) -> Option<(UnifiEvent, bool, String, serde_json::Value)> {
     // Event type can be in: type, key, or meta.message
     let event_type = raw
         .event_type
@@ -165,8 +195,7 @@ fn parse_network_event(raw: RawNetworkEvent, original: &str) -> Option<(UnifiEve
 
     let timestamp = raw
         .time
-        .map(|ms| chrono::DateTime::from_timestamp_millis(ms))
-        .flatten()
+        .and_then(chrono::DateTime::from_timestamp_millis)
         .unwrap_or_else(chrono::Utc::now);
 
     let summary = generate_summary(&event_type, &raw.data);
@@ -183,14 +212,15 @@ fn parse_network_event(raw: RawNetworkEvent, original: &str) -> Option<(UnifiEve
     let raw_json: serde_json::Value = serde_json::from_str(original).unwrap_or(serde_json::Value::Null);
 
     // Generate content-based ID for deduplication
-    // If UniFi provides an _id, use it as a key field; otherwise extract from payload
-    let key_fields = if let Some(unifi_id) = &raw.id {
-        vec![unifi_id.clone()]
-    } else {
-        extract_key_fields(&raw_json)
-    };
-    let key_refs: Vec<&str> = key_fields.iter().map(|s| s.as_str()).collect();
-    let id = generate_event_id(EventSource::Network, &event_type, timestamp, &key_refs);
+    // If UniFi provides an _id, use it as a key field; otherwise apply the missing-ID strategy
+    let id = unifi_event_id(
+        EventSource::Network,
+        &event_type,
+        timestamp,
+        raw.id.as_deref(),
+        &raw_json,
+        missing_id,
+    )?;
 
     // Entity ID for state tracking - extract from data if possible
     let entity_id = if let Some(first) = raw.data.first() {
@@ -253,3 +283,75 @@ fn generate_summary(event_type: &str, data: &[serde_json::Value]) -> String {
         _ => format!("{} event", event_type),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unifi::types::MissingIdPolicy;
+
+    #[test]
+    fn test_data_object_or_array() {
+        let array = r#"{"type":"alarm","time":1700000000000,"data":[{"_id":"a1","msg":"AP lost contact"}]}"#;
+        let object = r#"{"type":"alarm","time":1700000000000,"data":{"_id":"a1","msg":"AP lost contact"}}"#;
+
+        let mut ids = Vec::new();
+        for text in [array, object] {
+            let raw: RawNetworkEvent = serde_json::from_str(text).unwrap();
+            assert_eq!(raw.data.len(), 1);
+            let (event, _, entity_id, _) = parse_network_event(raw, text, MissingIdStrategy::ContentHash).unwrap();
+            assert_eq!(event.summary, "AP lost contact");
+            assert_eq!(entity_id, "alarm:a1");
+            ids.push(event.id);
+        }
+        // Both shapes identify the same event
+        assert_eq!(ids[0], ids[1]);
+
+        let raw: RawNetworkEvent = serde_json::from_str(r#"{"type":"evt","data":null}"#).unwrap();
+        assert!(raw.data.is_empty());
+    }
+
+    #[test]
+    fn test_missing_id_strategies() {
+        let text = r#"{"type":"evt","time":1700000000000,"data":[{"mac":"aa:bb","msg":"Roamed"}]}"#;
+        let parse = |strategy| {
+            let raw: RawNetworkEvent = serde_json::from_str(text).unwrap();
+            parse_network_event(raw, text, strategy).map(|(event, ..)| event.id)
+        };
+
+        let hashed = parse(MissingIdStrategy::ContentHash).unwrap();
+        assert_eq!(parse(MissingIdStrategy::ContentHash).unwrap(), hashed);
+
+        let unique = parse(MissingIdStrategy::Unique).unwrap();
+        assert_ne!(unique, hashed);
+        assert_ne!(parse(MissingIdStrategy::Unique).unwrap(), unique);
+
+        assert_eq!(parse(MissingIdStrategy::Drop), None);
+
+        // Events carrying a UniFi ID are identified by it whatever the strategy
+        let with_id = r#"{"type":"evt","time":1700000000000,"_id":"e1"}"#;
+        let ids: Vec<_> = [MissingIdStrategy::ContentHash, MissingIdStrategy::Unique, MissingIdStrategy::Drop]
+            .into_iter()
+            .map(|strategy| {
+                let raw: RawNetworkEvent = serde_json::from_str(with_id).unwrap();
+                parse_network_event(raw, with_id, strategy).unwrap().0.id
+            })
+            .collect();
+        assert!(ids.iter().all(|id| *id == ids[0]));
+    }
+
+    #[test]
+    fn test_missing_id_policy() {
+        let policy = MissingIdPolicy::parse("unique, network=drop").unwrap();
+        assert_eq!(policy.for_source(EventSource::Network), MissingIdStrategy::Drop);
+        assert_eq!(policy.for_source(EventSource::System), MissingIdStrategy::Unique);
+        // The backfill hashes instead, since it refetches the same history
+        assert_eq!(policy.for_backfill(EventSource::System), MissingIdStrategy::ContentHash);
+        assert_eq!(policy.for_backfill(EventSource::Network), MissingIdStrategy::Drop);
+        assert_eq!(
+            MissingIdPolicy::parse("").unwrap().for_source(EventSource::System),
+            MissingIdStrategy::ContentHash
+        );
+        assert_eq!(MissingIdPolicy::parse("network=sometimes"), None);
+        assert_eq!(MissingIdPolicy::parse("camera=drop"), None);
+    }
+}
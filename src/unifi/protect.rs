@@ -17,8 +17,10 @@
 use flate2::read::ZlibDecoder;
 use futures_util::{SinkExt, StreamExt};
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::io::Read;
-use tokio::sync::mpsc;
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex};
 use tokio_tungstenite::{
     connect_async_tls_with_config,
     tungstenite::{client::IntoClientRequest, http::HeaderValue, Message},
@@ -27,7 +29,7 @@ use tokio_tungstenite::{
 use tracing::{debug, error, info, trace, warn};
 
 use super::auth::UnifiSession;
-use super::client::{state_changed, SeenEvents, StateTracker};
+use super::client::{is_new_event, keep_state_update, ConnectionTracker, SeenEvents, StateTracker};
 use super::error::UnifiError;
 use super::types::{generate_event_id, EventSource, Severity, UnifiEvent};
 
@@ -38,6 +40,20 @@ const PACKET_TYPE_PAYLOAD: u8 = 2;
 
 const FORMAT_JSON: u8 = 1;
 
+/// Largest payload a frame may declare (or decompress to); real updates are a few KB
+const MAX_PAYLOAD_SIZE: usize = 4 * 1024 * 1024;
+
+/// Last known connection state per camera ID, kept across reconnects so an
+/// outage can be timed from disconnect to reconnect
+pub type CameraTracker = Arc<Mutex<HashMap<String, CameraStatus>>>;
+
+#[derive(Debug, Clone, Default)]
+pub struct CameraStatus {
+    /// Updates often carry only the changed fields, so the name is remembered
+    name: Option<String>,
+    disconnected_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
 /// Action frame from Protect WebSocket
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -77,29 +93,52 @@ impl PacketHeader {
             payload_size: u32::from_be_bytes([data[4], data[5], data[6], data[7]]),
         })
     }
+
+    /// Declared payload size, rejecting sizes no real frame would have
+    fn checked_payload_size(&self) -> Result<usize, UnifiError> {
+        let size = self.payload_size as usize;
+        if size > MAX_PAYLOAD_SIZE {
+            return Err(UnifiError::Protocol(format!(
+                "Frame declares {} byte payload, over the {} byte limit",
+                size, MAX_PAYLOAD_SIZE
+            )));
+        }
+        Ok(size)
+    }
 }
 
 /// Start the Protect WebSocket connection and stream events
+#[allow(clippy::too_many_arguments)]
 pub async fn connect_protect_websocket(
     session: &UnifiSession,
     last_update_id: &str,
     event_tx: mpsc::Sender<UnifiEvent>,
     seen_events: SeenEvents,
     state_tracker: StateTracker,
+    camera_tracker: CameraTracker,
     db: Option<Database>,
+    connections: &ConnectionTracker,
 ) -> Result<(), UnifiError> {
     let ws_url = format!(
-        "wss://{}/proxy/protect/ws/updates?lastUpdateId={}",
-        session.config.host, last_update_id
+        "{}?lastUpdateId={}",
+        session.config.ws_url(EventSource::Protect),
+        last_update_id
     );
+    let store_unchanged = session.config.stores_unchanged_updates(EventSource::Protect);
 
     info!("Connecting to Protect WebSocket: {}", ws_url);
 
-    // Build request with authentication cookie
+    // Build request with User-Agent and authentication cookie
     let mut request = ws_url
         .into_client_request()
         .map_err(|e| UnifiError::WebSocket(e.to_string()))?;
 
+    request.headers_mut().insert(
+        "User-Agent",
+        HeaderValue::from_str(&session.config.user_agent)
+            .map_err(|e| UnifiError::WebSocket(e.to_string()))?,
+    );
+
     let cookie_header = session.get_cookie_header();
     if !cookie_header.is_empty() {
         request.headers_mut().insert(
@@ -117,36 +156,41 @@ pub async fn connect_protect_websocket(
 
     let connector = Connector::NativeTls(tls_connector);
 
-    let (ws_stream, _) = connect_async_tls_with_config(request, None, false, Some(connector))
-        .await
-        .map_err(|e| UnifiError::WebSocket(e.to_string()))?;
+    let (ws_stream, _) = tokio::time::timeout(
+        session.config.connect_timeout,
+        connect_async_tls_with_config(request, None, false, Some(connector)),
+    )
+    .await
+    .map_err(|_| UnifiError::ConnectionFailed("WebSocket handshake timed out".to_string()))?
+    .map_err(|e| UnifiError::WebSocket(e.to_string()))?;
 
     let (mut write, mut read) = ws_stream.split();
 
     info!("Protect WebSocket connected");
 
     while let Some(msg) = read.next().await {
+        if let Ok(frame) = &msg {
+            connections.frame_received(EventSource::Protect, frame.len());
+        }
+
         match msg {
             Ok(Message::Binary(data)) => {
                 trace!("Protect binary message: {} bytes", data.len());
 
                 match parse_protect_packet(&data) {
                     Ok(Some((event, action_type, entity_id, state_data, new_update_id))) => {
-                        // For "update" actions, check if state actually changed
-                        if action_type == "update" {
-                            if !state_changed(&state_tracker, &entity_id, &state_data).await {
-                                trace!("Skipping unchanged update for {}", entity_id);
-                                continue;
-                            }
+                        // For "update" actions, check if state actually changed unless
+                        // unchanged updates are kept
+                        if action_type == "update" && !keep_state_update(&state_tracker, &entity_id, &state_data, store_unchanged).await {
+                            trace!("Skipping unchanged update for {}", entity_id);
+                            continue;
                         }
+                        let event = pair_camera_transition(&mut *camera_tracker.lock().await, event);
 
-                        // Deduplicate against seen events
-                        let mut seen = seen_events.lock().await;
-                        if !seen.insert(event.id.clone()) {
-                            trace!("Skipping duplicate event: {}", event.id);
+                        // Deduplicate against seen and already stored events
+                        if !is_new_event(&seen_events, db.as_ref(), &event).await {
                             continue;
                         }
-                        drop(seen);
 
                         // Save new_update_id for resume after restart
                         if let (Some(ref db), Some(ref update_id)) = (&db, &new_update_id) {
@@ -188,8 +232,11 @@ pub async fn connect_protect_websocket(
     Ok(())
 }
 
+/// Parsed Protect packet: (event, action_type, entity_id, state_data, new_update_id)
+type ParsedPacket = (UnifiEvent, String, String, serde_json::Value, Option<String>);
+
 /// Returns (event, action_type, entity_id, state_data, new_update_id) for state tracking
-fn parse_protect_packet(data: &[u8]) -> Result<Option<(UnifiEvent, String, String, serde_json::Value, Option<String>)>, UnifiError> {
+fn parse_protect_packet(data: &[u8]) -> Result<Option<ParsedPacket>, UnifiError> {
     if data.len() < 8 {
         return Err(UnifiError::Protocol("Packet too short for header".into()));
     }
@@ -206,7 +253,7 @@ fn parse_protect_packet(data: &[u8]) -> Result<Option<(UnifiEvent, String, Strin
     }
 
     let action_start = 8;
-    let action_end = action_start + action_header.payload_size as usize;
+    let action_end = action_start + action_header.checked_payload_size()?;
 
     if data.len() < action_end {
         return Err(UnifiError::Protocol("Packet too short for action payload".into()));
@@ -244,7 +291,7 @@ fn parse_protect_packet(data: &[u8]) -> Result<Option<(UnifiEvent, String, Strin
     }
 
     let data_start = action_end + 8;
-    let data_end = data_start + data_header.payload_size as usize;
+    let data_end = data_start + data_header.checked_payload_size()?;
 
     if data.len() < data_end {
         return Err(UnifiError::Protocol("Packet too short for data payload".into()));
@@ -280,11 +327,18 @@ fn decompress_if_needed(data: &[u8], compressed: bool, _format: u8) -> Result<Ve
         return Ok(data.to_vec());
     }
 
-    let mut decoder = ZlibDecoder::new(data);
+    // Read one byte past the limit so an oversized payload is detected
+    let mut decoder = ZlibDecoder::new(data).take(MAX_PAYLOAD_SIZE as u64 + 1);
     let mut decompressed = Vec::new();
     decoder
         .read_to_end(&mut decompressed)
         .map_err(|e| UnifiError::Protocol(format!("Decompression failed: {}", e)))?;
+    if decompressed.len() > MAX_PAYLOAD_SIZE {
+        return Err(UnifiError::Protocol(format!(
+            "Decompressed payload over the {} byte limit",
+            MAX_PAYLOAD_SIZE
+        )));
+    }
 
     Ok(decompressed)
 }
@@ -328,8 +382,17 @@ fn create_protect_event(
         event_id_str = eid.to_string();
         key_fields.push(&event_id_str);
     }
-    let key_refs: Vec<&str> = key_fields.iter().map(|s| *s).collect();
-    let id = generate_event_id(EventSource::Protect, &event_type, timestamp, &key_refs);
+    let id = generate_event_id(EventSource::Protect, &event_type, timestamp, &key_fields);
+
+    let mut raw = serde_json::json!({
+        "action": action.action,
+        "modelKey": action.model_key,
+        "id": action.id,
+        "data": data,
+    });
+    if action.model_key == "event" {
+        raw["normalized"] = normalize_detection(&data);
+    }
 
     Ok(UnifiEvent {
         id,
@@ -338,12 +401,93 @@ fn create_protect_event(
         event_type,
         summary,
         severity,
-        raw: serde_json::json!({
-            "action": action.action,
-            "modelKey": action.model_key,
-            "id": action.id,
-            "data": data,
-        }),
+        raw,
+    })
+}
+
+/// Turn a camera update that changes its connection state into a
+/// `camera.disconnected` (Warning) or `camera.reconnected` (Info) event, the
+/// latter with the outage length. Other events pass through unchanged.
+fn pair_camera_transition(cameras: &mut HashMap<String, CameraStatus>, mut event: UnifiEvent) -> UnifiEvent {
+    if event.event_type != "camera.update" {
+        return event;
+    }
+    let Some(camera_id) = event.raw["id"].as_str().map(str::to_string) else {
+        return event;
+    };
+    let data = &event.raw["data"];
+    let status = cameras.entry(camera_id.clone()).or_default();
+    if let Some(name) = data.get("name").and_then(|n| n.as_str()) {
+        status.name = Some(name.to_string());
+    }
+    let Some(state) = data.get("state").and_then(|s| s.as_str()).map(str::to_string) else {
+        return event;
+    };
+    let name = status.name.clone().unwrap_or_else(|| camera_id.clone());
+
+    // Pair on the outstanding disconnect rather than the previous state, since
+    // cameras may pass through CONNECTING (or flap) on the way back
+    match (state.as_str(), status.disconnected_at) {
+        ("DISCONNECTED", None) => {
+            status.disconnected_at = Some(event.timestamp);
+            event.event_type = "camera.disconnected".to_string();
+            event.summary = format!("Camera '{}' disconnected", name);
+            event.severity = Some(Severity::Warning);
+        }
+        ("CONNECTED", Some(at)) => {
+            status.disconnected_at = None;
+            let downtime = (event.timestamp - at).num_seconds().max(0);
+            event.event_type = "camera.reconnected".to_string();
+            event.summary = format!("Camera '{}' reconnected after {}", name, format_downtime(downtime));
+            event.severity = Some(Severity::Info);
+            event.raw["downtime_secs"] = downtime.into();
+        }
+        _ => return event,
+    }
+
+    event.id = generate_event_id(EventSource::Protect, &event.event_type, event.timestamp, &[&camera_id]);
+    event
+}
+
+/// Outage length as e.g. "45s", "12m 5s" or "3h 20m"
+fn format_downtime(secs: i64) -> String {
+    match secs {
+        s if s < 60 => format!("{}s", s),
+        s if s < 3600 => format!("{}m {}s", s / 60, s % 60),
+        s => format!("{}h {}m", s / 3600, s % 3600 / 60),
+    }
+}
+
+/// Normalized view of a Protect detection event, so consumers don't have to
+/// know the vendor field names: `{camera_id, camera_name, detected_types,
+/// duration_secs, score}` (fields are null when not present)
+fn normalize_detection(data: &serde_json::Value) -> serde_json::Value {
+    let camera_name = data
+        .get("cameraName")
+        .or_else(|| data.get("name"))
+        .and_then(|v| v.as_str());
+
+    let detected_types: Vec<&str> = data
+        .get("smartDetectTypes")
+        .and_then(|v| v.as_array())
+        .map(|types| types.iter().filter_map(|t| t.as_str()).collect())
+        .unwrap_or_default();
+
+    // start/end are in milliseconds; end is missing while the event is ongoing
+    let duration_secs = match (
+        data.get("start").and_then(|v| v.as_i64()),
+        data.get("end").and_then(|v| v.as_i64()),
+    ) {
+        (Some(start), Some(end)) if end >= start => Some((end - start) as f64 / 1000.0),
+        _ => None,
+    };
+
+    serde_json::json!({
+        "camera_id": data.get("camera").and_then(|v| v.as_str()),
+        "camera_name": camera_name,
+        "detected_types": detected_types,
+        "duration_secs": duration_secs,
+        "score": data.get("score").and_then(|v| v.as_i64()),
     })
 }
 
@@ -439,4 +583,120 @@ mod tests {
         assert!(!header.compressed);
         assert_eq!(header.payload_size, 10);
     }
+
+    #[test]
+    fn test_rejects_oversized_payload() {
+        // Action header declaring a 4 GiB payload, followed by a few bytes
+        let mut data = vec![PACKET_TYPE_ACTION, FORMAT_JSON, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF];
+        data.extend_from_slice(b"{}");
+
+        match parse_protect_packet(&data) {
+            Err(UnifiError::Protocol(msg)) => assert!(msg.contains("byte limit"), "{}", msg),
+            other => panic!("expected protocol error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_smart_detect_normalized() {
+        let action = ActionFrame {
+            action: "add".to_string(),
+            id: "event-1".to_string(),
+            model_key: "event".to_string(),
+            new_update_id: None,
+        };
+        let data = serde_json::json!({
+            "id": "65a1b2c3",
+            "type": "smartDetectZone",
+            "camera": "cam-1",
+            "cameraName": "Front Door",
+            "smartDetectTypes": ["person", "vehicle"],
+            "start": 1_700_000_000_000i64,
+            "end": 1_700_000_012_500i64,
+            "score": 87,
+        });
+
+        let event = create_protect_event(&action, data).unwrap();
+        let normalized = &event.raw["normalized"];
+        assert_eq!(normalized["camera_id"], "cam-1");
+        assert_eq!(normalized["camera_name"], "Front Door");
+        assert_eq!(normalized["detected_types"], serde_json::json!(["person", "vehicle"]));
+        assert_eq!(normalized["duration_secs"], 12.5);
+        assert_eq!(normalized["score"], 87);
+
+        // Raw vendor data is kept alongside
+        assert_eq!(event.raw["data"]["smartDetectTypes"][0], "person");
+    }
+
+    #[test]
+    fn test_ongoing_detection_normalized() {
+        let action = ActionFrame {
+            action: "add".to_string(),
+            id: "event-2".to_string(),
+            model_key: "event".to_string(),
+            new_update_id: None,
+        };
+        let data = serde_json::json!({"type": "motion", "start": 1_700_000_000_000i64});
+
+        let event = create_protect_event(&action, data).unwrap();
+        let normalized = &event.raw["normalized"];
+        assert!(normalized["duration_secs"].is_null());
+        assert!(normalized["camera_name"].is_null());
+        assert_eq!(normalized["detected_types"], serde_json::json!([]));
+
+        // Non-detection updates carry no normalized block
+        let camera = ActionFrame {
+            action: "update".to_string(),
+            id: "cam-1".to_string(),
+            model_key: "camera".to_string(),
+            new_update_id: None,
+        };
+        let event = create_protect_event(&camera, serde_json::json!({"state": "CONNECTED"})).unwrap();
+        assert!(event.raw.get("normalized").is_none());
+    }
+
+    #[test]
+    fn test_camera_disconnect_reconnect_paired() {
+        let camera = ActionFrame {
+            action: "update".to_string(),
+            id: "cam-1".to_string(),
+            model_key: "camera".to_string(),
+            new_update_id: None,
+        };
+        let mut cameras = HashMap::new();
+        let mut update = |data: serde_json::Value, ts: i64| {
+            let mut data = data;
+            data["timestamp"] = ts.into();
+            pair_camera_transition(&mut cameras, create_protect_event(&camera, data).unwrap())
+        };
+
+        let event = update(serde_json::json!({"name": "Porch", "state": "CONNECTED"}), 1_700_000_000);
+        assert_eq!(event.event_type, "camera.update");
+
+        let down = update(serde_json::json!({"state": "DISCONNECTED"}), 1_700_000_100);
+        assert_eq!(down.event_type, "camera.disconnected");
+        assert_eq!(down.summary, "Camera 'Porch' disconnected");
+        assert_eq!(down.severity, Some(Severity::Warning));
+
+        // Repeated disconnected states don't start a new outage
+        let event = update(serde_json::json!({"state": "DISCONNECTED", "uptime": 0}), 1_700_000_200);
+        assert_eq!(event.event_type, "camera.update");
+
+        // An intermediate CONNECTING state neither ends nor restarts the outage
+        let event = update(serde_json::json!({"state": "CONNECTING"}), 1_700_000_700);
+        assert_eq!(event.event_type, "camera.update");
+        let event = update(serde_json::json!({"state": "DISCONNECTED"}), 1_700_000_750);
+        assert_eq!(event.event_type, "camera.update");
+        let event = update(serde_json::json!({"state": "CONNECTING"}), 1_700_000_800);
+        assert_eq!(event.event_type, "camera.update");
+
+        let up = update(serde_json::json!({"state": "CONNECTED"}), 1_700_000_825);
+        assert_eq!(up.event_type, "camera.reconnected");
+        assert_eq!(up.summary, "Camera 'Porch' reconnected after 12m 5s");
+        assert_eq!(up.severity, Some(Severity::Info));
+        assert_eq!(up.raw["downtime_secs"], 725);
+        assert_ne!(up.id, down.id);
+
+        let event = update(serde_json::json!({"state": "CONNECTED", "uptime": 10}), 1_700_000_900);
+        assert_eq!(event.event_type, "camera.update");
+    }
 }
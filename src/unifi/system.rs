@@ -5,7 +5,6 @@
 //! Events: Cross-application OS-level events
 
 use futures_util::{SinkExt, StreamExt};
-use serde::Deserialize;
 use tokio::sync::mpsc;
 use tokio_tungstenite::{
     connect_async_tls_with_config,
@@ -15,15 +14,21 @@ use tokio_tungstenite::{
 use tracing::{error, info, trace, warn};
 
 use super::auth::UnifiSession;
-use super::client::{state_changed, SeenEvents, StateTracker};
+use super::client::{is_new_event, keep_state_update, ConnectionTracker, SeenEvents, StateTracker};
 use super::error::UnifiError;
-use super::types::{extract_key_fields, generate_event_id, EventSource, UnifiEvent};
+use super::types::{unifi_event_id, EventSource, MissingIdStrategy, UnifiEvent};
+
+use crate::db::Database;
 
 /// Raw system event from WebSocket
-#[derive(Debug, Deserialize)]
+///
+/// Messages differ across firmware: the type may be under `type`, `key`,
+/// `eventType` or `event`, or nested in `data` under a generic envelope type.
+/// Fields are probed from a JSON value rather than derived with serde aliases,
+/// since frames may carry several of the alternatives at once.
+#[derive(Debug)]
 struct RawSystemEvent {
     /// Event type
-    #[serde(rename = "type")]
     event_type: Option<String>,
 
     /// Event key
@@ -32,29 +37,106 @@ struct RawSystemEvent {
     /// Event data
     data: Option<serde_json::Value>,
 
-    /// Timestamp
+    /// Timestamp (milliseconds, or seconds on some firmware)
     timestamp: Option<i64>,
 
     /// Unique ID
     id: Option<String>,
 }
 
+/// Top-level types that only wrap the real event in `data`
+const ENVELOPE_TYPES: &[&str] = &["event", "events", "message", "notification"];
+
+/// Fields of `data` that may hold the event type
+const NESTED_TYPE_FIELDS: &[&str] = &["type", "key", "eventType", "event"];
+
+impl RawSystemEvent {
+    /// Reads the first usable value for each field, in order of preference.
+    /// Returns `None` if the frame isn't a JSON object.
+    fn from_value(value: &serde_json::Value) -> Option<Self> {
+        let obj = value.as_object()?;
+        let first_str = |fields: &[&str]| {
+            fields.iter().find_map(|f| obj.get(*f).and_then(|v| v.as_str())).map(str::to_string)
+        };
+        Some(Self {
+            event_type: first_str(&["type", "eventType", "event"]),
+            key: first_str(&["key"]),
+            data: obj.get("data").cloned(),
+            timestamp: ["timestamp", "time", "ts"].iter().find_map(|f| obj.get(*f).and_then(|v| v.as_i64())),
+            id: first_str(&["id", "_id"]),
+        })
+    }
+
+    fn nested_str(&self, fields: &[&str]) -> Option<String> {
+        let data = self.data.as_ref()?;
+        fields
+            .iter()
+            .find_map(|field| data.get(*field).and_then(|v| v.as_str()))
+            .map(str::to_string)
+    }
+
+    fn resolved_type(&self) -> Option<String> {
+        let top = self.event_type.clone().or_else(|| self.key.clone());
+        match top {
+            Some(t) if !ENVELOPE_TYPES.contains(&t.as_str()) => Some(t),
+            top => self.nested_str(NESTED_TYPE_FIELDS).or(top),
+        }
+    }
+
+    fn resolved_timestamp(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        let ts = self.timestamp.or_else(|| {
+            let data = self.data.as_ref()?;
+            ["timestamp", "time", "ts"].iter().find_map(|field| data.get(*field).and_then(|v| v.as_i64()))
+        })?;
+        if ts > 1_000_000_000_000 {
+            chrono::DateTime::from_timestamp_millis(ts)
+        } else {
+            chrono::DateTime::from_timestamp(ts, 0)
+        }
+    }
+}
+
+/// Whether `event_type` matches a state-update pattern: an exact type, or one
+/// with a leading and/or trailing `*` wildcard. Matching ignores case.
+pub fn matches_type_pattern(pattern: &str, event_type: &str) -> bool {
+    let pattern = pattern.to_ascii_lowercase();
+    let event_type = event_type.to_ascii_lowercase();
+    match (pattern.strip_prefix('*'), pattern.strip_suffix('*')) {
+        (Some(rest), _) if rest.ends_with('*') => event_type.contains(&rest[..rest.len() - 1]),
+        (Some(suffix), _) => event_type.ends_with(suffix),
+        (None, Some(prefix)) => event_type.starts_with(prefix),
+        (None, None) => event_type == pattern,
+    }
+}
+
 /// Start the System WebSocket connection and stream events
 pub async fn connect_system_websocket(
     session: &UnifiSession,
     event_tx: mpsc::Sender<UnifiEvent>,
     seen_events: SeenEvents,
     state_tracker: StateTracker,
+    db: Option<Database>,
+    connections: &ConnectionTracker,
 ) -> Result<(), UnifiError> {
-    let ws_url = format!("wss://{}/api/ws/system", session.config.host);
+    let ws_url = session.config.ws_url(EventSource::System);
+
+    let missing_id = session.config.missing_id.for_source(EventSource::System);
+    let state_types = &session.config.system_state_event_types;
+    let store_unchanged = session.config.stores_unchanged_updates(EventSource::System);
 
     info!("Connecting to System WebSocket: {}", ws_url);
 
-    // Build request with authentication cookie
+    // Build request with User-Agent and authentication cookie
     let mut request = ws_url
         .into_client_request()
         .map_err(|e| UnifiError::WebSocket(e.to_string()))?;
 
+    request.headers_mut().insert(
+        "User-Agent",
+        HeaderValue::from_str(&session.config.user_agent)
+            .map_err(|e| UnifiError::WebSocket(e.to_string()))?,
+    );
+
     let cookie_header = session.get_cookie_header();
     if !cookie_header.is_empty() {
         request.headers_mut().insert(
@@ -72,37 +154,41 @@ pub async fn connect_system_websocket(
 
     let connector = Connector::NativeTls(tls_connector);
 
-    let (ws_stream, _) = connect_async_tls_with_config(request, None, false, Some(connector))
-        .await
-        .map_err(|e| UnifiError::WebSocket(e.to_string()))?;
+    let (ws_stream, _) = tokio::time::timeout(
+        session.config.connect_timeout,
+        connect_async_tls_with_config(request, None, false, Some(connector)),
+    )
+    .await
+    .map_err(|_| UnifiError::ConnectionFailed("WebSocket handshake timed out".to_string()))?
+    .map_err(|e| UnifiError::WebSocket(e.to_string()))?;
 
     let (mut write, mut read) = ws_stream.split();
 
     info!("System WebSocket connected");
 
     while let Some(msg) = read.next().await {
+        if let Ok(frame) = &msg {
+            connections.frame_received(EventSource::System, frame.len());
+        }
+
         match msg {
             Ok(Message::Text(text)) => {
                 trace!("System event: {}", text);
 
-                match serde_json::from_str::<RawSystemEvent>(&text) {
-                    Ok(raw) => {
-                        if let Some((event, is_state_update, entity_id, state_data)) = parse_system_event(raw, &text) {
-                            // For state update events, check if state actually changed
-                            if is_state_update {
-                                if !state_changed(&state_tracker, &entity_id, &state_data).await {
-                                    trace!("Skipping unchanged state for {}", entity_id);
-                                    continue;
-                                }
+                match serde_json::from_str::<serde_json::Value>(&text) {
+                    Ok(value) => {
+                        if let Some((event, is_state_update, entity_id, state_data)) = parse_system_event(value, missing_id, state_types) {
+                            // For state update events, check if state actually changed unless
+                            // unchanged updates are kept
+                            if is_state_update && !keep_state_update(&state_tracker, &entity_id, &state_data, store_unchanged).await {
+                                trace!("Skipping unchanged state for {}", entity_id);
+                                continue;
                             }
 
-                            // Deduplicate against seen events
-                            let mut seen = seen_events.lock().await;
-                            if !seen.insert(event.id.clone()) {
-                                trace!("Skipping duplicate event: {}", event.id);
+                            // Deduplicate against seen and already stored events
+                            if !is_new_event(&seen_events, db.as_ref(), &event).await {
                                 continue;
                             }
-                            drop(seen);
 
                             if event_tx.send(event).await.is_err() {
                                 warn!("Event channel closed, stopping System WebSocket");
@@ -136,35 +222,31 @@ pub async fn connect_system_websocket(
 }
 
 /// Returns (event, is_state_update, entity_id, state_data) for state tracking
-fn parse_system_event(raw: RawSystemEvent, original: &str) -> Option<(UnifiEvent, bool, String, serde_json::Value)> {
-    let event_type = raw
-        .event_type
-        .or(raw.key)
-        .unwrap_or_else(|| "unknown".to_string());
-
-    let timestamp = raw
-        .timestamp
-        .map(|ms| chrono::DateTime::from_timestamp_millis(ms))
-        .flatten()
-        .unwrap_or_else(chrono::Utc::now);
+fn parse_system_event(
+    raw_json: serde_json::Value,
+    missing_id: MissingIdStrategy,
+    state_types: &[String],
+) -> Option<(UnifiEvent, bool, String, serde_json::Value)> {
+    let raw = RawSystemEvent::from_value(&raw_json)?;
+    let event_type = raw.resolved_type().unwrap_or_else(|| "unknown".to_string());
+
+    let timestamp = raw.resolved_timestamp().unwrap_or_else(chrono::Utc::now);
 
     let summary = generate_summary(&event_type, &raw.data);
 
     // Check if this is a state update event (heartbeat-like)
-    let is_state_update = event_type == "DEVICE_STATE_CHANGED" || event_type.contains("state");
-
-    // Parse original for key field extraction
-    let raw_json: serde_json::Value = serde_json::from_str(original).unwrap_or(serde_json::Value::Null);
+    let is_state_update = state_types.iter().any(|pattern| matches_type_pattern(pattern, &event_type));
 
     // Generate content-based ID for deduplication
-    // If UniFi provides an id, use it as a key field; otherwise extract from payload
-    let key_fields = if let Some(unifi_id) = &raw.id {
-        vec![unifi_id.clone()]
-    } else {
-        extract_key_fields(&raw_json)
-    };
-    let key_refs: Vec<&str> = key_fields.iter().map(|s| s.as_str()).collect();
-    let id = generate_event_id(EventSource::System, &event_type, timestamp, &key_refs);
+    // If UniFi provides an id, use it as a key field; otherwise apply the missing-ID strategy
+    let id = unifi_event_id(
+        EventSource::System,
+        &event_type,
+        timestamp,
+        raw.id.as_deref(),
+        &raw_json,
+        missing_id,
+    )?;
 
     // Entity ID for state tracking
     let entity_id = raw.data
@@ -201,3 +283,88 @@ fn generate_summary(event_type: &str, data: &Option<serde_json::Value>) -> Strin
     }
     format!("System event: {}", event_type)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unifi::types::DEFAULT_SYSTEM_STATE_EVENT_TYPES;
+
+    /// Message shapes seen from the System WebSocket on different firmware
+    const DEVICE_STATE: &str =
+        r#"{"type":"DEVICE_STATE_CHANGED","timestamp":1700000000000,"data":{"deviceId":"dev1","state":"online"}}"#;
+    const ENVELOPED: &str =
+        r#"{"type":"event","data":{"type":"UPDATE_AVAILABLE","message":"Firmware 4.1 available","time":1700000000}}"#;
+    const EVENT_TYPE_FIELD: &str =
+        r#"{"eventType":"BACKUP_COMPLETED","_id":"b1","time":1700000000000,"data":{"msg":"Backup finished"}}"#;
+    const KEY_ONLY: &str = r#"{"key":"admin.login","ts":1700000000,"data":{"id":"u1"}}"#;
+
+    fn parse(text: &str) -> (UnifiEvent, bool, String) {
+        let value: serde_json::Value = serde_json::from_str(text).unwrap();
+        let defaults: Vec<String> = DEFAULT_SYSTEM_STATE_EVENT_TYPES.iter().map(|s| s.to_string()).collect();
+        let (event, is_state, entity_id, _) =
+            parse_system_event(value, MissingIdStrategy::ContentHash, &defaults).unwrap();
+        (event, is_state, entity_id)
+    }
+
+    #[test]
+    fn test_system_event_shapes() {
+        let expected_time = chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+
+        let (event, is_state, entity_id) = parse(DEVICE_STATE);
+        assert_eq!(event.event_type, "DEVICE_STATE_CHANGED");
+        assert_eq!(event.timestamp, expected_time);
+        assert!(is_state);
+        assert_eq!(entity_id, "system:dev1");
+
+        let (event, is_state, _) = parse(ENVELOPED);
+        assert_eq!(event.event_type, "UPDATE_AVAILABLE");
+        assert_eq!(event.summary, "Firmware 4.1 available");
+        assert_eq!(event.timestamp, expected_time);
+        assert!(!is_state);
+
+        let (event, is_state, _) = parse(EVENT_TYPE_FIELD);
+        assert_eq!(event.event_type, "BACKUP_COMPLETED");
+        assert_eq!(event.summary, "Backup finished");
+        assert_eq!(event.timestamp, expected_time);
+        assert!(!is_state);
+        // `_id` is the UniFi ID, so the strategy for missing IDs doesn't apply
+        let value: serde_json::Value = serde_json::from_str(EVENT_TYPE_FIELD).unwrap();
+        assert!(parse_system_event(value, MissingIdStrategy::Drop, &[]).is_some());
+
+        let (event, _, entity_id) = parse(KEY_ONLY);
+        assert_eq!(event.event_type, "admin.login");
+        assert_eq!(event.timestamp, expected_time);
+        assert_eq!(entity_id, "system:u1");
+    }
+
+    #[test]
+    fn test_system_event_with_duplicate_fields() {
+        // Both `id` and `_id`, and an `event` object next to the `type` string
+        let text = r#"{"type":"ALARM_TRIGGERED","event":{"name":"x"},"id":"a1","_id":"a2","ts":1700000000,"time":"later"}"#;
+        let value: serde_json::Value = serde_json::from_str(text).unwrap();
+        let raw = RawSystemEvent::from_value(&value).unwrap();
+        assert_eq!(raw.id.as_deref(), Some("a1"));
+
+        let (event, _, _) = parse(text);
+        assert_eq!(event.event_type, "ALARM_TRIGGERED");
+        assert_eq!(event.timestamp, chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap());
+
+        // A non-string `event` is skipped rather than failing the frame
+        let (event, _, _) = parse(r#"{"event":{"name":"x"},"key":"admin.login"}"#);
+        assert_eq!(event.event_type, "admin.login");
+    }
+
+    #[test]
+    fn test_state_type_patterns() {
+        assert!(matches_type_pattern("DEVICE_STATE_CHANGED", "device_state_changed"));
+        assert!(matches_type_pattern("*_STATE_CHANGED", "NETWORK_STATE_CHANGED"));
+        assert!(matches_type_pattern("device.*", "device.state"));
+        assert!(matches_type_pattern("*state*", "UPSTATE_SYNC"));
+        assert!(!matches_type_pattern("*_STATE_CHANGED", "STATEMENT_READY"));
+        assert!(!matches_type_pattern("admin.login", "admin.logout"));
+
+        // Substring matches no longer make an event a state update by default
+        let (_, is_state, _) = parse(r#"{"type":"statement_ready","data":{}}"#);
+        assert!(!is_state);
+    }
+}
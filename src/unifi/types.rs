@@ -2,6 +2,8 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::hash::{Hash, Hasher};
 use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::time::Duration;
 
 /// Source of a UniFi event
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -22,6 +24,39 @@ impl std::fmt::Display for EventSource {
     }
 }
 
+impl EventSource {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "protect" => Some(EventSource::Protect),
+            "network" => Some(EventSource::Network),
+            "system" => Some(EventSource::System),
+            _ => None,
+        }
+    }
+
+    /// Every source
+    pub const ALL: [EventSource; 3] = [EventSource::Protect, EventSource::Network, EventSource::System];
+
+    /// Parse a comma-separated list of sources, where `all` (or `true`)
+    /// selects every source and `none` (or `false`) none of them
+    pub fn parse_list(spec: &str) -> Option<Vec<Self>> {
+        match spec.trim().to_lowercase().as_str() {
+            "all" | "true" | "1" => return Some(Self::ALL.to_vec()),
+            "none" | "false" | "0" | "" => return Some(Vec::new()),
+            _ => {}
+        }
+        let mut sources = Vec::new();
+        for name in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let source = Self::from_str(&name.to_lowercase())?;
+            if !sources.contains(&source) {
+                sources.push(source);
+            }
+        }
+        Some(sources)
+    }
+}
+
 /// Unified event from any UniFi source
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UnifiEvent {
@@ -47,8 +82,8 @@ pub struct UnifiEvent {
     pub raw: serde_json::Value,
 }
 
-/// Event severity levels
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// Event severity levels, ordered from least to most severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Severity {
     Info,
@@ -57,6 +92,28 @@ pub enum Severity {
     Critical,
 }
 
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+            Severity::Critical => "critical",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "info" => Some(Severity::Info),
+            "warning" => Some(Severity::Warning),
+            "error" => Some(Severity::Error),
+            "critical" => Some(Severity::Critical),
+            _ => None,
+        }
+    }
+}
+
 /// Configuration for connecting to UniFi
 #[derive(Debug, Clone)]
 pub struct UnifiConfig {
@@ -71,8 +128,211 @@ pub struct UnifiConfig {
 
     /// Whether to verify TLS certificates (default: false for self-signed)
     pub verify_ssl: bool,
+
+    /// User-Agent sent with HTTP requests and WebSocket handshakes
+    pub user_agent: String,
+
+    /// Timeout for establishing HTTP and WebSocket connections
+    pub connect_timeout: Duration,
+
+    /// Timeout for a complete HTTP request
+    pub request_timeout: Duration,
+
+    /// Retries for idempotent GETs on transient failures (timeouts, connection errors, 5xx)
+    pub http_retries: u32,
+
+    /// Endpoints tried in order for historical system events
+    pub system_event_endpoints: Vec<String>,
+
+    /// Skip the REST backfill of historical events on connect and rely solely
+    /// on the live WebSockets (events between runs may be missed)
+    pub skip_historical_backfill: bool,
+
+    /// Scope of the in-memory deduplication set
+    pub dedup_scope: DedupScope,
+
+    /// Collapse events with the same source, type and entity seen within this
+    /// window of each other, even when their IDs differ (off by default)
+    pub dedup_window: Option<Duration>,
+
+    /// How events without a UniFi-provided ID are identified
+    pub missing_id: MissingIdPolicy,
+
+    /// WebSocket paths (after `wss://{host}`), which differ across firmware and proxies
+    pub protect_ws_path: String,
+    pub network_ws_path: String,
+    pub system_ws_path: String,
+
+    /// Severities for REST-fetched events the parser can't assign one to
+    pub rest_severity_defaults: SeverityDefaults,
+
+    /// System event types treated as state updates, which are only kept when
+    /// the device's state changed (see `system::matches_type_pattern`)
+    pub system_state_event_types: Vec<String>,
+
+    /// Sources whose state updates are kept even when the entity's state is
+    /// unchanged (by default repeats are dropped before storage)
+    pub store_unchanged_updates: Vec<EventSource>,
+
+    /// Replaces `https://{host}` for HTTP requests (e.g. a mock server in tests)
+    pub http_base_url: Option<String>,
+}
+
+/// How the in-memory deduplication set is scoped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DedupScope {
+    /// One set shared by all sources
+    #[default]
+    Global,
+    /// A separate set per source, so sources never suppress each other
+    PerSource,
+}
+
+impl DedupScope {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "global" => Some(DedupScope::Global),
+            "source" | "per-source" => Some(DedupScope::PerSource),
+            _ => None,
+        }
+    }
+}
+
+/// How to identify an event that arrives without a UniFi-provided ID
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissingIdStrategy {
+    /// Hash the event content, so identical events collapse into one
+    #[default]
+    ContentHash,
+    /// Give every such event a random ID, so none are deduplicated
+    Unique,
+    /// Discard the event
+    Drop,
+}
+
+impl MissingIdStrategy {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "hash" | "content-hash" => Some(MissingIdStrategy::ContentHash),
+            "unique" => Some(MissingIdStrategy::Unique),
+            "drop" => Some(MissingIdStrategy::Drop),
+            _ => None,
+        }
+    }
+}
+
+/// [`MissingIdStrategy`] to use, globally and per source
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MissingIdPolicy {
+    pub default: MissingIdStrategy,
+    pub per_source: HashMap<EventSource, MissingIdStrategy>,
+}
+
+impl MissingIdPolicy {
+    /// Parse comma-separated entries, each either a strategy (the default for
+    /// all sources) or `source=strategy` (e.g. `unique,network=drop`)
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut policy = Self::default();
+        for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            match entry.split_once('=') {
+                Some((source, strategy)) => {
+                    let source = EventSource::from_str(source.trim())?;
+                    let strategy = MissingIdStrategy::from_str(strategy.trim())?;
+                    policy.per_source.insert(source, strategy);
+                }
+                None => policy.default = MissingIdStrategy::from_str(entry)?,
+            }
+        }
+        Some(policy)
+    }
+
+    pub fn for_source(&self, source: EventSource) -> MissingIdStrategy {
+        self.per_source.get(&source).copied().unwrap_or(self.default)
+    }
+
+    /// Strategy for events fetched by the historical backfill. `Unique` only
+    /// applies to live events: the backfill refetches the same history on
+    /// every start, so random IDs would store it again each time.
+    pub fn for_backfill(&self, source: EventSource) -> MissingIdStrategy {
+        match self.for_source(source) {
+            MissingIdStrategy::Unique => MissingIdStrategy::ContentHash,
+            strategy => strategy,
+        }
+    }
+}
+
+/// Default endpoints for historical system events (differ across firmware versions)
+pub const DEFAULT_SYSTEM_EVENT_ENDPOINTS: &[&str] = &[
+    "/api/system/logs",
+    "/proxy/network/api/s/default/stat/alarm",
+];
+
+/// Default severities for events whose parser doesn't assign one, by source
+/// and optionally by event type prefix
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SeverityDefaults {
+    /// (source, event type prefix, severity); an empty prefix matches any type
+    rules: Vec<(EventSource, String, Severity)>,
+}
+
+impl SeverityDefaults {
+    /// Parse comma-separated `source=severity` or `source:prefix=severity`
+    /// entries (e.g. `system=info,network:EVT_AP_=warning`)
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut rules = Vec::new();
+        for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (selector, severity) = entry.split_once('=')?;
+            let (source, prefix) = selector.split_once(':').unwrap_or((selector, ""));
+            rules.push((
+                EventSource::from_str(source.trim())?,
+                prefix.trim().to_string(),
+                Severity::from_str(severity.trim())?,
+            ));
+        }
+        Some(Self { rules })
+    }
+
+    /// Default for an event type; the longest matching prefix wins
+    pub fn for_event(&self, source: EventSource, event_type: &str) -> Option<Severity> {
+        self.rules
+            .iter()
+            .filter(|(s, prefix, _)| *s == source && event_type.starts_with(prefix.as_str()))
+            .max_by_key(|(_, prefix, _)| prefix.len())
+            .map(|(_, _, severity)| *severity)
+    }
+}
+
+/// Default state-update patterns for System events
+pub const DEFAULT_SYSTEM_STATE_EVENT_TYPES: &[&str] = &["*_STATE_CHANGED", "*_STATE_UPDATE", "*:state", "*.state"];
+
+/// Default WebSocket paths
+pub const DEFAULT_PROTECT_WS_PATH: &str = "/proxy/protect/ws/updates";
+pub const DEFAULT_NETWORK_WS_PATH: &str = "/proxy/network/wss/s/default/events";
+pub const DEFAULT_SYSTEM_WS_PATH: &str = "/api/ws/system";
+
+/// Check a WebSocket path override: an absolute path with no scheme, host,
+/// query or fragment (Protect's `lastUpdateId` query is appended separately)
+pub fn validate_ws_path(path: &str) -> Result<(), String> {
+    if !path.starts_with('/') {
+        return Err("must start with '/'".to_string());
+    }
+    if path.starts_with("//") {
+        return Err("must not include a host".to_string());
+    }
+    if let Some(c) = path.chars().find(|c| matches!(c, '?' | '#') || c.is_whitespace() || c.is_control()) {
+        return Err(format!("must not contain {:?}", c));
+    }
+    Ok(())
 }
 
+/// Upper bound on `http_retries`
+pub const MAX_HTTP_RETRIES: u32 = 10;
+
+/// Default User-Agent: `unifi-monitor/<version>`
+pub const DEFAULT_USER_AGENT: &str = concat!("unifi-monitor/", env!("CARGO_PKG_VERSION"));
+
 impl UnifiConfig {
     pub fn new(host: impl Into<String>, username: impl Into<String>, password: impl Into<String>) -> Self {
         Self {
@@ -80,12 +340,143 @@ impl UnifiConfig {
             username: username.into(),
             password: password.into(),
             verify_ssl: false, // UniFi uses self-signed certs by default
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            connect_timeout: Duration::from_secs(10),
+            request_timeout: Duration::from_secs(30),
+            http_retries: 2,
+            system_event_endpoints: DEFAULT_SYSTEM_EVENT_ENDPOINTS
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            skip_historical_backfill: false,
+            dedup_scope: DedupScope::Global,
+            dedup_window: None,
+            missing_id: MissingIdPolicy::default(),
+            protect_ws_path: DEFAULT_PROTECT_WS_PATH.to_string(),
+            network_ws_path: DEFAULT_NETWORK_WS_PATH.to_string(),
+            system_ws_path: DEFAULT_SYSTEM_WS_PATH.to_string(),
+            rest_severity_defaults: SeverityDefaults::default(),
+            system_state_event_types: DEFAULT_SYSTEM_STATE_EVENT_TYPES
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            store_unchanged_updates: Vec::new(),
+            http_base_url: None,
+        }
+    }
+
+    /// Override the User-Agent
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Override the HTTP request timeout
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
+    /// Override the connect timeout (HTTP and WebSocket)
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Override the number of retries for idempotent GETs (at most
+    /// [`MAX_HTTP_RETRIES`])
+    pub fn with_http_retries(mut self, retries: u32) -> Self {
+        self.http_retries = retries.min(MAX_HTTP_RETRIES);
+        self
+    }
+
+    /// Override the endpoints tried for historical system events
+    pub fn with_system_event_endpoints(mut self, endpoints: Vec<String>) -> Self {
+        self.system_event_endpoints = endpoints;
+        self
+    }
+
+    /// Skip the historical REST backfill on connect
+    pub fn with_skip_historical_backfill(mut self, skip: bool) -> Self {
+        self.skip_historical_backfill = skip;
+        self
+    }
+
+    /// Override the deduplication scope
+    pub fn with_dedup_scope(mut self, scope: DedupScope) -> Self {
+        self.dedup_scope = scope;
+        self
+    }
+
+    /// Enable time-windowed dedup by (source, event type, entity)
+    pub fn with_dedup_window(mut self, window: Duration) -> Self {
+        self.dedup_window = Some(window);
+        self
+    }
+
+    /// Override how events without a UniFi ID are identified
+    pub fn with_missing_id_policy(mut self, policy: MissingIdPolicy) -> Self {
+        self.missing_id = policy;
+        self
+    }
+
+    /// Override the WebSocket path for a source (see [`validate_ws_path`])
+    pub fn with_ws_path(mut self, source: EventSource, path: impl Into<String>) -> Self {
+        let path = path.into();
+        match source {
+            EventSource::Protect => self.protect_ws_path = path,
+            EventSource::Network => self.network_ws_path = path,
+            EventSource::System => self.system_ws_path = path,
         }
+        self
+    }
+
+    /// Set default severities for REST-fetched events
+    pub fn with_rest_severity_defaults(mut self, defaults: SeverityDefaults) -> Self {
+        self.rest_severity_defaults = defaults;
+        self
+    }
+
+    /// Override which System event types are treated as state updates
+    pub fn with_system_state_event_types(mut self, patterns: Vec<String>) -> Self {
+        self.system_state_event_types = patterns;
+        self
+    }
+
+    /// Keep unchanged state updates from these sources
+    pub fn with_store_unchanged_updates(mut self, sources: Vec<EventSource>) -> Self {
+        self.store_unchanged_updates = sources;
+        self
+    }
+
+    /// Whether unchanged state updates from `source` are kept
+    pub fn stores_unchanged_updates(&self, source: EventSource) -> bool {
+        self.store_unchanged_updates.contains(&source)
+    }
+
+    /// Send HTTP requests to `url` (scheme and authority, no trailing slash)
+    /// instead of `https://{host}`; WebSockets still use `host`
+    pub fn with_base_url(mut self, url: impl Into<String>) -> Self {
+        self.http_base_url = Some(url.into().trim_end_matches('/').to_string());
+        self
     }
 
     /// Base URL for HTTP requests
     pub fn base_url(&self) -> String {
-        format!("https://{}", self.host)
+        match &self.http_base_url {
+            Some(url) => url.clone(),
+            None => format!("https://{}", self.host),
+        }
+    }
+
+    /// WebSocket URL for a source, without any query
+    pub fn ws_url(&self, source: EventSource) -> String {
+        let path = match source {
+            EventSource::Protect => &self.protect_ws_path,
+            EventSource::Network => &self.network_ws_path,
+            EventSource::System => &self.system_ws_path,
+        };
+        format!("wss://{}{}", self.host, path)
     }
 }
 
@@ -119,6 +510,33 @@ pub fn generate_event_id(
     format!("{}-{:016x}", source, hasher.finish())
 }
 
+/// ID for an event from a UniFi source
+///
+/// When UniFi provides an ID it is hashed with the event's type and time.
+/// Otherwise `strategy` decides: hash the payload's key fields, add a random
+/// nonce so the event is never deduplicated, or return `None` to drop it.
+pub fn unifi_event_id(
+    source: EventSource,
+    event_type: &str,
+    timestamp: DateTime<Utc>,
+    unifi_id: Option<&str>,
+    payload: &serde_json::Value,
+    strategy: MissingIdStrategy,
+) -> Option<String> {
+    let key_fields = match (unifi_id, strategy) {
+        (Some(unifi_id), _) => vec![unifi_id.to_string()],
+        (None, MissingIdStrategy::ContentHash) => extract_key_fields(payload),
+        (None, MissingIdStrategy::Unique) => {
+            let mut fields = extract_key_fields(payload);
+            fields.push(uuid::Uuid::new_v4().to_string());
+            fields
+        }
+        (None, MissingIdStrategy::Drop) => return None,
+    };
+    let key_refs: Vec<&str> = key_fields.iter().map(|s| s.as_str()).collect();
+    Some(generate_event_id(source, event_type, timestamp, &key_refs))
+}
+
 /// Extract key fields from a JSON payload for ID generation.
 /// Looks for common identifier fields in order of preference.
 pub fn extract_key_fields(payload: &serde_json::Value) -> Vec<String> {
@@ -134,9 +552,13 @@ pub fn extract_key_fields(payload: &serde_json::Value) -> Vec<String> {
         }
     }
 
-    // For events with nested data array, check first element
+    // For events with nested data (an array, or sometimes a single object), check the first element
     if fields.is_empty() {
-        if let Some(data) = payload.get("data").and_then(|d| d.as_array()).and_then(|a| a.first()) {
+        let data = payload
+            .get("data")
+            .and_then(|d| d.as_array().map_or(Some(d), |a| a.first()))
+            .filter(|d| d.is_object());
+        if let Some(data) = data {
             for key in id_keys {
                 if let Some(val) = data.get(key).and_then(|v| v.as_str()) {
                     fields.push(val.to_string());
@@ -23,6 +23,10 @@ use super::AppError;
 // ============================================================================
 
 /// Challenge with timestamp for expiry
+///
+/// Challenges only live in memory, so they use the monotonic clock and are
+/// unaffected by wall-clock steps. Sessions and invite tokens are persisted and
+/// use the wall clock, guarded against backward steps in the database layer.
 #[derive(Clone)]
 pub struct TimestampedChallenge<T> {
     pub challenge: T,
@@ -330,7 +334,9 @@ pub async fn register_finish(
 
     // Store passkey
     let name = req.name.as_deref();
-    state.db.store_passkey(&cred_id, &cred_bytes, name)?;
+    if !state.db.store_passkey(&cred_id, &cred_bytes, name)? {
+        return Err(AppError::Conflict("This passkey is already registered".to_string()));
+    }
 
     info!(cred_id = %cred_id, name = ?name, "Passkey registered");
 
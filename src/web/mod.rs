@@ -9,12 +9,14 @@ use axum::{
         sse::{Event, KeepAlive, Sse},
         IntoResponse,
     },
-    routing::{delete, get, post},
+    routing::{delete, get, post, put},
     Json, Router,
 };
 use axum_extra::extract::CookieJar;
 use futures_util::stream::Stream;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::{convert::Infallible, sync::Arc};
 use tokio::sync::broadcast;
 use tokio_stream::wrappers::BroadcastStream;
@@ -26,7 +28,12 @@ use tracing::{info, warn};
 use url::Url;
 use webauthn_rs::Webauthn;
 
-use crate::db::{Classification, Database};
+use crate::db::settings::{self, SettingKind, SettingScope};
+use crate::db::{BulkEventAction, Classification, ClassificationMode, Database, EventFilter, EventTypeSummary, PayloadFilter, Rule, StoredEvent};
+use crate::notify::{Notifier, RecipientStatus};
+use crate::processor::EventProcessor;
+use crate::unifi::{ConnectionTracker, HistoricalFetchResult, SourceConnection};
+use crate::unifi::types::{extract_key_fields, generate_event_id, EventSource, Severity, UnifiEvent};
 use auth::{AuthState, validate_session_from_cookies};
 
 /// Event sent via SSE to frontend (no payload - fetch separately)
@@ -43,6 +50,39 @@ pub struct SseEvent {
     pub created_at: i64,
 }
 
+impl From<&StoredEvent> for SseEvent {
+    fn from(event: &StoredEvent) -> Self {
+        Self {
+            id: event.id.clone(),
+            source: event.source.to_string(),
+            event_type: event.event_type.clone(),
+            severity: event.severity.map(|s| s.as_str().to_string()),
+            summary: event.summary.clone(),
+            timestamp: event.timestamp,
+            classification: event.classification.as_str().to_string(),
+            notified: event.notified,
+            created_at: event.created_at,
+        }
+    }
+}
+
+impl SseEvent {
+    /// Whether the event is at least `min` severe (events without a severity never are)
+    fn is_at_least(&self, min: Severity) -> bool {
+        self.severity
+            .as_deref()
+            .and_then(Severity::from_str)
+            .is_some_and(|severity| severity >= min)
+    }
+
+    /// Hide the payload's personal data where it appears in the summary
+    /// (privacy mode, see [`crate::privacy::redact_summary`])
+    pub fn redact(mut self, payload: &serde_json::Value) -> Self {
+        self.summary = crate::privacy::redact_summary(&self.summary, payload);
+        self
+    }
+}
+
 /// Shared application state (basic, for backwards compat)
 #[derive(Clone)]
 pub struct AppState {
@@ -53,8 +93,10 @@ pub struct AppState {
 /// Telegram configuration
 #[derive(Clone)]
 pub struct TelegramConfig {
-    pub token: String,
-    pub chat_id: String,
+    /// Default recipients
+    pub chat_ids: Vec<String>,
+    /// Startup validation of each recipient, filled in by the notification sender
+    pub recipients: Arc<std::sync::RwLock<Vec<RecipientStatus>>>,
 }
 
 /// Full application state with auth
@@ -64,8 +106,26 @@ pub struct FullAppState {
     pub sse_tx: broadcast::Sender<SseEvent>,
     pub auth: AuthState,
     pub telegram: Option<TelegramConfig>,
+    /// Notification backends, for sending test messages
+    pub notifiers: Arc<Vec<Box<dyn Notifier>>>,
+    /// Processor for events injected via the API
+    pub processor: Arc<EventProcessor>,
+    /// Historical backfill result, set once the UniFi client has connected
+    pub backfill: BackfillStatus,
+    /// Per-source WebSocket connection state
+    pub connections: ConnectionTracker,
+    /// When the process started (unix seconds)
+    pub started_at: i64,
+    /// Open `/api/events/stream` connections and their cap
+    pub sse_connections: SseConnections,
+    /// Redact personal data from summaries in event listings and the live
+    /// stream (payloads are served unchanged)
+    pub privacy_mode: bool,
 }
 
+/// Shared slot for the startup backfill result
+pub type BackfillStatus = Arc<std::sync::RwLock<Option<HistoricalFetchResult>>>;
+
 /// Create the web server router (legacy - no auth)
 pub fn create_router(state: AppState, static_dir: Option<&str>) -> Router {
     let cors = CorsLayer::new()
@@ -175,6 +235,18 @@ pub fn create_router_with_auth(state: FullAppState, static_dir: Option<&str>) ->
         config: Arc::new(rate_limit_config),
     };
 
+    // Separate rate limiter for event injection (same limits, independent budget)
+    let ingest_rate_limit_config = GovernorConfigBuilder::default()
+        .key_extractor(PeerIpKeyExtractor)
+        .per_second(1)
+        .burst_size(10)
+        .finish()
+        .unwrap();
+
+    let ingest_rate_limiter = GovernorLayer {
+        config: Arc::new(ingest_rate_limit_config),
+    };
+
     let auth_state = Arc::new(state.auth.clone());
     let full_state = Arc::new(state);
 
@@ -197,29 +269,56 @@ pub fn create_router_with_auth(state: FullAppState, static_dir: Option<&str>) ->
         // Events API
         .route("/api/events", get(list_events))
         .route("/api/events/count", get(count_events))
+        .route("/api/events/actionable", get(list_actionable_events))
         .route("/api/events/types", get(list_event_types))
+        .route("/api/events/types/{event_type}/events", get(list_events_for_type))
         .route("/api/events/stream", get(event_stream))
+        .route("/api/events/bulk", post(bulk_update_events))
+        .route("/api/events/{id}", get(get_event))
         .route("/api/events/{id}/payload", get(get_event_payload))
+        .route("/api/events/{id}/pin", post(pin_event).delete(unpin_event))
+        .route("/api/entities", get(list_entity_states))
         // Rules API
         .route("/api/rules", get(list_rules))
         .route("/api/rules", post(set_rule))
+        .route("/api/rules", delete(delete_rule_by_body))
         .route("/api/rules/{event_type}", delete(delete_rule))
+        .route("/api/rules/test", get(test_rule))
+        .route("/api/rules/merge", post(merge_rules))
         // Stats
         .route("/api/stats", get(get_stats))
+        .route("/api/system/info", get(get_system_info))
+        .route("/api/connection/status", get(get_connection_status))
         // Notifications API
         .route("/api/notifications/history", get(get_notification_history))
+        .route("/api/notifications/backends", get(list_notification_backends))
         .route("/api/notifications/test", post(send_test_notification))
+        .route("/api/notifications/test/{backend_id}", post(send_backend_test_notification))
         .route("/api/notifications/status", get(get_notification_status))
+        .route("/api/notifications/mute", post(mute_notifications))
+        .route("/api/notifications/unmute", post(unmute_notifications))
+        .route("/api/settings", get(get_settings))
+        .route("/api/settings", put(update_settings))
+        .route("/api/config/export", get(export_config))
+        .route("/api/config/import", post(import_config))
+        .with_state(full_state.clone());
+
+    // Event injection (requires valid session, rate limited)
+    let ingest_routes = Router::new()
+        .route("/api/events", post(create_event))
+        .layer(ingest_rate_limiter)
         .with_state(full_state.clone());
 
     // Public routes (no auth required)
     let public_routes = Router::new()
         .route("/api/health", get(health))
+        .route("/api/health/db", get(health_db))
         .with_state(full_state);
 
     let api_router = Router::new()
         .merge(auth_routes)
         .merge(protected_routes)
+        .merge(ingest_routes)
         .merge(public_routes)
         .layer(cors);
 
@@ -276,6 +375,33 @@ async fn health(
     Json(serde_json::json!({ "status": "ok" }))
 }
 
+/// Readiness check: unlike `/api/health`, confirms the database still accepts
+/// writes, returning 503 when it doesn't (e.g. the disk is full or the mount
+/// went read-only)
+async fn health_db(
+    State(state): State<Arc<FullAppState>>,
+) -> axum::response::Response {
+    // The write lock may be held by a long cleanup or VACUUM, so don't block
+    // the runtime waiting for it
+    let db = state.db.clone();
+    let result = tokio::task::spawn_blocking(move || db.check_writable())
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|result| result.map_err(|e| e.to_string()));
+    match result {
+        Ok(()) => Json(serde_json::json!({ "status": "ok" })).into_response(),
+        Err(e) => {
+            // Details stay in the log; this endpoint needs no auth
+            tracing::warn!(error = %e, "Database health check failed");
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(serde_json::json!({ "status": "error", "error": "database is not writable" })),
+            )
+                .into_response()
+        }
+    }
+}
+
 async fn health_legacy() -> impl IntoResponse {
     Json(serde_json::json!({ "status": "ok" }))
 }
@@ -284,26 +410,205 @@ async fn health_legacy() -> impl IntoResponse {
 // SSE Event Stream
 // ============================================================================
 
+#[derive(Debug, Deserialize)]
+pub struct EventStreamQuery {
+    /// Only send events at least this severe ("info", "warning", "error" or "critical")
+    min_severity: Option<String>,
+}
+
 async fn event_stream(
     State(state): State<Arc<FullAppState>>,
     jar: CookieJar,
+    Query(query): Query<EventStreamQuery>,
 ) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
     require_auth(&jar, &state.db)?;
 
-    let rx = state.sse_tx.subscribe();
-    let stream = BroadcastStream::new(rx).filter_map(|result| {
-        match result {
-            Ok(sse_event) => {
-                let json = serde_json::to_string(&sse_event).unwrap_or_default();
-                Some(Ok(Event::default().event("event").data(json)))
-            }
-            Err(_) => None, // Skip lagged messages
-        }
+    let min_severity = query
+        .min_severity
+        .as_deref()
+        .map(|s| Severity::from_str(s).ok_or_else(|| AppError::BadRequest("Invalid min_severity".to_string())))
+        .transpose()?;
+
+    let slot = state.sse_connections.try_acquire().ok_or_else(|| {
+        warn!(max = state.sse_connections.max(), "Rejecting SSE connection, too many open");
+        AppError::ServiceUnavailable("Too many open event streams".to_string())
+    })?;
+
+    let subscriber = SseSubscriber::new(state.sse_tx.subscribe(), state.db.clone(), min_severity, state.privacy_mode, slot);
+    let stream = futures_util::stream::unfold(subscriber, |mut subscriber| async move {
+        let event = subscriber.next().await?;
+        Some((Ok(event), subscriber))
     });
 
     Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
 }
 
+/// Default cap on concurrent `/api/events/stream` connections
+pub const DEFAULT_MAX_SSE_CONNECTIONS: usize = 100;
+
+/// Counts open SSE connections against a cap
+#[derive(Debug, Clone)]
+pub struct SseConnections {
+    active: Arc<AtomicUsize>,
+    max: usize,
+}
+
+impl Default for SseConnections {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_SSE_CONNECTIONS)
+    }
+}
+
+impl SseConnections {
+    pub fn new(max: usize) -> Self {
+        Self {
+            active: Arc::new(AtomicUsize::new(0)),
+            max,
+        }
+    }
+
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    /// Connections currently open
+    pub fn active(&self) -> usize {
+        self.active.load(Ordering::SeqCst)
+    }
+
+    /// Take a slot for a new connection, or `None` if all are in use
+    fn try_acquire(&self) -> Option<SseSlot> {
+        self.active
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| (n < self.max).then_some(n + 1))
+            .ok()
+            .map(|_| SseSlot {
+                active: self.active.clone(),
+            })
+    }
+}
+
+/// A connection's slot, released when the stream is dropped (including when
+/// the client disconnects abruptly)
+struct SseSlot {
+    active: Arc<AtomicUsize>,
+}
+
+impl Drop for SseSlot {
+    fn drop(&mut self) {
+        self.active.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Most events replayed from the database after a subscriber falls behind
+const SSE_CATCH_UP_LIMIT: usize = 1000;
+
+/// IDs remembered per subscriber so replayed events aren't sent again live
+const SSE_RECENT_IDS: usize = 2048;
+
+/// One SSE client's view of the broadcast channel
+///
+/// If the client falls behind and the channel drops events for it, it gets a
+/// `resync` frame followed by the missed events replayed from the database,
+/// so a slow client sees every stored event rather than silently losing some.
+struct SseSubscriber {
+    rx: broadcast::Receiver<SseEvent>,
+    db: Database,
+    min_severity: Option<Severity>,
+    /// Redact summaries of replayed events (live ones are redacted when sent)
+    privacy_mode: bool,
+    /// Replay cursor: `created_at` of the newest event delivered
+    since: i64,
+    /// Replayed events not yet sent
+    pending: VecDeque<SseEvent>,
+    recent: HashSet<String>,
+    recent_order: VecDeque<String>,
+    /// Held for as long as the stream lives
+    _slot: SseSlot,
+}
+
+impl SseSubscriber {
+    fn new(
+        rx: broadcast::Receiver<SseEvent>,
+        db: Database,
+        min_severity: Option<Severity>,
+        privacy_mode: bool,
+        slot: SseSlot,
+    ) -> Self {
+        Self {
+            rx,
+            db,
+            min_severity,
+            privacy_mode,
+            _slot: slot,
+            since: chrono::Utc::now().timestamp(),
+            pending: VecDeque::new(),
+            recent: HashSet::new(),
+            recent_order: VecDeque::new(),
+        }
+    }
+
+    /// Next frame for the client; `None` once the channel closes
+    async fn next(&mut self) -> Option<Event> {
+        loop {
+            let sse_event = match self.pending.pop_front() {
+                Some(sse_event) => sse_event,
+                None => match self.rx.recv().await {
+                    Ok(sse_event) => sse_event,
+                    Err(broadcast::error::RecvError::Lagged(missed)) => return Some(self.resync(missed)),
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                },
+            };
+
+            if !self.remember(&sse_event) {
+                continue;
+            }
+            if self.min_severity.is_some_and(|min| !sse_event.is_at_least(min)) {
+                continue;
+            }
+            let json = serde_json::to_string(&sse_event).unwrap_or_default();
+            return Some(Event::default().event("event").data(json));
+        }
+    }
+
+    /// Queue missed events from the database and tell the client it fell behind
+    fn resync(&mut self, missed: u64) -> Event {
+        warn!(missed, "SSE subscriber fell behind, replaying from the database");
+        let replayed = match self.db.events_created_since(self.since, SSE_CATCH_UP_LIMIT) {
+            Ok(events) => events,
+            Err(e) => {
+                warn!(error = %e, "Failed to replay events for SSE subscriber");
+                Vec::new()
+            }
+        };
+
+        // Past the limit the client can't be caught up and should reload
+        let complete = replayed.len() < SSE_CATCH_UP_LIMIT;
+        let privacy_mode = self.privacy_mode;
+        self.pending.extend(replayed.iter().map(|e| {
+            let sse_event = SseEvent::from(e);
+            if privacy_mode { sse_event.redact(&e.payload) } else { sse_event }
+        }));
+
+        let data = serde_json::json!({ "missed": missed, "replayed": replayed.len(), "complete": complete });
+        Event::default().event("resync").data(data.to_string())
+    }
+
+    /// Record an event as delivered; returns false if it already was
+    fn remember(&mut self, sse_event: &SseEvent) -> bool {
+        if !self.recent.insert(sse_event.id.clone()) {
+            return false;
+        }
+        self.recent_order.push_back(sse_event.id.clone());
+        if self.recent_order.len() > SSE_RECENT_IDS {
+            if let Some(oldest) = self.recent_order.pop_front() {
+                self.recent.remove(&oldest);
+            }
+        }
+        self.since = self.since.max(sse_event.created_at);
+        true
+    }
+}
+
 async fn event_stream_legacy(
     State(state): State<Arc<AppState>>,
 ) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
@@ -325,7 +630,7 @@ async fn event_stream_legacy(
 // Events API
 // ============================================================================
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct ListEventsQuery {
     /// Filter by classifications (comma-separated: "notify,ignored")
     classification: Option<String>,
@@ -333,12 +638,22 @@ pub struct ListEventsQuery {
     event_type: Option<String>,
     /// Search query (searches event_type, summary, source, payload)
     search: Option<String>,
+    /// Search the summary only
+    summary_search: Option<String>,
+    /// Payload condition (`path op value`, e.g. "data.score > 80")
+    payload_filter: Option<String>,
     /// Number of events to return (default 200)
     limit: Option<usize>,
     /// Offset for pagination
     offset: Option<usize>,
     /// Include payload in response (default false for list)
     include_payload: Option<bool>,
+    /// Only pinned (true) or unpinned (false) events
+    pinned: Option<bool>,
+    /// Only acknowledged (true) or unacknowledged (false) events
+    acknowledged: Option<bool>,
+    /// Only notified (true) or not yet notified (false) events
+    notified: Option<bool>,
 }
 
 impl ListEventsQuery {
@@ -359,6 +674,30 @@ impl ListEventsQuery {
             .map(|s| s.split(',').map(|t| t.trim().to_string()).collect())
             .unwrap_or_default()
     }
+
+    fn payload_filter(&self) -> Result<Option<PayloadFilter>, AppError> {
+        self.payload_filter
+            .as_deref()
+            .filter(|s| !s.trim().is_empty())
+            .map(|s| {
+                PayloadFilter::parse(s)
+                    .map_err(|e| AppError::BadRequest(format!("Invalid payload_filter: {}", e)))
+            })
+            .transpose()
+    }
+
+    fn filter(&self) -> Result<EventFilter, AppError> {
+        Ok(EventFilter {
+            classifications: self.classifications(),
+            event_types: self.event_types(),
+            search: self.search.clone().filter(|q| !q.trim().is_empty()),
+            summary_search: self.summary_search.clone().filter(|q| !q.trim().is_empty()),
+            payload: self.payload_filter()?,
+            pinned: self.pinned,
+            acknowledged: self.acknowledged,
+            notified: self.notified,
+        })
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -372,6 +711,8 @@ pub struct EventResponse {
     pub classification: String,
     pub notified: bool,
     pub created_at: i64,
+    pub pinned: bool,
+    pub acknowledged_at: Option<i64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub payload: Option<serde_json::Value>,
 }
@@ -382,52 +723,235 @@ async fn list_events(
     Query(query): Query<ListEventsQuery>,
 ) -> Result<Json<Vec<EventResponse>>, AppError> {
     require_auth(&jar, &state.db)?;
-    list_events_impl(&state.db, query)
+    list_events_impl(&state.db, query, state.privacy_mode)
 }
 
 async fn list_events_legacy(
     State(state): State<Arc<AppState>>,
     Query(query): Query<ListEventsQuery>,
 ) -> Result<Json<Vec<EventResponse>>, AppError> {
-    list_events_impl(&state.db, query)
+    list_events_impl(&state.db, query, false)
 }
 
 fn list_events_impl(
     db: &Database,
     query: ListEventsQuery,
+    privacy_mode: bool,
 ) -> Result<Json<Vec<EventResponse>>, AppError> {
-    let classifications = query.classifications();
-    let event_types = query.event_types();
-    let event_type_refs: Vec<&str> = event_types.iter().map(|s| s.as_str()).collect();
     let include_payload = query.include_payload.unwrap_or(false);
 
     let events = db.query_events(
-        &classifications,
-        &event_type_refs,
-        query.search.as_deref(),
+        &query.filter()?,
         query.limit.unwrap_or(200),
         query.offset.unwrap_or(0),
     )?;
 
     let response: Vec<EventResponse> = events
         .into_iter()
-        .map(|e| EventResponse {
-            id: e.id,
-            source: e.source.to_string(),
-            event_type: e.event_type,
-            severity: e.severity.map(|s| format!("{:?}", s).to_lowercase()),
-            summary: e.summary,
-            timestamp: e.timestamp,
-            classification: e.classification.as_str().to_string(),
-            notified: e.notified,
-            created_at: e.created_at,
-            payload: if include_payload { Some(e.payload) } else { None },
-        })
+        .map(|e| event_response(e, include_payload, privacy_mode))
         .collect();
 
     Ok(Json(response))
 }
 
+/// A summary as shown to clients: in privacy mode, with the payload's
+/// personal data redacted, or withheld when the payload is no longer stored
+fn visible_summary(privacy_mode: bool, summary: String, payload: Option<&serde_json::Value>) -> String {
+    match (privacy_mode, payload) {
+        (false, _) => summary,
+        (true, Some(payload)) => crate::privacy::redact_summary(&summary, payload),
+        (true, None) => crate::privacy::REDACTED.to_string(),
+    }
+}
+
+fn event_response(e: StoredEvent, include_payload: bool, privacy_mode: bool) -> EventResponse {
+    let summary = visible_summary(privacy_mode, e.summary, Some(&e.payload));
+    EventResponse {
+        id: e.id,
+        source: e.source.to_string(),
+        event_type: e.event_type,
+        severity: e.severity.map(|s| format!("{:?}", s).to_lowercase()),
+        summary,
+        timestamp: e.timestamp,
+        classification: e.classification.as_str().to_string(),
+        notified: e.notified,
+        created_at: e.created_at,
+        pinned: e.pinned,
+        acknowledged_at: e.acknowledged_at,
+        payload: if include_payload { Some(e.payload) } else { None },
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EventTypeEventsQuery {
+    pub limit: Option<usize>,
+    /// Also return the total number of events of this type
+    pub include_count: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EventTypeEventsResponse {
+    pub event_type: String,
+    pub events: Vec<EventResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ActionableEventsQuery {
+    /// Number of events to return (default 200)
+    limit: Option<usize>,
+    /// Offset for pagination
+    offset: Option<usize>,
+}
+
+/// Notify events not yet acknowledged or notified, oldest first
+async fn list_actionable_events(
+    State(state): State<Arc<FullAppState>>,
+    jar: CookieJar,
+    Query(query): Query<ActionableEventsQuery>,
+) -> Result<Json<Vec<EventResponse>>, AppError> {
+    require_auth(&jar, &state.db)?;
+
+    let events = state
+        .db
+        .actionable_events(query.limit.unwrap_or(200), query.offset.unwrap_or(0))?;
+    Ok(Json(
+        events
+            .into_iter()
+            .map(|e| event_response(e, false, state.privacy_mode))
+            .collect(),
+    ))
+}
+
+/// Recent events of exactly one type (the path segment is percent-decoded,
+/// so types containing `/` must be sent as `%2F`)
+async fn list_events_for_type(
+    State(state): State<Arc<FullAppState>>,
+    jar: CookieJar,
+    axum::extract::Path(event_type): axum::extract::Path<String>,
+    Query(query): Query<EventTypeEventsQuery>,
+) -> Result<Json<EventTypeEventsResponse>, AppError> {
+    require_auth(&jar, &state.db)?;
+
+    let filter = EventFilter {
+        event_types: vec![event_type.clone()],
+        ..Default::default()
+    };
+    let events = state.db.query_events(&filter, query.limit.unwrap_or(50), 0)?;
+    let count = if query.include_count.unwrap_or(false) {
+        Some(state.db.count_events(&filter)?)
+    } else {
+        None
+    };
+
+    Ok(Json(EventTypeEventsResponse {
+        events: events
+            .into_iter()
+            .map(|e| event_response(e, false, state.privacy_mode))
+            .collect(),
+        event_type,
+        count,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateEventRequest {
+    /// Event source ("protect", "network" or "system")
+    pub source: String,
+    pub event_type: String,
+    pub summary: String,
+    /// Severity ("info", "warning", "error" or "critical")
+    pub severity: Option<String>,
+    pub payload: Option<serde_json::Value>,
+    /// Unix timestamp in seconds (default now)
+    pub timestamp: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreateEventResponse {
+    pub id: String,
+    pub classification: String,
+}
+
+async fn create_event(
+    State(state): State<Arc<FullAppState>>,
+    jar: CookieJar,
+    Json(req): Json<CreateEventRequest>,
+) -> Result<Json<CreateEventResponse>, AppError> {
+    require_auth(&jar, &state.db)?;
+    create_event_impl(&state.processor, &state.sse_tx, state.privacy_mode, req).await
+}
+
+async fn create_event_impl(
+    processor: &EventProcessor,
+    sse_tx: &broadcast::Sender<SseEvent>,
+    privacy_mode: bool,
+    req: CreateEventRequest,
+) -> Result<Json<CreateEventResponse>, AppError> {
+    let source = EventSource::from_str(&req.source)
+        .ok_or_else(|| AppError::BadRequest("Invalid source".to_string()))?;
+    let severity = match req.severity.as_deref() {
+        Some(s) => Some(
+            Severity::from_str(s).ok_or_else(|| AppError::BadRequest("Invalid severity".to_string()))?,
+        ),
+        None => None,
+    };
+    if req.event_type.trim().is_empty() {
+        return Err(AppError::BadRequest("event_type is required".to_string()));
+    }
+
+    let timestamp = match req.timestamp {
+        Some(ts) => chrono::DateTime::from_timestamp(ts, 0)
+            .ok_or_else(|| AppError::BadRequest("Invalid timestamp".to_string()))?,
+        None => chrono::Utc::now(),
+    };
+    let payload = req.payload.unwrap_or(serde_json::Value::Null);
+
+    // Content-based ID, so re-submitting the same event is deduplicated
+    let mut key_fields = extract_key_fields(&payload);
+    key_fields.push(req.summary.clone());
+    let key_refs: Vec<&str> = key_fields.iter().map(|s| s.as_str()).collect();
+    let id = generate_event_id(source, &req.event_type, timestamp, &key_refs);
+
+    let event = UnifiEvent {
+        id,
+        timestamp,
+        source,
+        event_type: req.event_type,
+        summary: req.summary,
+        severity,
+        raw: payload,
+    };
+
+    let classification = processor
+        .process(event.clone())
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+
+    if classification != Classification::Suppressed {
+        let sse_event = SseEvent {
+            id: event.id.clone(),
+            source: event.source.to_string(),
+            event_type: event.event_type.clone(),
+            severity: event.severity.map(|s| s.as_str().to_string()),
+            summary: event.summary.clone(),
+            timestamp: event.timestamp.timestamp(),
+            classification: classification.as_str().to_string(),
+            notified: false,
+            created_at: chrono::Utc::now().timestamp(),
+        };
+        let _ = sse_tx.send(if privacy_mode { sse_event.redact(&event.raw) } else { sse_event });
+    }
+
+    info!(id = event.id, event_type = event.event_type, "Event injected via API");
+
+    Ok(Json(CreateEventResponse {
+        id: event.id,
+        classification: classification.as_str().to_string(),
+    }))
+}
+
 #[derive(Debug, Serialize)]
 pub struct CountResponse {
     pub count: i64,
@@ -453,15 +977,7 @@ fn count_events_impl(
     db: &Database,
     query: ListEventsQuery,
 ) -> Result<Json<CountResponse>, AppError> {
-    let classifications = query.classifications();
-    let event_types = query.event_types();
-    let event_type_refs: Vec<&str> = event_types.iter().map(|s| s.as_str()).collect();
-
-    let count = db.count_events(
-        &classifications,
-        &event_type_refs,
-        query.search.as_deref(),
-    )?;
+    let count = db.count_events(&query.filter()?)?;
 
     Ok(Json(CountResponse { count }))
 }
@@ -504,11 +1020,126 @@ fn list_event_types_impl(db: &Database) -> Result<Json<Vec<EventTypeResponse>>,
     Ok(Json(response))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct BulkEventsRequest {
+    /// Which events to change, as for `GET /api/events` (`limit`, `offset`
+    /// and `include_payload` are ignored). Must not be empty.
+    #[serde(default)]
+    pub filter: ListEventsQuery,
+    /// "acknowledge", "set_classification" or "delete"
+    pub action: String,
+    /// Target classification for "set_classification"
+    pub classification: Option<String>,
+    /// Must be true for "delete"
+    #[serde(default)]
+    pub confirm: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BulkEventsResponse {
+    pub action: String,
+    pub affected: usize,
+}
+
+/// Apply one action to every event matching a filter, in one transaction
+async fn bulk_update_events(
+    State(state): State<Arc<FullAppState>>,
+    jar: CookieJar,
+    Json(req): Json<BulkEventsRequest>,
+) -> Result<Json<BulkEventsResponse>, AppError> {
+    require_auth(&jar, &state.db)?;
+
+    let filter = req.filter.filter()?;
+    if filter.is_empty() {
+        return Err(AppError::BadRequest(
+            "A filter is required for bulk operations".to_string(),
+        ));
+    }
+
+    let action = match req.action.as_str() {
+        "acknowledge" => BulkEventAction::Acknowledge,
+        "set_classification" => {
+            if state.db.classification_mode() == ClassificationMode::Dynamic {
+                return Err(AppError::Conflict(
+                    "Classifications come from rules in dynamic mode; change the rules instead".to_string(),
+                ));
+            }
+            let classification = req
+                .classification
+                .as_deref()
+                .and_then(Classification::from_str)
+                .filter(|c| *c != Classification::Suppressed)
+                .ok_or_else(|| AppError::BadRequest("Invalid classification".to_string()))?;
+            BulkEventAction::SetClassification(classification)
+        }
+        "delete" => {
+            if !req.confirm {
+                return Err(AppError::BadRequest(
+                    "Deleting events requires confirm=true".to_string(),
+                ));
+            }
+            BulkEventAction::Delete
+        }
+        other => return Err(AppError::BadRequest(format!("Unknown action '{}'", other))),
+    };
+
+    let affected = state.db.bulk_update_events(&filter, action)?;
+
+    Ok(Json(BulkEventsResponse {
+        action: req.action,
+        affected,
+    }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct PinResponse {
+    pub id: String,
+    pub pinned: bool,
+}
+
+/// Pin an event so size-based cleanup never deletes it
+async fn pin_event(
+    State(state): State<Arc<FullAppState>>,
+    jar: CookieJar,
+    axum::extract::Path(event_id): axum::extract::Path<String>,
+) -> Result<Json<PinResponse>, AppError> {
+    require_auth(&jar, &state.db)?;
+    set_pinned_impl(&state.db, event_id, true)
+}
+
+async fn unpin_event(
+    State(state): State<Arc<FullAppState>>,
+    jar: CookieJar,
+    axum::extract::Path(event_id): axum::extract::Path<String>,
+) -> Result<Json<PinResponse>, AppError> {
+    require_auth(&jar, &state.db)?;
+    set_pinned_impl(&state.db, event_id, false)
+}
+
+fn set_pinned_impl(db: &Database, event_id: String, pinned: bool) -> Result<Json<PinResponse>, AppError> {
+    if !db.set_pinned(&event_id, pinned)? {
+        return Err(AppError::NotFound);
+    }
+    tracing::info!(event_id, pinned, "Event pin changed");
+    Ok(Json(PinResponse { id: event_id, pinned }))
+}
+
 #[derive(Debug, Serialize)]
 pub struct PayloadResponse {
     pub payload: serde_json::Value,
 }
 
+/// One event, for the dashboard's `/events/<id>` links
+async fn get_event(
+    State(state): State<Arc<FullAppState>>,
+    jar: CookieJar,
+    axum::extract::Path(event_id): axum::extract::Path<String>,
+) -> Result<Json<EventResponse>, AppError> {
+    require_auth(&jar, &state.db)?;
+    let event = state.db.get_event(&event_id)?.ok_or(AppError::NotFound)?;
+    Ok(Json(event_response(event, false, state.privacy_mode)))
+}
+
 async fn get_event_payload(
     State(state): State<Arc<FullAppState>>,
     jar: CookieJar,
@@ -540,6 +1171,7 @@ fn get_event_payload_impl(db: &Database, event_id: &str) -> Result<Json<PayloadR
 pub struct RuleResponse {
     pub event_type: String,
     pub classification: String,
+    pub priority: i64,
 }
 
 async fn list_rules(
@@ -557,13 +1189,15 @@ async fn list_rules_legacy(
 }
 
 fn list_rules_impl(db: &Database) -> Result<Json<Vec<RuleResponse>>, AppError> {
-    let rules = db.get_all_rules()?;
+    let mut rules = db.get_all_rules()?;
+    rules.sort_by(|a, b| a.event_type.cmp(&b.event_type));
 
     let response: Vec<RuleResponse> = rules
         .into_iter()
-        .map(|(event_type, classification)| RuleResponse {
-            event_type,
-            classification: classification.as_str().to_string(),
+        .map(|rule| RuleResponse {
+            event_type: rule.event_type,
+            classification: rule.classification.as_str().to_string(),
+            priority: rule.priority,
         })
         .collect();
 
@@ -572,8 +1206,12 @@ fn list_rules_impl(db: &Database) -> Result<Json<Vec<RuleResponse>>, AppError> {
 
 #[derive(Debug, Deserialize)]
 pub struct SetRuleRequest {
+    /// An event type, or a pattern where `*` matches any run of characters
     pub event_type: String,
     pub classification: String,
+    /// Breaks ties between overlapping patterns (higher wins); an existing
+    /// rule keeps its priority when omitted
+    pub priority: Option<i64>,
 }
 
 async fn set_rule(
@@ -596,14 +1234,20 @@ fn set_rule_impl(db: &Database, req: SetRuleRequest) -> Result<Json<RuleResponse
     let classification = Classification::from_str(&req.classification)
         .ok_or_else(|| AppError::BadRequest("Invalid classification".to_string()))?;
 
-    db.set_rule(&req.event_type, classification)?;
+    let priority = db.set_rule_with_priority(&req.event_type, classification, req.priority)?;
 
     Ok(Json(RuleResponse {
         event_type: req.event_type,
         classification: classification.as_str().to_string(),
+        priority,
     }))
 }
 
+/// DELETE /api/rules/{event_type}
+///
+/// The event type is percent-decoded (`/` must be sent as `%2F`). Types that
+/// collide with other rule routes (`test`, `merge`) can't be addressed this
+/// way; use `DELETE /api/rules` with a JSON body instead.
 async fn delete_rule(
     State(state): State<Arc<FullAppState>>,
     jar: CookieJar,
@@ -613,6 +1257,21 @@ async fn delete_rule(
     delete_rule_impl(&state.db, &event_type)
 }
 
+#[derive(Debug, Deserialize)]
+pub struct DeleteRuleRequest {
+    pub event_type: String,
+}
+
+/// DELETE /api/rules with `{"event_type": ...}`, which works for any event type
+async fn delete_rule_by_body(
+    State(state): State<Arc<FullAppState>>,
+    jar: CookieJar,
+    Json(req): Json<DeleteRuleRequest>,
+) -> Result<StatusCode, AppError> {
+    require_auth(&jar, &state.db)?;
+    delete_rule_impl(&state.db, &req.event_type)
+}
+
 async fn delete_rule_legacy(
     State(state): State<Arc<AppState>>,
     axum::extract::Path(event_type): axum::extract::Path<String>,
@@ -629,36 +1288,233 @@ fn delete_rule_impl(db: &Database, event_type: &str) -> Result<StatusCode, AppEr
     }
 }
 
-// ============================================================================
-// Stats API
-// ============================================================================
+#[derive(Debug, Deserialize)]
+pub struct MergeRulesRequest {
+    pub from: String,
+    pub to: String,
+}
 
 #[derive(Debug, Serialize)]
-pub struct StatsResponse {
-    pub total_events: i64,
-    pub unclassified_types: i64,
-    pub notify_types: i64,
-    pub ignored_types: i64,
+pub struct MergeRulesResponse {
+    pub from: String,
+    pub to: String,
+    /// Number of events moved from `from` to `to`
+    pub events_updated: usize,
+    /// Classification of the merged event type
+    pub classification: String,
 }
 
-async fn get_stats(
+/// Merge one event type into another (e.g. after a firmware rename)
+async fn merge_rules(
     State(state): State<Arc<FullAppState>>,
     jar: CookieJar,
-) -> Result<Json<StatsResponse>, AppError> {
+    Json(req): Json<MergeRulesRequest>,
+) -> Result<Json<MergeRulesResponse>, AppError> {
     require_auth(&jar, &state.db)?;
-    get_stats_impl(&state.db)
+
+    if req.from.is_empty() || req.to.is_empty() {
+        return Err(AppError::BadRequest("from and to are required".to_string()));
+    }
+    if req.from == req.to {
+        return Err(AppError::BadRequest("from and to must differ".to_string()));
+    }
+
+    let events_updated = state.db.rename_event_type(&req.from, &req.to)?;
+    let classification = state.db.get_classification(&req.to)?;
+
+    Ok(Json(MergeRulesResponse {
+        from: req.from,
+        to: req.to,
+        events_updated,
+        classification: classification.as_str().to_string(),
+    }))
 }
 
-async fn get_stats_legacy(
-    State(state): State<Arc<AppState>>,
-) -> Result<Json<StatsResponse>, AppError> {
-    get_stats_impl(&state.db)
+#[derive(Debug, Deserialize)]
+pub struct TestRuleQuery {
+    pub event_type: String,
 }
 
-fn get_stats_impl(db: &Database) -> Result<Json<StatsResponse>, AppError> {
-    let summaries = db.get_event_type_summary()?;
+#[derive(Debug, Serialize)]
+pub struct TestRuleResponse {
+    pub event_type: String,
+    pub classification: String,
+    /// How the classification was determined ("exact", "wildcard" or "default")
+    pub matched_by: String,
+    /// The rule pattern that matched, if any
+    pub matched_rule: Option<String>,
+}
 
-    let total_events: i64 = summaries.iter().map(|s| s.count).sum();
+async fn test_rule(
+    State(state): State<Arc<FullAppState>>,
+    jar: CookieJar,
+    Query(query): Query<TestRuleQuery>,
+) -> Result<Json<TestRuleResponse>, AppError> {
+    require_auth(&jar, &state.db)?;
+
+    let (classification, reason) = state.db.explain_classification(&query.event_type)?;
+
+    Ok(Json(TestRuleResponse {
+        matched_by: reason.as_str().to_string(),
+        matched_rule: reason.rule().map(str::to_string),
+        event_type: query.event_type,
+        classification: classification.as_str().to_string(),
+    }))
+}
+
+// ============================================================================
+// Entity State API
+// ============================================================================
+
+#[derive(Debug, Deserialize)]
+pub struct EntityStateQuery {
+    pub event_type: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EntityStateResponse {
+    pub event_type: String,
+    pub entity_id: String,
+    pub source: String,
+    pub summary: String,
+    pub first_seen: i64,
+    pub last_seen: i64,
+    pub change_count: i64,
+}
+
+/// Current state of entities for compacted event types (COMPACT_EVENT_TYPES)
+async fn list_entity_states(
+    State(state): State<Arc<FullAppState>>,
+    jar: CookieJar,
+    Query(query): Query<EntityStateQuery>,
+) -> Result<Json<Vec<EntityStateResponse>>, AppError> {
+    require_auth(&jar, &state.db)?;
+
+    let limit = query.limit.unwrap_or(100).min(1000);
+    let states = state.db.get_entity_states(query.event_type.as_deref(), limit)?;
+
+    Ok(Json(
+        states
+            .into_iter()
+            .map(|s| EntityStateResponse {
+                event_type: s.event_type,
+                entity_id: s.entity_id,
+                source: s.source.to_string(),
+                summary: visible_summary(state.privacy_mode, s.summary, Some(&s.payload)),
+                first_seen: s.first_seen,
+                last_seen: s.last_seen,
+                change_count: s.change_count,
+            })
+            .collect(),
+    ))
+}
+
+// ============================================================================
+// Stats API
+// ============================================================================
+
+#[derive(Debug, Serialize)]
+pub struct SystemInfoResponse {
+    pub version: String,
+    /// Startup historical backfill (null until the UniFi client has connected)
+    pub backfill: Option<HistoricalFetchResult>,
+}
+
+async fn get_system_info(
+    State(state): State<Arc<FullAppState>>,
+    jar: CookieJar,
+) -> Result<Json<SystemInfoResponse>, AppError> {
+    require_auth(&jar, &state.db)?;
+
+    Ok(Json(SystemInfoResponse {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        backfill: state.backfill.read().unwrap().clone(),
+    }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct SourceConnectionResponse {
+    pub source: String,
+    #[serde(flatten)]
+    pub state: SourceConnection,
+    /// Seconds since the current connection started (null while disconnected)
+    pub uptime_secs: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConnectionStatusResponse {
+    pub sources: Vec<SourceConnectionResponse>,
+}
+
+async fn get_connection_status(
+    State(state): State<Arc<FullAppState>>,
+    jar: CookieJar,
+) -> Result<Json<ConnectionStatusResponse>, AppError> {
+    require_auth(&jar, &state.db)?;
+
+    let now = chrono::Utc::now().timestamp();
+    let sources = state
+        .connections
+        .snapshot()
+        .into_iter()
+        .map(|(source, state)| SourceConnectionResponse {
+            source: source.to_string(),
+            uptime_secs: state.connected_since.map(|since| (now - since).max(0)),
+            state,
+        })
+        .collect();
+
+    Ok(Json(ConnectionStatusResponse { sources }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct StatsResponse {
+    pub total_events: i64,
+    pub unclassified_types: i64,
+    pub notify_types: i64,
+    pub ignored_types: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StatsQuery {
+    /// Only count events stored at or after this time (unix seconds)
+    since: Option<i64>,
+    /// Only count events stored since the service started
+    #[serde(default)]
+    since_startup: bool,
+}
+
+async fn get_stats(
+    State(state): State<Arc<FullAppState>>,
+    jar: CookieJar,
+    Query(query): Query<StatsQuery>,
+) -> Result<Json<StatsResponse>, AppError> {
+    require_auth(&jar, &state.db)?;
+
+    let since = match (query.since, query.since_startup) {
+        (Some(_), true) => return Err(AppError::BadRequest("Use either since or since_startup".to_string())),
+        (since, false) => since,
+        (None, true) => Some(state.started_at),
+    };
+    match since {
+        Some(since) => stats_response(state.db.get_event_type_summary_created_since(since)?),
+        None => get_stats_impl(&state.db),
+    }
+}
+
+async fn get_stats_legacy(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<StatsResponse>, AppError> {
+    get_stats_impl(&state.db)
+}
+
+fn get_stats_impl(db: &Database) -> Result<Json<StatsResponse>, AppError> {
+    stats_response(db.get_event_type_summary()?)
+}
+
+fn stats_response(summaries: Vec<EventTypeSummary>) -> Result<Json<StatsResponse>, AppError> {
+    let total_events: i64 = summaries.iter().map(|s| s.count).sum();
     let unclassified_types = summaries
         .iter()
         .filter(|s| s.classification == Classification::Unclassified)
@@ -716,7 +1572,9 @@ async fn get_notification_history(
             id: entry.id,
             event_id: entry.event_id,
             event_type: entry.event_type,
-            event_summary: entry.event_summary,
+            event_summary: entry
+                .event_summary
+                .map(|summary| visible_summary(state.privacy_mode, summary, entry.event_payload.as_ref())),
             status: entry.status,
             error_message: entry.error_message,
             created_at: entry.created_at,
@@ -729,6 +1587,31 @@ async fn get_notification_history(
 #[derive(Debug, Serialize)]
 pub struct NotificationStatusResponse {
     pub configured: bool,
+    /// Whether the mute-all kill switch is on
+    pub muted: bool,
+    /// Startup validation of each Telegram recipient
+    pub recipients: Vec<RecipientStatusResponse>,
+    /// Failed notifications waiting for their next attempt, soonest first
+    pub retries: Vec<ScheduledRetryResponse>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScheduledRetryResponse {
+    pub event_id: String,
+    pub event_type: String,
+    pub summary: String,
+    pub attempts: i32,
+    /// When the next attempt is due (unix seconds)
+    pub next_attempt_at: i64,
+    pub backoff_secs: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecipientStatusResponse {
+    pub chat_id: String,
+    /// "reachable", "rejected" or "unverified"
+    pub status: String,
+    pub error: Option<String>,
 }
 
 async fn get_notification_status(
@@ -737,8 +1620,282 @@ async fn get_notification_status(
 ) -> Result<Json<NotificationStatusResponse>, AppError> {
     require_auth(&jar, &state.db)?;
 
+    let recipients = state
+        .telegram
+        .as_ref()
+        .map(|telegram| {
+            telegram
+                .recipients
+                .read()
+                .unwrap()
+                .iter()
+                .map(|r| RecipientStatusResponse {
+                    chat_id: r.chat_id.clone(),
+                    status: r.check.as_str().to_string(),
+                    error: r.check.error().map(str::to_string),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let retries = state
+        .db
+        .get_scheduled_retries()?
+        .into_iter()
+        .map(|r| ScheduledRetryResponse {
+            event_id: r.event_id,
+            event_type: r.event_type,
+            summary: visible_summary(state.privacy_mode, r.summary, Some(&r.payload)),
+            attempts: r.attempts,
+            next_attempt_at: r.next_attempt_at,
+            backoff_secs: r.backoff_secs,
+        })
+        .collect();
+
     Ok(Json(NotificationStatusResponse {
-        configured: state.telegram.is_some(),
+        configured: !state.notifiers.is_empty(),
+        muted: state.db.notifications_muted()?,
+        recipients,
+        retries,
+    }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct MuteResponse {
+    pub muted: bool,
+    /// Pending notifications queued for delivery on unmute
+    pub requeued: usize,
+}
+
+async fn mute_notifications(
+    State(state): State<Arc<FullAppState>>,
+    jar: CookieJar,
+) -> Result<Json<MuteResponse>, AppError> {
+    require_auth(&jar, &state.db)?;
+
+    state.db.set_notifications_muted(true)?;
+    info!("Notifications muted");
+    Ok(Json(MuteResponse { muted: true, requeued: 0 }))
+}
+
+async fn unmute_notifications(
+    State(state): State<Arc<FullAppState>>,
+    jar: CookieJar,
+) -> Result<Json<MuteResponse>, AppError> {
+    require_auth(&jar, &state.db)?;
+
+    let requeued = unmute(&state).await?;
+    Ok(Json(MuteResponse { muted: false, requeued }))
+}
+
+/// Turn the kill switch off and queue anything held back while muted,
+/// returning how many notifications were requeued
+async fn unmute(state: &FullAppState) -> Result<usize, AppError> {
+    state.db.set_notifications_muted(false)?;
+    let requeued = state
+        .processor
+        .load_pending_notifications()
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    info!(requeued, "Notifications unmuted");
+    Ok(requeued)
+}
+
+// ============================================================================
+// Settings API
+// ============================================================================
+
+#[derive(Debug, Serialize)]
+pub struct SettingResponse {
+    pub key: &'static str,
+    /// Current value, or null if never set
+    pub value: serde_json::Value,
+    pub kind: SettingKind,
+    /// "runtime" settings apply immediately, "startup" ones after a restart
+    pub scope: SettingScope,
+    pub description: &'static str,
+}
+
+fn list_settings(db: &Database) -> Result<Vec<SettingResponse>, AppError> {
+    settings::SETTINGS
+        .iter()
+        .map(|def| {
+            Ok(SettingResponse {
+                key: def.key,
+                value: db
+                    .get_setting(def.key)?
+                    .map(|v| def.kind.to_json(&v))
+                    .unwrap_or(serde_json::Value::Null),
+                kind: def.kind,
+                scope: def.scope,
+                description: def.description,
+            })
+        })
+        .collect()
+}
+
+async fn get_settings(
+    State(state): State<Arc<FullAppState>>,
+    jar: CookieJar,
+) -> Result<Json<Vec<SettingResponse>>, AppError> {
+    require_auth(&jar, &state.db)?;
+    Ok(Json(list_settings(&state.db)?))
+}
+
+/// Set one or more settings from a `{"key": value}` object; nothing is
+/// written unless every entry is valid
+async fn update_settings(
+    State(state): State<Arc<FullAppState>>,
+    jar: CookieJar,
+    Json(body): Json<std::collections::HashMap<String, serde_json::Value>>,
+) -> Result<Json<Vec<SettingResponse>>, AppError> {
+    require_auth(&jar, &state.db)?;
+
+    let mut updates = Vec::with_capacity(body.len());
+    for (key, value) in &body {
+        let def = settings::setting_def(key)
+            .ok_or_else(|| AppError::BadRequest(format!("Unknown setting '{}'", key)))?;
+        let stored = def
+            .kind
+            .parse(value)
+            .map_err(|e| AppError::BadRequest(format!("Invalid value for '{}': {}", key, e)))?;
+        updates.push((def, stored));
+    }
+
+    for (def, stored) in updates {
+        if def.key == settings::NOTIFICATIONS_MUTED && stored == "false" {
+            // Same as the unmute endpoint, so held notifications go out
+            unmute(&state).await?;
+            continue;
+        }
+        state.db.set_setting(def.key, &stored)?;
+        if def.scope == SettingScope::Startup {
+            info!(key = def.key, value = stored, "Setting updated, takes effect after a restart");
+        } else {
+            info!(key = def.key, value = stored, "Setting updated");
+        }
+    }
+
+    Ok(Json(list_settings(&state.db)?))
+}
+
+// ============================================================================
+// Configuration bundle
+// ============================================================================
+
+/// Current configuration bundle format
+const CONFIG_BUNDLE_VERSION: u32 = 1;
+
+/// Portable configuration: classification rules and settings, without events
+/// or secrets (backend tokens and URLs stay in the environment)
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigBundle {
+    pub version: u32,
+    pub rules: Vec<BundleRule>,
+    /// Values of the known settings that are set
+    pub settings: std::collections::BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BundleRule {
+    pub event_type: String,
+    pub classification: String,
+    #[serde(default)]
+    pub priority: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportConfigResponse {
+    pub rules: usize,
+    pub settings: usize,
+}
+
+async fn export_config(
+    State(state): State<Arc<FullAppState>>,
+    jar: CookieJar,
+) -> Result<Json<ConfigBundle>, AppError> {
+    require_auth(&jar, &state.db)?;
+
+    let rules = state
+        .db
+        .get_all_rules()?
+        .into_iter()
+        .map(|rule| BundleRule {
+            event_type: rule.event_type,
+            classification: rule.classification.as_str().to_string(),
+            priority: rule.priority,
+        })
+        .collect();
+
+    let mut bundle_settings = std::collections::BTreeMap::new();
+    for def in settings::SETTINGS {
+        if let Some(value) = state.db.get_setting(def.key)? {
+            bundle_settings.insert(def.key.to_string(), def.kind.to_json(&value));
+        }
+    }
+
+    Ok(Json(ConfigBundle {
+        version: CONFIG_BUNDLE_VERSION,
+        rules,
+        settings: bundle_settings,
+    }))
+}
+
+/// Replace all rules and set the bundle's settings; nothing changes unless
+/// the whole bundle is valid
+async fn import_config(
+    State(state): State<Arc<FullAppState>>,
+    jar: CookieJar,
+    Json(body): Json<serde_json::Value>,
+) -> Result<Json<ImportConfigResponse>, AppError> {
+    require_auth(&jar, &state.db)?;
+
+    let bundle: ConfigBundle = serde_json::from_value(body)
+        .map_err(|e| AppError::BadRequest(format!("Invalid configuration bundle: {}", e)))?;
+    if bundle.version != CONFIG_BUNDLE_VERSION {
+        return Err(AppError::BadRequest(format!(
+            "Unsupported bundle version {} (expected {})",
+            bundle.version, CONFIG_BUNDLE_VERSION
+        )));
+    }
+
+    let rules = bundle
+        .rules
+        .iter()
+        .map(|rule| {
+            Classification::from_str(&rule.classification)
+                .map(|classification| Rule {
+                    event_type: rule.event_type.clone(),
+                    classification,
+                    priority: rule.priority,
+                })
+                .ok_or_else(|| {
+                    AppError::BadRequest(format!(
+                        "Invalid classification '{}' for '{}'",
+                        rule.classification, rule.event_type
+                    ))
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut bundle_settings = Vec::with_capacity(bundle.settings.len());
+    for (key, value) in &bundle.settings {
+        let def = settings::setting_def(key)
+            .ok_or_else(|| AppError::BadRequest(format!("Unknown setting '{}'", key)))?;
+        let stored = def
+            .kind
+            .parse(value)
+            .map_err(|e| AppError::BadRequest(format!("Invalid value for '{}': {}", key, e)))?;
+        bundle_settings.push((def.key, stored));
+    }
+
+    state.db.replace_config(&rules, &bundle_settings)?;
+
+    Ok(Json(ImportConfigResponse {
+        rules: rules.len(),
+        settings: bundle_settings.len(),
     }))
 }
 
@@ -746,27 +1903,100 @@ async fn get_notification_status(
 pub struct TestNotificationResponse {
     pub success: bool,
     pub error: Option<String>,
+    /// Result for each backend
+    pub backends: Vec<BackendTestResponse>,
 }
 
+#[derive(Debug, Serialize)]
+pub struct BackendTestResponse {
+    pub backend: String,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NotificationBackendResponse {
+    /// Identifies the backend in `POST /api/notifications/test/{backend_id}`
+    pub id: String,
+}
+
+/// Send a test through one backend, recording it in the notification log
+async fn test_backend(db: &Database, notifier: &dyn Notifier) -> BackendTestResponse {
+    let result = notifier.send_test().await;
+    let error = result.err().map(|e| e.to_string());
+
+    let summary = format!("Test notification ({})", notifier.id());
+    let status = if error.is_none() { "sent" } else { "failed" };
+    if let Err(e) = db.log_notification(None, None, Some(&summary), status, error.as_deref()) {
+        tracing::error!(error = %e, "Failed to log test notification");
+    }
+
+    BackendTestResponse {
+        backend: notifier.id().to_string(),
+        success: error.is_none(),
+        error,
+    }
+}
+
+/// List the configured notification backends
+async fn list_notification_backends(
+    State(state): State<Arc<FullAppState>>,
+    jar: CookieJar,
+) -> Result<Json<Vec<NotificationBackendResponse>>, AppError> {
+    require_auth(&jar, &state.db)?;
+
+    Ok(Json(
+        state
+            .notifiers
+            .iter()
+            .map(|n| NotificationBackendResponse { id: n.id().to_string() })
+            .collect(),
+    ))
+}
+
+/// Send a test through every configured backend
 async fn send_test_notification(
     State(state): State<Arc<FullAppState>>,
     jar: CookieJar,
 ) -> Result<Json<TestNotificationResponse>, AppError> {
     require_auth(&jar, &state.db)?;
 
-    let telegram = state.telegram.as_ref()
-        .ok_or_else(|| AppError::BadRequest("Telegram not configured".to_string()))?;
+    if state.notifiers.is_empty() {
+        return Err(AppError::BadRequest("No notification backend configured".to_string()));
+    }
 
-    match crate::processor::send_test_notification(&state.db, &telegram.token, &telegram.chat_id).await {
-        Ok(()) => Ok(Json(TestNotificationResponse {
-            success: true,
-            error: None,
-        })),
-        Err(e) => Ok(Json(TestNotificationResponse {
-            success: false,
-            error: Some(e.to_string()),
-        })),
+    let mut backends = Vec::with_capacity(state.notifiers.len());
+    for notifier in state.notifiers.iter() {
+        backends.push(test_backend(&state.db, notifier.as_ref()).await);
     }
+
+    let errors: Vec<String> = backends
+        .iter()
+        .filter_map(|b| b.error.as_ref().map(|e| format!("{}: {}", b.backend, e)))
+        .collect();
+
+    Ok(Json(TestNotificationResponse {
+        success: errors.is_empty(),
+        error: (!errors.is_empty()).then(|| errors.join("; ")),
+        backends,
+    }))
+}
+
+/// Send a test through a single backend
+async fn send_backend_test_notification(
+    State(state): State<Arc<FullAppState>>,
+    jar: CookieJar,
+    axum::extract::Path(backend_id): axum::extract::Path<String>,
+) -> Result<Json<BackendTestResponse>, AppError> {
+    require_auth(&jar, &state.db)?;
+
+    let notifier = state
+        .notifiers
+        .iter()
+        .find(|n| n.id() == backend_id)
+        .ok_or(AppError::NotFound)?;
+
+    Ok(Json(test_backend(&state.db, notifier.as_ref()).await))
 }
 
 // ============================================================================
@@ -779,7 +2009,9 @@ pub enum AppError {
     BadRequest(String),
     NotFound,
     Unauthorized(String),
+    Conflict(String),
     Internal(String),
+    ServiceUnavailable(String),
 }
 
 impl From<rusqlite::Error> for AppError {
@@ -804,9 +2036,789 @@ impl IntoResponse for AppError {
             AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
             AppError::NotFound => (StatusCode::NOT_FOUND, "Not found".to_string()),
             AppError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
+            AppError::Conflict(msg) => (StatusCode::CONFLICT, msg),
             AppError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
+            AppError::ServiceUnavailable(msg) => (StatusCode::SERVICE_UNAVAILABLE, msg),
         };
 
         (status, Json(serde_json::json!({ "error": message }))).into_response()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processor::ProcessorConfig;
+    use axum::body::Body;
+    use axum::http::Request;
+    use tokio::sync::mpsc;
+    use tower::ServiceExt;
+
+    /// Router with full auth, plus a valid session cookie for it
+    fn test_router(db: &Database) -> (Router, String) {
+        let (router, cookie, _) = test_router_with_sse(db);
+        (router, cookie)
+    }
+
+    /// Like `test_router`, also returning the sender that feeds `/api/events/stream`
+    fn test_router_with_sse(db: &Database) -> (Router, String, broadcast::Sender<SseEvent>) {
+        test_router_with_notifiers(db, Vec::new())
+    }
+
+    fn test_router_with_notifiers(
+        db: &Database,
+        notifiers: Vec<Box<dyn Notifier>>,
+    ) -> (Router, String, broadcast::Sender<SseEvent>) {
+        let (state, sse_tx) = test_state(db, notifiers);
+        let session = db.create_session(1).unwrap();
+        (create_router_with_auth(state, None), format!("unifi_session={}", session), sse_tx)
+    }
+
+    fn test_state(db: &Database, notifiers: Vec<Box<dyn Notifier>>) -> (FullAppState, broadcast::Sender<SseEvent>) {
+        let (notify_tx, _notify_rx) = mpsc::channel(10);
+        let processor = Arc::new(EventProcessor::new(db.clone(), ProcessorConfig::default(), notify_tx));
+        let (sse_tx, _) = broadcast::channel(10);
+        let webauthn = auth::create_webauthn("localhost", &Url::parse("http://localhost:8080").unwrap()).unwrap();
+        let auth = AuthState {
+            db: db.clone(),
+            webauthn: Arc::new(webauthn),
+            reg_challenges: Default::default(),
+            auth_challenges: Default::default(),
+            use_secure_cookies: false,
+        };
+        let state = FullAppState {
+            db: db.clone(),
+            sse_tx: sse_tx.clone(),
+            auth,
+            telegram: None,
+            notifiers: Arc::new(notifiers),
+            processor,
+            backfill: Default::default(),
+            connections: Default::default(),
+            started_at: chrono::Utc::now().timestamp(),
+            sse_connections: Default::default(),
+            privacy_mode: false,
+        };
+        (state, sse_tx)
+    }
+
+    async fn send(router: &Router, method: &str, uri: &str, cookie: &str) -> (StatusCode, serde_json::Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Cookie", cookie)
+            .body(Body::empty())
+            .unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json = serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null);
+        (status, json)
+    }
+
+    async fn send_json(
+        router: &Router,
+        method: &str,
+        uri: &str,
+        cookie: &str,
+        body: serde_json::Value,
+    ) -> (StatusCode, serde_json::Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Cookie", cookie)
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json = serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null);
+        (status, json)
+    }
+
+    fn store_typed_event(db: &Database, id: &str, event_type: &str) {
+        db.store_event(&UnifiEvent {
+            id: id.to_string(),
+            timestamp: chrono::Utc::now(),
+            source: EventSource::Network,
+            event_type: event_type.to_string(),
+            summary: "Test".to_string(),
+            severity: None,
+            raw: serde_json::json!({}),
+        })
+        .unwrap();
+    }
+
+    fn sse_event(id: &str, severity: Option<&str>) -> SseEvent {
+        SseEvent {
+            id: id.to_string(),
+            source: "network".to_string(),
+            event_type: "test".to_string(),
+            severity: severity.map(str::to_string),
+            summary: "Test".to_string(),
+            timestamp: 0,
+            classification: "unclassified".to_string(),
+            notified: false,
+            created_at: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_event_stream_min_severity() {
+        let db = Database::open_in_memory().unwrap();
+        let (router, cookie, sse_tx) = test_router_with_sse(&db);
+
+        let (status, _) = send(&router, "GET", "/api/events/stream?min_severity=loud", &cookie).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let request = Request::builder()
+            .uri("/api/events/stream?min_severity=warning")
+            .header("Cookie", &cookie)
+            .body(Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        sse_tx.send(sse_event("quiet", Some("info"))).unwrap();
+        sse_tx.send(sse_event("unknown", None)).unwrap();
+        sse_tx.send(sse_event("loud", Some("error"))).unwrap();
+
+        // The first frame the subscriber gets is the error
+        let mut body = response.into_body().into_data_stream();
+        let chunk = tokio::time::timeout(std::time::Duration::from_secs(5), body.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        let text = String::from_utf8(chunk.to_vec()).unwrap();
+        assert!(text.contains("\"id\":\"loud\""), "unexpected frame: {}", text);
+    }
+
+    #[tokio::test]
+    async fn test_event_stream_connection_cap() {
+        let db = Database::open_in_memory().unwrap();
+        let (mut state, _sse_tx) = test_state(&db, Vec::new());
+        state.sse_connections = SseConnections::new(2);
+        let connections = state.sse_connections.clone();
+        let router = create_router_with_auth(state, None);
+        let cookie = format!("unifi_session={}", db.create_session(1).unwrap());
+
+        let open = || {
+            let request = Request::builder()
+                .uri("/api/events/stream")
+                .header("Cookie", &cookie)
+                .body(Body::empty())
+                .unwrap();
+            router.clone().oneshot(request)
+        };
+
+        let first = open().await.unwrap();
+        let second = open().await.unwrap();
+        assert_eq!(first.status(), StatusCode::OK);
+        assert_eq!(second.status(), StatusCode::OK);
+        assert_eq!(connections.active(), 2);
+
+        let rejected = open().await.unwrap();
+        assert_eq!(rejected.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(connections.active(), 2);
+
+        // A client going away frees its slot
+        drop(first);
+        assert_eq!(connections.active(), 1);
+        let third = open().await.unwrap();
+        assert_eq!(third.status(), StatusCode::OK);
+
+        drop((second, third));
+        assert_eq!(connections.active(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_privacy_mode_redacts_summaries() {
+        let db = Database::open_in_memory().unwrap();
+        db.store_event(&UnifiEvent {
+            id: "evt-1".to_string(),
+            timestamp: chrono::Utc::now(),
+            source: EventSource::Network,
+            event_type: "sta:sync".to_string(),
+            summary: "Client sync: laptop-alice".to_string(),
+            severity: None,
+            raw: serde_json::json!({"hostname": "laptop-alice"}),
+        })
+        .unwrap();
+        let (mut state, _sse_tx) = test_state(&db, Vec::new());
+        state.privacy_mode = true;
+        let router = create_router_with_auth(state, None);
+        let cookie = format!("unifi_session={}", db.create_session(1).unwrap());
+
+        let (_, body) = send(&router, "GET", "/api/events", &cookie).await;
+        assert_eq!(body[0]["summary"], "Client sync: REDACTED");
+        let (_, body) = send(&router, "GET", "/api/events/evt-1", &cookie).await;
+        assert_eq!(body["summary"], "Client sync: REDACTED");
+
+        // Notification history and scheduled retries show the same summary
+        let summary = Some("Client sync: laptop-alice");
+        db.log_notification(Some("evt-1"), Some("sta:sync"), summary, "failed", None).unwrap();
+        db.log_notification(Some("gone"), Some("sta:sync"), summary, "sent", None).unwrap();
+        db.schedule_notification_retry("evt-1", chrono::Utc::now().timestamp() + 60, 60, &[]).unwrap();
+        let (_, body) = send(&router, "GET", "/api/notifications/history", &cookie).await;
+        let summaries: Vec<_> = body.as_array().unwrap().iter().map(|e| e["event_summary"].clone()).collect();
+        assert!(summaries.contains(&serde_json::json!("Client sync: REDACTED")));
+        // Without the event's payload there's nothing to redact against
+        assert!(summaries.contains(&serde_json::json!("REDACTED")));
+        let (_, body) = send(&router, "GET", "/api/notifications/status", &cookie).await;
+        assert_eq!(body["retries"][0]["summary"], "Client sync: REDACTED");
+
+        // The raw payload and the stored event are untouched
+        let (_, body) = send(&router, "GET", "/api/events/evt-1/payload", &cookie).await;
+        assert_eq!(body["payload"]["hostname"], "laptop-alice");
+        let stored = db.events_created_since(0, 10).unwrap();
+        assert_eq!(stored[0].summary, "Client sync: laptop-alice");
+    }
+
+    #[tokio::test]
+    async fn test_event_stream_replays_after_lag() {
+        let db = Database::open_in_memory().unwrap();
+        let (router, cookie, sse_tx) = test_router_with_sse(&db);
+
+        let request = Request::builder()
+            .uri("/api/events/stream")
+            .header("Cookie", &cookie)
+            .body(Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Overflow the test channel (capacity 10, rounded up to 16) before the client reads anything
+        for i in 0..20 {
+            let event = UnifiEvent {
+                id: format!("e{:02}", i),
+                timestamp: chrono::Utc::now(),
+                source: EventSource::Network,
+                event_type: "test".to_string(),
+                summary: "Test".to_string(),
+                severity: None,
+                raw: serde_json::json!({}),
+            };
+            db.store_event(&event).unwrap();
+            let mut sse = sse_event(&event.id, None);
+            sse.created_at = chrono::Utc::now().timestamp();
+            sse_tx.send(sse).unwrap();
+        }
+
+        // Read until the last live event, which would follow any duplicates
+        let mut body = response.into_body().into_data_stream();
+        let mut text = String::new();
+        while !text.contains("\"id\":\"e19\"") {
+            let chunk = tokio::time::timeout(std::time::Duration::from_secs(5), body.next())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            text.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+
+        assert!(text.starts_with("event: resync\n"), "unexpected stream: {}", text);
+        // Every event arrives exactly once, in order, despite the lag
+        let ids: Vec<&str> = text.match_indices("\"id\":\"e").map(|(i, _)| &text[i + 6..i + 9]).collect();
+        let expected: Vec<String> = (0..20).map(|i| format!("e{:02}", i)).collect();
+        assert_eq!(ids, expected);
+    }
+
+    #[tokio::test]
+    async fn test_stats_since_cutoff() {
+        let db = Database::open_in_memory().unwrap();
+        let (router, cookie) = test_router(&db);
+        store_typed_event(&db, "evt-1", "motion");
+        store_typed_event(&db, "evt-2", "ring");
+
+        let (status, body) = send(&router, "GET", "/api/stats", &cookie).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["total_events"], 2);
+
+        // Stored after the router's start time
+        let (_, body) = send(&router, "GET", "/api/stats?since_startup=true", &cookie).await;
+        assert_eq!(body["total_events"], 2);
+
+        let future = chrono::Utc::now().timestamp() + 3600;
+        let (_, body) = send(&router, "GET", &format!("/api/stats?since={}", future), &cookie).await;
+        assert_eq!(body["total_events"], 0);
+        assert_eq!(body["unclassified_types"], 0);
+
+        let uri = format!("/api/stats?since={}&since_startup=true", future);
+        let (status, _) = send(&router, "GET", &uri, &cookie).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_bulk_acknowledge() {
+        let db = Database::open_in_memory().unwrap();
+        let (router, cookie) = test_router(&db);
+        store_typed_event(&db, "evt-1", "motion");
+        store_typed_event(&db, "evt-2", "motion");
+        store_typed_event(&db, "evt-3", "ring");
+
+        let request = serde_json::json!({"filter": {"event_type": "motion"}, "action": "acknowledge"});
+        let (status, body) = send_json(&router, "POST", "/api/events/bulk", &cookie, request.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["affected"], 2);
+
+        // Already acknowledged events aren't counted again
+        let (_, body) = send_json(&router, "POST", "/api/events/bulk", &cookie, request).await;
+        assert_eq!(body["affected"], 0);
+
+        let (_, body) = send(&router, "GET", "/api/events?acknowledged=false", &cookie).await;
+        let events = body.as_array().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0]["id"], "evt-3");
+        assert!(events[0]["acknowledged_at"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_actionable_events() {
+        let db = Database::open_in_memory().unwrap();
+        let (router, cookie) = test_router(&db);
+        db.set_rule("ring", Classification::Notify).unwrap();
+        let now = chrono::Utc::now();
+        for (id, event_type, age_secs) in [
+            ("evt-new", "ring", 10),
+            ("evt-old", "ring", 60),
+            ("evt-acked", "ring", 30),
+            ("evt-notified", "ring", 40),
+            ("evt-motion", "motion", 50),
+        ] {
+            db.store_event(&UnifiEvent {
+                id: id.to_string(),
+                timestamp: now - chrono::Duration::seconds(age_secs),
+                source: EventSource::Protect,
+                event_type: event_type.to_string(),
+                summary: id.to_string(),
+                severity: None,
+                raw: serde_json::json!({}),
+            })
+            .unwrap();
+        }
+        db.mark_notified("evt-notified").unwrap();
+        let request = serde_json::json!({"filter": {"summary_search": "evt-acked"}, "action": "acknowledge"});
+        send_json(&router, "POST", "/api/events/bulk", &cookie, request).await;
+
+        let (status, body) = send(&router, "GET", "/api/events/actionable", &cookie).await;
+        assert_eq!(status, StatusCode::OK);
+        let ids: Vec<&str> = body.as_array().unwrap().iter().map(|e| e["id"].as_str().unwrap()).collect();
+        assert_eq!(ids, ["evt-old", "evt-new"]);
+    }
+
+    #[tokio::test]
+    async fn test_bulk_set_classification() {
+        let db = Database::open_in_memory().unwrap();
+        let (router, cookie) = test_router(&db);
+        store_typed_event(&db, "evt-1", "motion");
+        store_typed_event(&db, "evt-2", "ring");
+
+        let request = serde_json::json!({
+            "filter": {"classification": "unclassified", "event_type": "ring"},
+            "action": "set_classification",
+            "classification": "ignored",
+        });
+        let (status, body) = send_json(&router, "POST", "/api/events/bulk", &cookie, request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["affected"], 1);
+        let (_, body) = send(&router, "GET", "/api/events?classification=ignored", &cookie).await;
+        assert_eq!(body.as_array().unwrap().len(), 1);
+        assert_eq!(body[0]["id"], "evt-2");
+
+        // Reclassifying past events as notify doesn't queue them for delivery
+        let request = serde_json::json!({
+            "filter": {"event_type": "motion"},
+            "action": "set_classification",
+            "classification": "notify",
+        });
+        let (_, body) = send_json(&router, "POST", "/api/events/bulk", &cookie, request).await;
+        assert_eq!(body["affected"], 1);
+        let (_, body) = send(&router, "GET", "/api/events?classification=notify", &cookie).await;
+        assert_eq!(body[0]["notified"], true);
+        assert!(db.get_pending_notifications().unwrap().is_empty());
+
+        // Suppressed events aren't stored, so they can't be bulk-assigned
+        let request = serde_json::json!({
+            "filter": {"event_type": "motion"},
+            "action": "set_classification",
+            "classification": "suppressed",
+        });
+        let (status, _) = send_json(&router, "POST", "/api/events/bulk", &cookie, request).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_bulk_delete_requires_confirm_and_filter() {
+        let db = Database::open_in_memory().unwrap();
+        let (router, cookie) = test_router(&db);
+        store_typed_event(&db, "evt-1", "motion");
+        store_typed_event(&db, "evt-2", "motion");
+        store_typed_event(&db, "evt-3", "ring");
+        db.set_pinned("evt-2", true).unwrap();
+
+        let request = serde_json::json!({"filter": {"event_type": "motion"}, "action": "delete"});
+        let (status, _) = send_json(&router, "POST", "/api/events/bulk", &cookie, request).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // An empty filter is rejected for every action
+        let request = serde_json::json!({"filter": {"search": null}, "action": "delete", "confirm": true});
+        let (status, _) = send_json(&router, "POST", "/api/events/bulk", &cookie, request).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let request = serde_json::json!({"action": "acknowledge"});
+        let (status, _) = send_json(&router, "POST", "/api/events/bulk", &cookie, request).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // Pinned events survive
+        let request = serde_json::json!({"filter": {"event_type": "motion"}, "action": "delete", "confirm": true});
+        let (status, body) = send_json(&router, "POST", "/api/events/bulk", &cookie, request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["affected"], 1);
+        assert_eq!(db.count_events(&EventFilter::default()).unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_backend_test_notification_routing() {
+        use crate::notify::{TelegramNotifier, WebhookNotifier};
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .and(body_partial_json(serde_json::json!({"type": "test"})))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/bot123:abc/sendMessage"))
+            .respond_with(ResponseTemplate::new(400).set_body_string("chat not found"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let db = Database::open_in_memory().unwrap();
+        let notifiers: Vec<Box<dyn Notifier>> = vec![
+            Box::new(TelegramNotifier::new("123:abc".to_string(), vec!["-1001".to_string()]).with_api_base(server.uri())),
+            Box::new(WebhookNotifier::new(format!("{}/hook", server.uri()), None)),
+        ];
+        let (router, cookie, _) = test_router_with_notifiers(&db, notifiers);
+
+        let (status, body) = send(&router, "GET", "/api/notifications/backends", &cookie).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, serde_json::json!([{"id": "telegram"}, {"id": "webhook"}]));
+
+        // Each test reaches only its own backend (the mocks' expectations
+        // are verified when the server drops)
+        let (status, body) = send(&router, "POST", "/api/notifications/test/webhook", &cookie).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["backend"], "webhook");
+        assert_eq!(body["success"], true);
+
+        let (_, body) = send(&router, "POST", "/api/notifications/test/telegram", &cookie).await;
+        assert_eq!(body["backend"], "telegram");
+        assert_eq!(body["success"], false);
+        let error = body["error"].as_str().unwrap();
+        assert_eq!(error, "API error: -1001: 400 Bad Request: chat not found");
+
+        let (status, _) = send(&router, "POST", "/api/notifications/test/email", &cookie).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let log = db.get_notification_history(10).unwrap();
+        assert_eq!(log.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_health_db_detects_read_only_database() {
+        let path = std::env::temp_dir().join(format!("unifi-monitor-test-{}.db", uuid::Uuid::new_v4()));
+        let db = Database::open(&path).unwrap();
+        let (router, cookie) = test_router(&db);
+        let (status, body) = send(&router, "GET", "/api/health/db", &cookie).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "ok");
+        // The scratch write was rolled back
+        assert_eq!(db.get_setting("health_check").unwrap(), None);
+
+        // The liveness probe stays up while the database can't be written
+        let read_only = Database::open_read_only(&path).unwrap();
+        let router = create_router_with_auth(test_state(&read_only, Vec::new()).0, None);
+        let (status, body) = send(&router, "GET", "/api/health/db", "").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["status"], "error");
+        assert_eq!(body["error"], "database is not writable");
+        let (status, _) = send(&router, "GET", "/api/health", "").await;
+        assert_eq!(status, StatusCode::OK);
+
+        drop((db, read_only, router));
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mute_endpoints_toggle_status() {
+        let db = Database::open_in_memory().unwrap();
+        let (router, cookie) = test_router(&db);
+
+        let (status, body) = send(&router, "POST", "/api/notifications/mute", &cookie).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["muted"], true);
+        assert!(db.notifications_muted().unwrap());
+
+        let (_, body) = send(&router, "GET", "/api/notifications/status", &cookie).await;
+        assert_eq!(body["muted"], true);
+
+        let (status, body) = send(&router, "POST", "/api/notifications/unmute", &cookie).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["muted"], false);
+        let (_, body) = send(&router, "GET", "/api/notifications/status", &cookie).await;
+        assert_eq!(body["muted"], false);
+        assert_eq!(body["retries"], serde_json::json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_get_single_event() {
+        let db = Database::open_in_memory().unwrap();
+        store_typed_event(&db, "evt-1", "motion");
+        let (router, cookie) = test_router(&db);
+
+        let (status, body) = send(&router, "GET", "/api/events/evt-1", &cookie).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["id"], "evt-1");
+        assert_eq!(body["event_type"], "motion");
+        assert!(body.get("payload").is_none());
+
+        let (status, _) = send(&router, "GET", "/api/events/missing", &cookie).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = send(&router, "GET", "/api/events/evt-1", "").await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_unmute_through_settings_requeues_held() {
+        let db = Database::open_in_memory().unwrap();
+        db.set_rule("ring", Classification::Notify).unwrap();
+        db.set_notifications_muted(true).unwrap();
+        store_typed_event(&db, "e1", "ring");
+
+        let (mut state, _) = test_state(&db, Vec::new());
+        let (notify_tx, mut notify_rx) = mpsc::channel(10);
+        state.processor = Arc::new(EventProcessor::new(db.clone(), ProcessorConfig::default(), notify_tx));
+        let router = create_router_with_auth(state, None);
+        let cookie = format!("unifi_session={}", db.create_session(1).unwrap());
+
+        let update = serde_json::json!({"notifications_muted": false});
+        let (status, _) = send_json(&router, "PUT", "/api/settings", &cookie, update).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(!db.notifications_muted().unwrap());
+        assert_eq!(notify_rx.try_recv().unwrap().id, "e1");
+    }
+
+    #[tokio::test]
+    async fn test_settings_round_trip() {
+        let db = Database::open_in_memory().unwrap();
+        let (router, cookie) = test_router(&db);
+
+        let (status, body) = send(&router, "GET", "/api/settings", &cookie).await;
+        assert_eq!(status, StatusCode::OK);
+        let muted = body.as_array().unwrap().iter().find(|s| s["key"] == "notifications_muted").unwrap();
+        assert_eq!(muted["value"], serde_json::Value::Null);
+        assert_eq!(muted["scope"], "runtime");
+
+        let update = serde_json::json!({"notifications_muted": true, "mute_mode": "hold", "sse_buffer_size": 500});
+        let (status, body) = send_json(&router, "PUT", "/api/settings", &cookie, update).await;
+        assert_eq!(status, StatusCode::OK);
+        let buffer = body.as_array().unwrap().iter().find(|s| s["key"] == "sse_buffer_size").unwrap();
+        assert_eq!(buffer["value"], 500);
+        assert_eq!(buffer["scope"], "startup");
+        assert!(db.notifications_muted().unwrap());
+        assert_eq!(db.get_setting("mute_mode").unwrap().as_deref(), Some("hold"));
+
+        // An invalid entry rejects the whole update
+        for update in [
+            serde_json::json!({"notifications_muted": false, "mute_mode": "later"}),
+            serde_json::json!({"notifications_muted": false, "no_such_setting": 1}),
+            serde_json::json!({"sse_buffer_size": 0}),
+            serde_json::json!({"sse_buffer_size": 1_000_000_000}),
+        ] {
+            let (status, _) = send_json(&router, "PUT", "/api/settings", &cookie, update).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+        }
+        assert!(db.notifications_muted().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_config_bundle_round_trip() {
+        let source = Database::open_in_memory().unwrap();
+        source.set_rule("motion", Classification::Notify).unwrap();
+        source.set_rule("sta:sync", Classification::Ignored).unwrap();
+        source.set_setting("mute_mode", "hold").unwrap();
+        let (router, cookie) = test_router(&source);
+        let (status, bundle) = send(&router, "GET", "/api/config/export", &cookie).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(bundle["version"], 1);
+        assert_eq!(bundle["settings"], serde_json::json!({"mute_mode": "hold"}));
+
+        let target = Database::open_in_memory().unwrap();
+        target.set_rule("stale", Classification::Notify).unwrap();
+        let (router, cookie) = test_router(&target);
+        let (status, body) = send_json(&router, "POST", "/api/config/import", &cookie, bundle.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["rules"], 2);
+        assert_eq!(target.get_all_rules().unwrap(), source.get_all_rules().unwrap());
+        assert_eq!(target.get_setting("mute_mode").unwrap().as_deref(), Some("hold"));
+
+        // Bad bundles change nothing
+        let mut unknown_field = bundle.clone();
+        unknown_field["events"] = serde_json::json!([]);
+        let mut wrong_version = bundle.clone();
+        wrong_version["version"] = serde_json::json!(2);
+        let mut bad_rule = bundle.clone();
+        bad_rule["rules"] = serde_json::json!([{"event_type": "x", "classification": "loud"}]);
+        for bad in [unknown_field, wrong_version, bad_rule] {
+            let (status, body) = send_json(&router, "POST", "/api/config/import", &cookie, bad).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+        }
+        assert_eq!(target.get_all_rules().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_config_bundle_keeps_rule_tie_order() {
+        // Equally ranked wildcards are tie-broken by creation order, which
+        // must survive an export and import
+        let source = Database::open_in_memory().unwrap();
+        source.set_rule("door.*", Classification::Notify).unwrap();
+        source.set_rule("*.open", Classification::Ignored).unwrap();
+        let (router, cookie) = test_router(&source);
+        let (_, bundle) = send(&router, "GET", "/api/config/export", &cookie).await;
+
+        let target = Database::open_in_memory().unwrap();
+        let (router, cookie) = test_router(&target);
+        let (status, _) = send_json(&router, "POST", "/api/config/import", &cookie, bundle).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(target.get_all_rules().unwrap(), source.get_all_rules().unwrap());
+        let expected = source.matching_rule("door.open").unwrap().unwrap();
+        assert_eq!(expected.event_type, "door.*");
+        assert_eq!(target.matching_rule("door.open").unwrap(), Some(expected));
+    }
+
+    #[tokio::test]
+    async fn test_rule_paths_round_trip_tricky_types() {
+        let db = Database::open_in_memory().unwrap();
+        let (router, cookie) = test_router(&db);
+
+        // Types that collide with static routes, or need percent-encoding
+        let cases = [
+            ("sta:sync", "sta:sync"),
+            ("camera.update", "camera.update"),
+            ("a/b", "a%2Fb"),
+            ("with space", "with%20space"),
+            ("100%", "100%25"),
+            ("ünïcode", "%C3%BCn%C3%AFcode"),
+        ];
+        for (event_type, encoded) in cases {
+            db.set_rule(event_type, Classification::Notify).unwrap();
+            let (status, _) = send(&router, "DELETE", &format!("/api/rules/{}", encoded), &cookie).await;
+            assert_eq!(status, StatusCode::NO_CONTENT, "deleting {:?}", event_type);
+            assert_eq!(db.get_rule(event_type).unwrap(), None);
+        }
+
+        // The body form also handles types shadowed by other rule routes
+        for event_type in ["test", "merge", "a/b", "sta:sync"] {
+            db.set_rule(event_type, Classification::Ignored).unwrap();
+            let request = Request::builder()
+                .method("DELETE")
+                .uri("/api/rules")
+                .header("Cookie", &cookie)
+                .header("Content-Type", "application/json")
+                .body(Body::from(serde_json::json!({"event_type": event_type}).to_string()))
+                .unwrap();
+            let response = router.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::NO_CONTENT, "deleting {:?}", event_type);
+            assert_eq!(db.get_rule(event_type).unwrap(), None);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_events_for_type_with_special_characters() {
+        let db = Database::open_in_memory().unwrap();
+        store_typed_event(&db, "a", "sta:sync");
+        store_typed_event(&db, "b", "sta:sync");
+        store_typed_event(&db, "c", "camera.update");
+        store_typed_event(&db, "d", "backup/nightly done");
+        let (router, cookie) = test_router(&db);
+
+        let (status, body) = send(&router, "GET", "/api/events/types/sta:sync/events?include_count=true", &cookie).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["event_type"], "sta:sync");
+        assert_eq!(body["events"].as_array().unwrap().len(), 2);
+        assert_eq!(body["count"], 2);
+
+        // Percent-encoded colons, slashes and spaces are decoded
+        let (_, body) = send(&router, "GET", "/api/events/types/sta%3Async/events?limit=1", &cookie).await;
+        assert_eq!(body["events"].as_array().unwrap().len(), 1);
+        assert!(body.get("count").is_none());
+
+        let (_, body) = send(&router, "GET", "/api/events/types/backup%2Fnightly%20done/events", &cookie).await;
+        assert_eq!(body["event_type"], "backup/nightly done");
+        assert_eq!(body["events"][0]["id"], "d");
+
+        let (status, _) = send(&router, "GET", "/api/events/types/sta:sync/events", "").await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_injected_event_is_stored_and_classified() {
+        let db = Database::open_in_memory().unwrap();
+        db.set_rule("backup.failed", Classification::Notify).unwrap();
+
+        let (notify_tx, mut notify_rx) = mpsc::channel(10);
+        let processor = EventProcessor::new(db.clone(), ProcessorConfig::default(), notify_tx);
+        let (sse_tx, mut sse_rx) = broadcast::channel(10);
+
+        let req = CreateEventRequest {
+            source: "system".to_string(),
+            event_type: "backup.failed".to_string(),
+            summary: "Nightly backup failed".to_string(),
+            severity: Some("error".to_string()),
+            payload: Some(serde_json::json!({"job": "nightly"})),
+            timestamp: Some(1_700_000_000),
+        };
+        let Json(response) = create_event_impl(&processor, &sse_tx, false, req).await.unwrap();
+        assert_eq!(response.classification, "notify");
+
+        let events = db.query_events(&EventFilter { event_types: vec!["backup.failed".to_string()], ..Default::default() }, 10, 0).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].id, response.id);
+        assert_eq!(events[0].source, EventSource::System);
+        assert_eq!(events[0].severity, Some(Severity::Error));
+        assert_eq!(events[0].timestamp, 1_700_000_000);
+
+        assert_eq!(notify_rx.recv().await.unwrap().id, response.id);
+        assert_eq!(sse_rx.recv().await.unwrap().id, response.id);
+    }
+
+    #[tokio::test]
+    async fn test_injected_event_rejects_invalid_source() {
+        let db = Database::open_in_memory().unwrap();
+        let (notify_tx, _notify_rx) = mpsc::channel(10);
+        let processor = EventProcessor::new(db.clone(), ProcessorConfig::default(), notify_tx);
+        let (sse_tx, _) = broadcast::channel(10);
+
+        let req = CreateEventRequest {
+            source: "custom".to_string(),
+            event_type: "test".to_string(),
+            summary: "Test".to_string(),
+            severity: None,
+            payload: None,
+            timestamp: None,
+        };
+        assert!(matches!(
+            create_event_impl(&processor, &sse_tx, false, req).await,
+            Err(AppError::BadRequest(_))
+        ));
+        assert_eq!(db.get_event_count().unwrap(), 0);
+    }
+}